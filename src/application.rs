@@ -1,12 +1,28 @@
-use std::cell::Cell;
+use std::cell::{Cell, OnceCell};
 
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::{Manager, jiff};
+use ccm::{Calendar, Manager};
 use gettextrs::gettext;
 use gtk::{gio, glib};
+use tracing::warn;
 
+use crate::astronomy::Astronomy;
+use crate::birthdays::Birthdays;
+use crate::caldav::Accounts;
+use crate::calendar_properties::CalendarProperties;
+use crate::calendar_systems::CalendarSystem;
+use crate::cli;
 use crate::config::VERSION;
-use crate::widgets::CalendarManagerWindow;
+use crate::dbus;
+use crate::event_locations::EventLocations;
+use crate::holidays::Holidays;
+use crate::saved_searches::SavedSearches;
+use crate::search::Index;
+use crate::spawn;
+use crate::subscriptions::Subscriptions;
+use crate::time_zones::TimeZones;
+use crate::vdir::Folders;
+use crate::widgets::{CalendarManagerWindow, ImportDialog, PreferencesDialog};
 
 mod imp {
     use super::*;
@@ -21,7 +37,27 @@ mod imp {
         current_month: Cell<i32>,
         #[property(get, set)]
         current_day: Cell<i32>,
+        /// The calendar system whose dates are shown next to the Gregorian ones.
+        #[property(get, set, builder(CalendarSystem::default()))]
+        calendar_system: Cell<CalendarSystem>,
         pub manager: Manager,
+        pub time_zones: OnceCell<TimeZones>,
+        pub event_locations: OnceCell<EventLocations>,
+        pub subscriptions: OnceCell<Subscriptions>,
+        pub caldav_accounts: OnceCell<Accounts>,
+        pub vdir_folders: OnceCell<Folders>,
+        pub saved_searches: OnceCell<SavedSearches>,
+        pub calendar_properties: OnceCell<CalendarProperties>,
+        pub holidays: OnceCell<Holidays>,
+        pub birthdays: OnceCell<Birthdays>,
+        pub astronomy: OnceCell<Astronomy>,
+        pub search_index: OnceCell<Index>,
+        /// Whether the feeds, accounts and folders are synced and the events indexed, which
+        /// waits for the first activation so that commands run from a terminal stay quick.
+        pub background_started: Cell<bool>,
+        /// The exit status of the last command run from a terminal in this process, which
+        /// `run` does not return once the main loop has run.
+        pub command_exit_code: Cell<Option<glib::ExitCode>>,
     }
 
     #[glib::object_subclass]
@@ -38,12 +74,56 @@ mod imp {
             let obj = self.obj();
             obj.setup_gactions();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
+            obj.set_accels_for_action("app.preferences", &["<primary>comma"]);
         }
     }
 
     impl ApplicationImpl for CalendarManagerApplication {
+        fn startup(&self) {
+            self.parent_startup();
+
+            let settings = gio::Settings::new(
+                &self
+                    .obj()
+                    .application_id()
+                    .expect("application ID should be set"),
+            );
+            let time_zones = self.time_zones.get_or_init(|| TimeZones::new(&settings));
+            self.event_locations.get_or_init(EventLocations::new);
+            let today = time_zones.now();
+            let obj = self.obj();
+            obj.set_current_year(today.year() as i32);
+            obj.set_current_month(today.month() as i32);
+            obj.set_current_day(today.day() as i32);
+            settings
+                .bind("calendar-system", &*obj, "calendar-system")
+                .build();
+
+            self.subscriptions
+                .get_or_init(|| Subscriptions::new(&settings));
+            self.caldav_accounts
+                .get_or_init(|| Accounts::new(&settings));
+            self.vdir_folders.get_or_init(|| Folders::new(&settings));
+
+            self.saved_searches
+                .get_or_init(|| SavedSearches::new(&settings));
+            self.calendar_properties
+                .get_or_init(|| CalendarProperties::new(&settings));
+
+            self.holidays.get_or_init(|| Holidays::new(&settings));
+            self.birthdays.get_or_init(|| Birthdays::new(&settings));
+            self.astronomy.get_or_init(|| Astronomy::new(&settings));
+
+            if let (Some(connection), Some(object_path)) =
+                (obj.dbus_connection(), obj.dbus_object_path())
+            {
+                dbus::register(&connection, &object_path);
+            }
+        }
+
         fn activate(&self) {
             let application = self.obj();
+            application.start_background_work();
             // Get the current window or create one if necessary
             let window = application.active_window().unwrap_or_else(|| {
                 let window = CalendarManagerWindow::new(&*application);
@@ -52,6 +132,47 @@ mod imp {
 
             window.present();
         }
+
+        /// Run a command without opening a window, e.g. `calendar-manager list`, or open the
+        /// files given as arguments.
+        fn command_line(&self, command_line: &gio::ApplicationCommandLine) -> glib::ExitCode {
+            let arguments: Vec<String> = command_line
+                .arguments()
+                .iter()
+                .skip(1)
+                .map(|argument| argument.to_string_lossy().into_owned())
+                .collect();
+            if cli::is_command(&arguments) {
+                return cli::run(command_line, &arguments);
+            }
+
+            let files: Vec<gio::File> = arguments
+                .iter()
+                .map(|argument| command_line.create_file_for_arg(argument))
+                .collect();
+            if files.is_empty() {
+                self.activate();
+            } else {
+                self.open(&files, "");
+            }
+            glib::ExitCode::SUCCESS
+        }
+
+        /// Show an import preview for each file, e.g. `calendar-manager invite.ics`.
+        fn open(&self, files: &[gio::File], _hint: &str) {
+            self.activate();
+
+            let window = self
+                .obj()
+                .active_window()
+                .expect("activate should have created a window");
+            let files = files.to_vec();
+            spawn!(async move {
+                for file in files {
+                    ImportDialog::present_for_file(&file, None, &window).await;
+                }
+            });
+        }
     }
 
     impl GtkApplicationImpl for CalendarManagerApplication {}
@@ -66,11 +187,6 @@ glib::wrapper! {
 
 impl CalendarManagerApplication {
     pub fn new(application_id: &str, flags: &gio::ApplicationFlags) -> Self {
-        let now = jiff::Zoned::now();
-        let current_year = now.year();
-        let current_month = now.month();
-        let current_day = now.day();
-
         glib::Object::builder()
             .property("application-id", application_id)
             .property("flags", flags)
@@ -78,16 +194,135 @@ impl CalendarManagerApplication {
                 "resource-base-path",
                 "/io/gitlab/TitouanReal/CalendarManager",
             )
-            .property("current-year", current_year as i32)
-            .property("current-month", current_month as i32)
-            .property("current-day", current_day as i32)
             .build()
     }
 
+    /// Sync the feeds, accounts and folders, and index the events, unless it was already done.
+    fn start_background_work(&self) {
+        let imp = self.imp();
+        if imp.background_started.replace(true) {
+            return;
+        }
+
+        self.subscriptions().refresh_all();
+        self.caldav_accounts().sync_all();
+        self.vdir_folders().sync_all();
+
+        match Index::new() {
+            Ok(index) => {
+                index.watch(&imp.manager);
+                let _ = imp.search_index.set(index);
+            }
+            Err(error) => warn!("Could not create the search index: {error}"),
+        }
+    }
+
+    /// The exit status of the last command run from a terminal in this process, if any.
+    pub fn command_exit_code(&self) -> Option<glib::ExitCode> {
+        self.imp().command_exit_code.get()
+    }
+
+    pub fn set_command_exit_code(&self, exit_code: glib::ExitCode) {
+        self.imp().command_exit_code.set(Some(exit_code));
+    }
+
     pub fn manager(&self) -> Manager {
         self.imp().manager.clone()
     }
 
+    /// Forget what is kept about `calendar` and its events outside of ccm, before deleting it.
+    pub fn forget_calendar(&self, calendar: &Calendar) {
+        self.subscriptions().unsubscribe(calendar);
+        self.calendar_properties().forget(calendar);
+        self.event_locations().forget(calendar);
+        self.time_zones().forget(calendar);
+    }
+
+    pub fn time_zones(&self) -> TimeZones {
+        self.imp()
+            .time_zones
+            .get()
+            .expect("time zones should be initialized on startup")
+            .clone()
+    }
+
+    pub fn event_locations(&self) -> EventLocations {
+        self.imp()
+            .event_locations
+            .get()
+            .expect("event locations should be initialized on startup")
+            .clone()
+    }
+
+    pub fn subscriptions(&self) -> Subscriptions {
+        self.imp()
+            .subscriptions
+            .get()
+            .expect("subscriptions should be initialized on startup")
+            .clone()
+    }
+
+    pub fn caldav_accounts(&self) -> Accounts {
+        self.imp()
+            .caldav_accounts
+            .get()
+            .expect("CalDAV accounts should be initialized on startup")
+            .clone()
+    }
+
+    pub fn vdir_folders(&self) -> Folders {
+        self.imp()
+            .vdir_folders
+            .get()
+            .expect("vdir folders should be initialized on startup")
+            .clone()
+    }
+
+    /// The full-text index of the events, if it could be created.
+    pub fn search_index(&self) -> Option<Index> {
+        self.imp().search_index.get().cloned()
+    }
+
+    pub fn saved_searches(&self) -> SavedSearches {
+        self.imp()
+            .saved_searches
+            .get()
+            .expect("saved searches should be initialized on startup")
+            .clone()
+    }
+
+    pub fn calendar_properties(&self) -> CalendarProperties {
+        self.imp()
+            .calendar_properties
+            .get()
+            .expect("calendar properties should be initialized on startup")
+            .clone()
+    }
+
+    pub fn holidays(&self) -> Holidays {
+        self.imp()
+            .holidays
+            .get()
+            .expect("holidays should be initialized on startup")
+            .clone()
+    }
+
+    pub fn birthdays(&self) -> Birthdays {
+        self.imp()
+            .birthdays
+            .get()
+            .expect("birthdays should be initialized on startup")
+            .clone()
+    }
+
+    pub fn astronomy(&self) -> Astronomy {
+        self.imp()
+            .astronomy
+            .get()
+            .expect("astronomy should be initialized on startup")
+            .clone()
+    }
+
     fn setup_gactions(&self) {
         let quit_action = gio::ActionEntry::builder("quit")
             .activate(move |app: &Self, _, _| app.quit())
@@ -95,7 +330,12 @@ impl CalendarManagerApplication {
         let about_action = gio::ActionEntry::builder("about")
             .activate(move |app: &Self, _, _| app.show_about())
             .build();
-        self.add_action_entries([quit_action, about_action]);
+        let preferences_action = gio::ActionEntry::builder("preferences")
+            .activate(move |app: &Self, _, _| {
+                PreferencesDialog::new().present(app.active_window().as_ref())
+            })
+            .build();
+        self.add_action_entries([quit_action, about_action, preferences_action]);
     }
 
     fn show_about(&self) {
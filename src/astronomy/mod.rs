@@ -0,0 +1,115 @@
+//! The phases of the Moon and the course of the Sun, computed locally for the location set in the
+//! preferences.
+
+use std::{cell::Cell, sync::LazyLock};
+
+use ccm::jiff::civil;
+use gtk::{
+    gio,
+    glib::{self, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+mod moon;
+mod sun;
+
+pub use self::{
+    moon::MoonPhase,
+    sun::{Daylight, SunTimes},
+};
+use crate::{CalendarManagerApplication, time_zones};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Astronomy)]
+    pub struct Astronomy {
+        /// Whether the principal phases of the Moon are shown in the month and year views.
+        #[property(get, set)]
+        show_moon_phases: Cell<bool>,
+        /// Whether the night and twilight hours are shaded in the time grids.
+        #[property(get, set)]
+        show_daylight: Cell<bool>,
+        /// The latitude of the location, in degrees, north being positive.
+        #[property(get, set, minimum = -90.0, maximum = 90.0)]
+        latitude: Cell<f64>,
+        /// The longitude of the location, in degrees, east being positive.
+        #[property(get, set, minimum = -180.0, maximum = 180.0)]
+        longitude: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Astronomy {
+        const NAME: &'static str = "Astronomy";
+        type Type = super::Astronomy;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Astronomy {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("changed").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The astronomical overlays of the views, and the location they are computed for.
+    pub struct Astronomy(ObjectSubclass<imp::Astronomy>);
+}
+
+impl Astronomy {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        settings
+            .bind("show-moon-phases", &obj, "show-moon-phases")
+            .build();
+        settings
+            .bind("show-daylight", &obj, "show-daylight")
+            .build();
+        settings.bind("latitude", &obj, "latitude").build();
+        settings.bind("longitude", &obj, "longitude").build();
+
+        obj.connect_notify_local(None, |obj, _| obj.emit_by_name::<()>("changed", &[]));
+
+        obj
+    }
+
+    /// The principal phase of the Moon on `date` in the display time zone, if there is one and
+    /// the phases are shown.
+    pub fn moon_phase(&self, date: civil::Date) -> Option<MoonPhase> {
+        if !self.show_moon_phases() {
+            return None;
+        }
+        let display = CalendarManagerApplication::default().time_zones().display();
+        let (start, end) = time_zones::day_bounds(date, &display)?;
+        moon::phase_between(start, end)
+    }
+
+    /// The name of the icon of `phase`, as the Moon is seen from the location.
+    pub fn moon_icon_name(&self, phase: MoonPhase) -> &'static str {
+        phase.icon_name(self.latitude() >= 0.)
+    }
+
+    /// The times of the Sun on `date` at the location, if the daylight is shown.
+    pub fn sun_times(&self, date: civil::Date) -> Option<SunTimes> {
+        if !self.show_daylight() {
+            return None;
+        }
+        SunTimes::new(date, self.latitude(), self.longitude())
+    }
+
+    /// Call `f` when the overlays are shown or hidden, or the location changes.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            false,
+            closure_local!(move |obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+}
@@ -0,0 +1,190 @@
+//! The phases of the Moon, from the elongation of the Moon from the Sun.
+//!
+//! The positions come from the orbital elements of Paul Schlyter's “How to compute planetary
+//! positions”, with the main perturbations of the Moon, which are within a few arcminutes and
+//! place the phases within an hour or so.
+
+use ccm::jiff::Timestamp;
+use gettextrs::gettext;
+
+/// The Julian day of the Unix epoch.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+
+/// The Julian day of the epoch of the orbital elements, 1999 December 31 at 0:00.
+const ELEMENTS_EPOCH_JULIAN_DAY: f64 = 2_451_543.5;
+
+/// The principal phases of the Moon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoonPhase {
+    New,
+    FirstQuarter,
+    Full,
+    LastQuarter,
+}
+
+impl MoonPhase {
+    const ALL: [Self; 4] = [Self::New, Self::FirstQuarter, Self::Full, Self::LastQuarter];
+
+    /// The elongation of the Moon from the Sun at the phase, in degrees.
+    fn elongation(self) -> f64 {
+        match self {
+            Self::New => 0.,
+            Self::FirstQuarter => 90.,
+            Self::Full => 180.,
+            Self::LastQuarter => 270.,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::New => gettext("New Moon"),
+            Self::FirstQuarter => gettext("First Quarter"),
+            Self::Full => gettext("Full Moon"),
+            Self::LastQuarter => gettext("Last Quarter"),
+        }
+    }
+
+    /// The name of the icon of the phase, as seen from the northern hemisphere if `northern`, or
+    /// else from the southern hemisphere, where the lit side of the Moon is the other one.
+    pub fn icon_name(self, northern: bool) -> &'static str {
+        match (self, northern) {
+            (Self::New, _) => "moon-new-symbolic",
+            (Self::Full, _) => "moon-full-symbolic",
+            (Self::FirstQuarter, true) | (Self::LastQuarter, false) => "moon-right-half-symbolic",
+            (Self::FirstQuarter, false) | (Self::LastQuarter, true) => "moon-left-half-symbolic",
+        }
+    }
+}
+
+/// The principal phase of the Moon happening from `start` to `end`, excluded, if any.
+///
+/// The Moon takes more than 7 days from one phase to the next, so there is at most one in a day.
+pub fn phase_between(start: Timestamp, end: Timestamp) -> Option<MoonPhase> {
+    let start_elongation = elongation(start);
+    let end_elongation = elongation(end);
+    let travelled = (end_elongation - start_elongation).rem_euclid(360.);
+    MoonPhase::ALL.into_iter().find(|phase| {
+        let since_phase = (end_elongation - phase.elongation()).rem_euclid(360.);
+        // The phase is reached during the day if the Moon went past it since the start of the day
+        0. < since_phase && since_phase <= travelled
+    })
+}
+
+/// The elongation of the Moon from the Sun at `timestamp`, in ecliptic longitude, in degrees from
+/// 0 at the new moon to 360.
+fn elongation(timestamp: Timestamp) -> f64 {
+    let julian_day = timestamp.as_millisecond() as f64 / 86_400_000. + UNIX_EPOCH_JULIAN_DAY;
+    let d = julian_day - ELEMENTS_EPOCH_JULIAN_DAY;
+
+    // The Sun
+    let sun_perihelion = 282.9404 + 4.70935e-5 * d;
+    let sun_eccentricity = 0.016709 - 1.151e-9 * d;
+    let sun_anomaly = (356.0470 + 0.985_600_258_5 * d).rem_euclid(360.);
+    let sun_longitude = true_anomaly(sun_anomaly, sun_eccentricity) + sun_perihelion;
+
+    // The Moon
+    let node = 125.1228 - 0.052_953_808_3 * d;
+    let inclination = 5.1454_f64.to_radians();
+    let moon_perigee = 318.0634 + 0.164_357_322_3 * d;
+    let moon_eccentricity = 0.054900;
+    let moon_anomaly = (115.3654 + 13.064_992_950_9 * d).rem_euclid(360.);
+    let argument = (true_anomaly(moon_anomaly, moon_eccentricity) + moon_perigee).to_radians();
+    let node_radians = node.to_radians();
+    let x = node_radians.cos() * argument.cos()
+        - node_radians.sin() * argument.sin() * inclination.cos();
+    let y = node_radians.sin() * argument.cos()
+        + node_radians.cos() * argument.sin() * inclination.cos();
+    let moon_longitude = y.atan2(x).to_degrees();
+
+    // The main perturbations of the longitude of the Moon
+    let sun_mean_longitude = sun_anomaly + sun_perihelion;
+    let moon_mean_longitude = moon_anomaly + moon_perigee + node;
+    let elongation = (moon_mean_longitude - sun_mean_longitude).to_radians();
+    let latitude_argument = (moon_mean_longitude - node).to_radians();
+    let sun_anomaly = sun_anomaly.to_radians();
+    let moon_anomaly = moon_anomaly.to_radians();
+    let perturbations = -1.274 * (moon_anomaly - 2. * elongation).sin()
+        + 0.658 * (2. * elongation).sin()
+        - 0.186 * sun_anomaly.sin()
+        - 0.059 * (2. * moon_anomaly - 2. * elongation).sin()
+        - 0.057 * (moon_anomaly - 2. * elongation + sun_anomaly).sin()
+        + 0.053 * (moon_anomaly + 2. * elongation).sin()
+        + 0.046 * (2. * elongation - sun_anomaly).sin()
+        + 0.041 * (moon_anomaly - sun_anomaly).sin()
+        - 0.035 * elongation.sin()
+        - 0.031 * (moon_anomaly + sun_anomaly).sin()
+        - 0.015 * (2. * latitude_argument - 2. * elongation).sin()
+        + 0.011 * (moon_anomaly - 4. * elongation).sin();
+
+    (moon_longitude + perturbations - sun_longitude).rem_euclid(360.)
+}
+
+/// The true anomaly of an orbit of `eccentricity` at `mean_anomaly`, in degrees.
+fn true_anomaly(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let mean_anomaly = mean_anomaly.to_radians();
+    // Kepler's equation, solved by Newton's method
+    let mut eccentric_anomaly =
+        mean_anomaly + eccentricity * mean_anomaly.sin() * (1. + eccentricity * mean_anomaly.cos());
+    for _ in 0..5 {
+        eccentric_anomaly -=
+            (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+                / (1. - eccentricity * eccentric_anomaly.cos());
+    }
+    let x = eccentric_anomaly.cos() - eccentricity;
+    let y = (1. - eccentricity * eccentricity).sqrt() * eccentric_anomaly.sin();
+    y.atan2(x).to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::ToSpan;
+
+    use super::*;
+
+    /// The phase found during the UTC day of `date`.
+    fn phase_on(date: &str) -> Option<MoonPhase> {
+        let start: Timestamp = format!("{date}T00:00:00Z").parse().unwrap();
+        phase_between(start, start.checked_add(24.hours()).unwrap())
+    }
+
+    #[test]
+    fn phases_are_found() {
+        // The phases of April 2024, at 18:21, 19:13, 23:49 and 11:27 UTC
+        assert_eq!(phase_on("2024-04-08"), Some(MoonPhase::New));
+        assert_eq!(phase_on("2024-04-15"), Some(MoonPhase::FirstQuarter));
+        assert_eq!(phase_on("2024-04-23"), Some(MoonPhase::Full));
+        assert_eq!(phase_on("2024-05-01"), Some(MoonPhase::LastQuarter));
+        // The lunar eclipse of 7 September 2025, at 18:09 UTC
+        assert_eq!(phase_on("2025-09-07"), Some(MoonPhase::Full));
+
+        assert_eq!(phase_on("2024-04-07"), None);
+        assert_eq!(phase_on("2024-04-09"), None);
+        assert_eq!(phase_on("2024-04-24"), None);
+    }
+
+    #[test]
+    fn phases_are_within_an_hour_or_so() {
+        let full_moon: Timestamp = "2024-04-23T23:49:00Z".parse().unwrap();
+        let before = full_moon.checked_sub(2.hours()).unwrap();
+        let after = full_moon.checked_add(2.hours()).unwrap();
+        assert_eq!(phase_between(before, full_moon), None);
+        assert_eq!(phase_between(before, after), Some(MoonPhase::Full));
+        assert_eq!(
+            phase_between(after, after.checked_add(24.hours()).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn icons_depend_on_the_hemisphere() {
+        assert_eq!(
+            MoonPhase::FirstQuarter.icon_name(true),
+            "moon-right-half-symbolic"
+        );
+        assert_eq!(
+            MoonPhase::FirstQuarter.icon_name(false),
+            "moon-left-half-symbolic"
+        );
+        assert_eq!(MoonPhase::Full.icon_name(false), "moon-full-symbolic");
+    }
+}
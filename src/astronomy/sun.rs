@@ -0,0 +1,180 @@
+//! The times of sunrise, sunset and twilight, from the sunrise equation.
+
+use ccm::jiff::{Timestamp, civil};
+
+/// The Julian day of the Unix epoch.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+
+/// The Julian day of 2000 January 1 at noon.
+const J2000: f64 = 2_451_545.;
+
+/// The altitude of the center of the Sun at sunrise and sunset, in degrees, below the horizon
+/// because of the refraction of the atmosphere and the radius of the Sun.
+const SUNRISE_ALTITUDE: f64 = -0.833;
+
+/// The altitude of the center of the Sun at the start and end of civil twilight, in degrees.
+const CIVIL_TWILIGHT_ALTITUDE: f64 = -6.;
+
+/// The obliquity of the ecliptic, in degrees.
+const OBLIQUITY: f64 = 23.4397;
+
+/// How much daylight there is at some time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Daylight {
+    Night,
+    /// Civil twilight, between dawn and sunrise or between sunset and dusk.
+    Twilight,
+    Day,
+}
+
+/// When the Sun is above some altitude on a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crossing {
+    /// From the first timestamp to the second.
+    Between(Timestamp, Timestamp),
+    /// All day, like in summer near the poles.
+    Always,
+    Never,
+}
+
+impl Crossing {
+    fn contains(self, timestamp: Timestamp) -> bool {
+        match self {
+            Self::Between(start, end) => start <= timestamp && timestamp < end,
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// The course of the Sun on a day at a place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SunTimes {
+    /// From sunrise to sunset.
+    pub day: Crossing,
+    /// From dawn to dusk, when civil twilight starts and ends.
+    pub civil_day: Crossing,
+}
+
+impl SunTimes {
+    /// The course of the Sun on `date` at `latitude` and `longitude`, in degrees, east and north
+    /// being positive.
+    pub fn new(date: civil::Date, latitude: f64, longitude: f64) -> Option<Self> {
+        let days = civil::date(2000, 1, 1).until(date).ok()?.get_days();
+        // The mean solar noon, then the equation of time
+        let mean_noon = f64::from(days) - longitude / 360.;
+        let anomaly = (357.5291 + 0.985_600_28 * mean_noon)
+            .rem_euclid(360.)
+            .to_radians();
+        let center =
+            1.9148 * anomaly.sin() + 0.02 * (2. * anomaly).sin() + 0.0003 * (3. * anomaly).sin();
+        let ecliptic_longitude = (anomaly.to_degrees() + center + 180. + 102.9372)
+            .rem_euclid(360.)
+            .to_radians();
+        let transit =
+            J2000 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2. * ecliptic_longitude).sin();
+        let declination = (ecliptic_longitude.sin() * OBLIQUITY.to_radians().sin()).asin();
+
+        let crossing = |altitude: f64| {
+            let latitude = latitude.to_radians();
+            let cos_hour_angle = (altitude.to_radians().sin() - latitude.sin() * declination.sin())
+                / (latitude.cos() * declination.cos());
+            if cos_hour_angle <= -1. {
+                return Some(Crossing::Always);
+            }
+            if cos_hour_angle >= 1. {
+                return Some(Crossing::Never);
+            }
+            let half_day = cos_hour_angle.acos().to_degrees() / 360.;
+            Some(Crossing::Between(
+                timestamp(transit - half_day)?,
+                timestamp(transit + half_day)?,
+            ))
+        };
+
+        Some(Self {
+            day: crossing(SUNRISE_ALTITUDE)?,
+            civil_day: crossing(CIVIL_TWILIGHT_ALTITUDE)?,
+        })
+    }
+
+    pub fn daylight(&self, timestamp: Timestamp) -> Daylight {
+        if self.day.contains(timestamp) {
+            Daylight::Day
+        } else if self.civil_day.contains(timestamp) {
+            Daylight::Twilight
+        } else {
+            Daylight::Night
+        }
+    }
+}
+
+fn timestamp(julian_day: f64) -> Option<Timestamp> {
+    let milliseconds = (julian_day - UNIX_EPOCH_JULIAN_DAY) * 86_400_000.;
+    Timestamp::from_millisecond(milliseconds.round() as i64).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::ToSpan;
+
+    use super::*;
+
+    fn assert_near(timestamp: Timestamp, expected: &str) {
+        let expected: Timestamp = expected.parse().unwrap();
+        let difference = timestamp.duration_since(expected).abs();
+        assert!(
+            difference.as_secs() <= 3 * 60,
+            "{timestamp} is not near {expected}"
+        );
+    }
+
+    #[test]
+    fn sunrise_and_sunset() {
+        // London on the summer solstice of 2024
+        let times = SunTimes::new(civil::date(2024, 6, 21), 51.5074, -0.1278).unwrap();
+        let Crossing::Between(sunrise, sunset) = times.day else {
+            panic!("the Sun should rise and set in London");
+        };
+        assert_near(sunrise, "2024-06-21T03:43:00Z");
+        assert_near(sunset, "2024-06-21T20:21:00Z");
+
+        let Crossing::Between(dawn, dusk) = times.civil_day else {
+            panic!("civil twilight should start and end in London");
+        };
+        assert!(dawn < sunrise && sunset < dusk);
+
+        assert_eq!(
+            times.daylight("2024-06-21T12:00:00Z".parse().unwrap()),
+            Daylight::Day
+        );
+        assert_eq!(
+            times.daylight(sunset.checked_add(10.minutes()).unwrap()),
+            Daylight::Twilight
+        );
+        assert_eq!(
+            times.daylight(dusk.checked_add(10.minutes()).unwrap()),
+            Daylight::Night
+        );
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        // Tromsø, north of the Arctic Circle
+        let summer = SunTimes::new(civil::date(2024, 6, 21), 69.6492, 18.9553).unwrap();
+        assert_eq!(summer.day, Crossing::Always);
+        assert_eq!(summer.civil_day, Crossing::Always);
+
+        let winter = SunTimes::new(civil::date(2024, 12, 21), 69.6492, 18.9553).unwrap();
+        assert_eq!(winter.day, Crossing::Never);
+        assert!(matches!(winter.civil_day, Crossing::Between(..)));
+        assert_eq!(
+            winter.daylight("2024-12-21T10:30:00Z".parse().unwrap()),
+            Daylight::Twilight
+        );
+        assert_eq!(
+            winter.daylight("2024-12-21T18:00:00Z".parse().unwrap()),
+            Daylight::Night
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Birthdays of the contacts indexed in Tracker, shown as a read-only calendar.
+//!
+//! Contacts are `nco:PersonContact`s with a `nco:birthDate`. They are read again when the
+//! contact store notifies a change. Other dates of the contacts, like anniversaries, are not
+//! read.
+
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    sync::LazyLock,
+};
+
+use ccm::jiff::civil;
+use gettextrs::gettext;
+use gtk::{
+    gio,
+    glib::{self, clone, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+use tracing::{debug, warn};
+
+use crate::spawn;
+
+/// The Tracker endpoint on the session bus where contacts are indexed.
+const CONTACTS_SERVICE: &str = "org.freedesktop.Tracker3.Miner.Files";
+
+const CONTACTS_QUERY: &str = "SELECT COALESCE(?fullname, ?nickname, ?given) ?birthDate
+WHERE {
+  ?contact a nco:PersonContact ;
+    nco:birthDate ?birthDate .
+  OPTIONAL { ?contact nco:fullname ?fullname }
+  OPTIONAL { ?contact nco:nickname ?nickname }
+  OPTIONAL { ?contact nco:nameGiven ?given }
+}";
+
+/// Address books write birth dates without a year with a placeholder year before this one, like
+/// 1604.
+const FIRST_KNOWN_YEAR: i16 = 1900;
+
+/// A contact with a birth date.
+#[derive(Debug, Clone)]
+struct Contact {
+    name: String,
+    birth_date: civil::Date,
+}
+
+/// The birthday of a contact in a given year.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Birthday {
+    pub date: civil::Date,
+    pub name: String,
+    /// The age the contact turns, if their birth year is known.
+    pub age: Option<i16>,
+}
+
+impl Birthday {
+    /// The title shown in the views, e.g. `Ada’s Birthday (36)`.
+    pub fn title(&self) -> String {
+        match self.age {
+            // Translators: The birthday of a contact and the age they turn
+            Some(age) => gettext("{name}’s Birthday ({age})")
+                .replace("{name}", &self.name)
+                .replace("{age}", &age.to_string()),
+            // Translators: The birthday of a contact whose birth year is unknown
+            None => gettext("{name}’s Birthday").replace("{name}", &self.name),
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Birthdays)]
+    pub struct Birthdays {
+        /// Whether the birthdays are shown in the views.
+        #[property(get, set)]
+        visible: Cell<bool>,
+        pub contacts: RefCell<Vec<Contact>>,
+        pub connection: OnceCell<tsparql::SparqlConnection>,
+        pub notifier: OnceCell<tsparql::Notifier>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Birthdays {
+        const NAME: &'static str = "Birthdays";
+        type Type = super::Birthdays;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Birthdays {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("changed").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The birthdays calendar, whose events are the yearly birthdays of the contacts.
+    pub struct Birthdays(ObjectSubclass<imp::Birthdays>);
+}
+
+impl Birthdays {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        settings.bind("show-birthdays", &obj, "visible").build();
+        obj.connect_visible_notify(|obj| obj.emit_by_name::<()>("changed", &[]));
+
+        spawn!(clone!(
+            #[weak]
+            obj,
+            async move { obj.watch_contacts().await }
+        ));
+
+        obj
+    }
+
+    /// The birthdays from `start` to `end`, excluded, by date, if the calendar is shown.
+    pub fn between(&self, start: civil::Date, end: civil::Date) -> Vec<Birthday> {
+        if !self.visible() {
+            return Vec::new();
+        }
+
+        birthdays_between(&self.imp().contacts.borrow(), start, end)
+    }
+
+    /// Call `f` when the birthdays change, or are shown or hidden.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            false,
+            closure_local!(move |obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    /// Connect to the contact store, read the contacts and read them again when they change.
+    async fn watch_contacts(&self) {
+        let connection =
+            match tsparql::SparqlConnection::bus_new_future(CONTACTS_SERVICE, None, None).await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    debug!("No contacts to read birthdays from: {error}");
+                    return;
+                }
+            };
+
+        if let Some(notifier) = connection.create_notifier() {
+            notifier.connect_events(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_, _, _, _| {
+                    spawn!(async move { obj.load().await });
+                }
+            ));
+            let _ = self.imp().notifier.set(notifier);
+        }
+        let _ = self.imp().connection.set(connection);
+
+        self.load().await;
+    }
+
+    async fn load(&self) {
+        let Some(connection) = self.imp().connection.get() else {
+            return;
+        };
+        match query_contacts(connection).await {
+            Ok(contacts) => {
+                self.imp().contacts.replace(contacts);
+                self.emit_by_name::<()>("changed", &[]);
+            }
+            Err(error) => warn!("Could not read the birthdays of the contacts: {error}"),
+        }
+    }
+}
+
+async fn query_contacts(
+    connection: &tsparql::SparqlConnection,
+) -> Result<Vec<Contact>, glib::Error> {
+    let cursor = connection.query_future(CONTACTS_QUERY).await?;
+    let mut contacts = Vec::new();
+    while cursor.next_future().await? {
+        let (Some(name), Some(birth_date)) = (cursor.string(0), cursor.string(1)) else {
+            continue;
+        };
+        // Only the date matters, as written, whatever the time and offset after it
+        let Some(birth_date) = birth_date
+            .get(..10)
+            .and_then(|date| date.parse::<civil::Date>().ok())
+        else {
+            warn!("Ignoring the invalid birth date of {name}: {birth_date}");
+            continue;
+        };
+        contacts.push(Contact {
+            name: name.into(),
+            birth_date,
+        });
+    }
+    Ok(contacts)
+}
+
+/// The birthdays of `contacts` from `start` to `end`, excluded, by date.
+fn birthdays_between(contacts: &[Contact], start: civil::Date, end: civil::Date) -> Vec<Birthday> {
+    let mut birthdays: Vec<Birthday> = (start.year()..=end.year())
+        .flat_map(|year| {
+            contacts.iter().filter_map(move |contact| {
+                let birth_date = contact.birth_date;
+                let first_of_month = civil::Date::new(year, birth_date.month(), 1).ok()?;
+                // Those born on February 29 celebrate on February 28 in common years
+                let day = birth_date.day().min(first_of_month.days_in_month());
+                let date = first_of_month.with().day(day).build().ok()?;
+                let age = (birth_date.year() >= FIRST_KNOWN_YEAR)
+                    .then_some(year - birth_date.year())
+                    .filter(|age| *age > 0);
+                Some(Birthday {
+                    date,
+                    name: contact.name.clone(),
+                    age,
+                })
+            })
+        })
+        .filter(|birthday| start <= birthday.date && birthday.date < end)
+        .collect();
+    birthdays.sort_by_key(|birthday| birthday.date);
+    birthdays
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::civil::date;
+
+    use super::*;
+
+    fn contact(name: &str, birth_date: civil::Date) -> Contact {
+        Contact {
+            name: name.to_owned(),
+            birth_date,
+        }
+    }
+
+    fn birthday(date: civil::Date, name: &str, age: Option<i16>) -> Birthday {
+        Birthday {
+            date,
+            name: name.to_owned(),
+            age,
+        }
+    }
+
+    #[test]
+    fn leap_day_birthdays_fall_back_in_common_years() {
+        let contacts = [contact("Leap", date(2000, 2, 29))];
+
+        assert_eq!(
+            birthdays_between(&contacts, date(2025, 1, 1), date(2026, 1, 1)),
+            [birthday(date(2025, 2, 28), "Leap", Some(25))]
+        );
+        assert_eq!(
+            birthdays_between(&contacts, date(2028, 1, 1), date(2029, 1, 1)),
+            [birthday(date(2028, 2, 29), "Leap", Some(28))]
+        );
+    }
+
+    #[test]
+    fn placeholder_years_have_no_age() {
+        let contacts = [
+            contact("Unknown", date(1604, 5, 12)),
+            contact("Known", date(1990, 5, 12)),
+            // Born this year, not turning 0
+            contact("Newborn", date(2026, 5, 1)),
+        ];
+
+        assert_eq!(
+            birthdays_between(&contacts, date(2026, 5, 1), date(2026, 6, 1)),
+            [
+                birthday(date(2026, 5, 1), "Newborn", None),
+                birthday(date(2026, 5, 12), "Unknown", None),
+                birthday(date(2026, 5, 12), "Known", Some(36)),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_cross_year_boundaries() {
+        let contacts = [
+            contact("December", date(1980, 12, 31)),
+            contact("January", date(1985, 1, 1)),
+            contact("June", date(1985, 6, 1)),
+        ];
+
+        assert_eq!(
+            birthdays_between(&contacts, date(2026, 12, 15), date(2027, 1, 15)),
+            [
+                birthday(date(2026, 12, 31), "December", Some(46)),
+                birthday(date(2027, 1, 1), "January", Some(42)),
+            ]
+        );
+        // The end is excluded
+        assert!(birthdays_between(&contacts, date(2026, 12, 15), date(2026, 12, 31)).is_empty());
+    }
+}
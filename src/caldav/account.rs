@@ -0,0 +1,69 @@
+use std::cell::RefCell;
+
+use gtk::{glib, prelude::*, subclass::prelude::*};
+
+use crate::mirror::{Source, SourceImpl};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Account)]
+    pub struct Account {
+        #[property(get, set, construct_only)]
+        server_url: RefCell<String>,
+        #[property(get, set, construct_only)]
+        username: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Account {
+        const NAME: &'static str = "CalDavAccount";
+        type Type = super::Account;
+        type ParentType = Source;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Account {}
+    impl SourceImpl for Account {}
+}
+
+glib::wrapper! {
+    /// A CalDAV account whose calendars are synced with a local collection.
+    pub struct Account(ObjectSubclass<imp::Account>)
+        @extends Source;
+}
+
+impl Account {
+    pub fn new(id: &str, server_url: &str, username: &str, collection_uri: &str) -> Self {
+        glib::Object::builder()
+            .property("id", id)
+            .property("server-url", server_url)
+            .property("username", username)
+            .property("collection-uri", collection_uri)
+            .build()
+    }
+
+    /// Restore an account saved with [`Account::to_variant`].
+    pub fn from_variant(variant: &glib::Variant) -> Option<Self> {
+        let dict = glib::VariantDict::new(Some(variant));
+        let lookup_string = |key: &str| dict.lookup::<String>(key).ok().flatten();
+
+        let account = Self::new(
+            &lookup_string("id")?,
+            &lookup_string("server-url")?,
+            &lookup_string("username")?,
+            &lookup_string("collection-uri")?,
+        );
+        account.upcast_ref::<Source>().restore_status(&dict);
+        Some(account)
+    }
+
+    pub fn to_variant(&self) -> glib::Variant {
+        let dict = glib::VariantDict::new(None);
+        self.upcast_ref::<Source>().save_status(&dict);
+        dict.insert("server-url", self.server_url());
+        dict.insert("username", self.username());
+        dict.end()
+    }
+}
@@ -0,0 +1,696 @@
+use std::fmt;
+
+use gtk::glib;
+use soup::prelude::*;
+
+use super::xml::{self, MultiStatus};
+use crate::mirror::{Changes, Precondition, RemoteCalendar, RemoteResource, Store};
+
+#[derive(Debug)]
+pub enum Error {
+    Network(glib::Error),
+    InvalidUrl(String),
+    /// The server answered with an unexpected status code.
+    Http(u32, String),
+    InvalidResponse(String),
+    /// The resource changed on the server since it was last fetched.
+    PreconditionFailed,
+    /// The sync token is not valid anymore, a full listing is needed.
+    InvalidSyncToken,
+    NoCalendarHome,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(error) => write!(f, "{error}"),
+            Self::InvalidUrl(url) => write!(f, "Invalid URL: {url}"),
+            Self::Http(status, reason) => write!(f, "{status} {reason}"),
+            Self::InvalidResponse(error) => write!(f, "Invalid response from the server: {error}"),
+            Self::PreconditionFailed => write!(f, "The resource was modified on the server"),
+            Self::InvalidSyncToken => write!(f, "The sync token is not valid anymore"),
+            Self::NoCalendarHome => write!(f, "The server does not provide calendars"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<glib::Error> for Error {
+    fn from(error: glib::Error) -> Self {
+        Self::Network(error)
+    }
+}
+
+/// A CalDAV client authenticating with HTTP basic authentication.
+#[derive(Debug)]
+pub struct Client {
+    session: soup::Session,
+    server_url: glib::Uri,
+    authorization: String,
+}
+
+impl Client {
+    pub fn new(server_url: &str, username: &str, password: &str) -> Result<Self, Error> {
+        let server_url = glib::Uri::parse(server_url, glib::UriFlags::NONE)?;
+        let credentials = glib::base64_encode(format!("{username}:{password}").as_bytes());
+
+        Ok(Self {
+            session: soup::Session::new(),
+            server_url,
+            authorization: format!("Basic {credentials}"),
+        })
+    }
+
+    /// Find the calendars of the user that can contain events.
+    ///
+    /// The server URL can point to the principal, the calendar home or just the server, in
+    /// which case `/.well-known/caldav` is used.
+    pub async fn discover(&self) -> Result<Vec<RemoteCalendar>, Error> {
+        let home = self.calendar_home().await?;
+        let calendars = self
+            .propfind(&home, "1", xml::CALENDARS)
+            .await?
+            .responses
+            .into_iter()
+            .filter(|response| response.is_calendar && response.supports_events)
+            .map(|response| RemoteCalendar {
+                name: response
+                    .display_name
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| last_segment(&response.href)),
+                href: response.href,
+                color: response.color,
+            })
+            .collect();
+
+        Ok(calendars)
+    }
+
+    /// The collection holding the calendars of the user.
+    async fn calendar_home(&self) -> Result<String, Error> {
+        let server_url = self.server_url.to_str();
+        let principal = match self.current_user_principal(&server_url).await {
+            Ok(Some(principal)) => principal,
+            Ok(None) | Err(Error::Http(..)) => {
+                let well_known = self.resolve("/.well-known/caldav")?;
+                self.current_user_principal(&well_known)
+                    .await?
+                    .unwrap_or_else(|| server_url.to_string())
+            }
+            Err(error) => return Err(error),
+        };
+
+        self.propfind(&principal, "0", xml::CALENDAR_HOME_SET)
+            .await?
+            .responses
+            .into_iter()
+            .find_map(|response| response.calendar_home_set)
+            .ok_or(Error::NoCalendarHome)
+    }
+
+    /// The changes since `sync_token` with the sync-collection REPORT of RFC 6578, which must be
+    /// sent with `Depth: 0`.
+    async fn sync_collection(&self, href: &str, sync_token: &str) -> Result<Changes, Error> {
+        let multistatus = self
+            .request(
+                "REPORT",
+                href,
+                &[("Depth", "0")],
+                Some(xml::sync_collection(sync_token)),
+            )
+            .await
+            .map_err(|error| match error {
+                // RFC 6578 uses 403 with a valid-sync-token precondition, some servers use 409
+                Error::Http(403 | 409, _) => Error::InvalidSyncToken,
+                error => error,
+            })
+            .and_then(|(_, body)| parse_multistatus(&body))?;
+
+        let mut changes = Changes {
+            sync_token: multistatus.sync_token,
+            complete: sync_token.is_empty(),
+            ..Changes::default()
+        };
+        for response in multistatus.responses {
+            if is_same_path(&response.href, href) {
+                continue;
+            }
+            match (response.status, response.etag) {
+                (Some(404), _) => changes.removed.push(response.href),
+                (_, Some(etag)) => changes.changed.push((response.href, etag)),
+                _ => (),
+            }
+        }
+        Ok(changes)
+    }
+
+    async fn current_user_principal(&self, url: &str) -> Result<Option<String>, Error> {
+        Ok(self
+            .propfind(url, "0", xml::CURRENT_USER_PRINCIPAL)
+            .await?
+            .responses
+            .into_iter()
+            .find_map(|response| response.current_user_principal))
+    }
+
+    async fn propfind(&self, href: &str, depth: &str, body: &str) -> Result<MultiStatus, Error> {
+        let (_, body) = self
+            .request(
+                "PROPFIND",
+                href,
+                &[("Depth", depth)],
+                Some(body.to_string()),
+            )
+            .await?;
+        parse_multistatus(&body)
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        href: &str,
+        headers: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<(soup::Message, String), Error> {
+        let message = self.message(method, href, headers)?;
+        if let Some(body) = body {
+            message.set_request_body_from_bytes(
+                Some("application/xml; charset=utf-8"),
+                Some(&glib::Bytes::from_owned(body.into_bytes())),
+            );
+        }
+
+        let body = self.send(&message).await?;
+        Ok((message, body))
+    }
+
+    fn message(
+        &self,
+        method: &str,
+        href: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<soup::Message, Error> {
+        let url = self.resolve(href)?;
+        let message =
+            soup::Message::new(method, &url).map_err(|_| Error::InvalidUrl(url.clone()))?;
+        if let Some(request_headers) = message.request_headers() {
+            request_headers.append("Authorization", &self.authorization);
+            for (name, value) in headers {
+                request_headers.append(name, value);
+            }
+        }
+        Ok(message)
+    }
+
+    async fn send(&self, message: &soup::Message) -> Result<String, Error> {
+        let body = self
+            .session
+            .send_and_read_future(message, glib::Priority::DEFAULT)
+            .await?;
+
+        let status = message.status_code();
+        match status {
+            200..=299 => Ok(String::from_utf8_lossy(&body).into_owned()),
+            412 => Err(Error::PreconditionFailed),
+            _ => Err(Error::Http(
+                status,
+                message.reason_phrase().unwrap_or_default().to_string(),
+            )),
+        }
+    }
+
+    /// Resolve `href`, which is usually an absolute path, against the server URL.
+    fn resolve(&self, href: &str) -> Result<String, Error> {
+        Ok(self
+            .server_url
+            .parse_relative(href, glib::UriFlags::NONE)?
+            .to_str()
+            .to_string())
+    }
+}
+
+impl Store for Client {
+    type Error = Error;
+
+    async fn calendars(&self) -> Result<Vec<RemoteCalendar>, Error> {
+        self.discover().await
+    }
+
+    /// The calendar is created in the calendar home with MKCALENDAR (RFC 4791), under a random
+    /// name. Servers refusing it fail the sync, so that the user sees why the calendar is
+    /// missing.
+    async fn create_calendar(
+        &self,
+        name: &str,
+        color: &str,
+    ) -> Result<Option<RemoteCalendar>, Error> {
+        let home = self.calendar_home().await?;
+        let id = glib::uuid_string_random();
+        let href = format!("{}/{id}/", home.trim_end_matches('/'));
+        self.request("MKCALENDAR", &href, &[], Some(xml::mkcalendar(name, color)))
+            .await?;
+
+        // Use the href the server lists the calendar with, to recognize it at the next sync
+        let created = self
+            .discover()
+            .await?
+            .into_iter()
+            .find(|calendar| last_segment(&calendar.href) == id.as_str());
+        Ok(Some(created.unwrap_or_else(|| RemoteCalendar {
+            href,
+            name: name.to_owned(),
+            color: Some(color.to_owned()),
+        })))
+    }
+
+    /// Servers without sync tokens, or an expired token, fall back to a complete listing of the
+    /// ETags.
+    async fn changes(&self, href: &str, sync_token: &str) -> Result<Changes, Error> {
+        match self.sync_collection(href, sync_token).await {
+            Ok(changes) => return Ok(changes),
+            Err(Error::InvalidSyncToken) if !sync_token.is_empty() => {
+                return Box::pin(Store::changes(self, href, "")).await;
+            }
+            Err(Error::Http(..) | Error::InvalidSyncToken) => (),
+            Err(error) => return Err(error),
+        }
+
+        let changed = self
+            .propfind(href, "1", xml::ETAGS)
+            .await?
+            .responses
+            .into_iter()
+            .filter(|response| !is_same_path(&response.href, href))
+            .filter_map(|response| Some((response.href, response.etag?)))
+            .collect();
+
+        Ok(Changes {
+            changed,
+            complete: true,
+            ..Changes::default()
+        })
+    }
+
+    async fn fetch(&self, href: &str, hrefs: &[String]) -> Result<Vec<RemoteResource>, Error> {
+        if hrefs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = xml::calendar_multiget(hrefs.iter().map(String::as_str));
+        let (_, body) = self
+            .request("REPORT", href, &[("Depth", "1")], Some(body))
+            .await?;
+        Ok(parse_multistatus(&body)?
+            .responses
+            .into_iter()
+            .filter_map(|response| {
+                Some(RemoteResource {
+                    data: response.calendar_data?,
+                    href: response.href,
+                    etag: response.etag,
+                })
+            })
+            .collect())
+    }
+
+    async fn put(
+        &self,
+        href: &str,
+        precondition: Precondition<'_>,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let condition = match precondition {
+            Precondition::Absent => Some(("If-None-Match", "*")),
+            Precondition::Matches(etag) => Some(("If-Match", etag)),
+            Precondition::Any => None,
+        };
+        let message = self.message("PUT", href, condition.as_slice())?;
+        message.set_request_body_from_bytes(
+            Some("text/calendar; charset=utf-8"),
+            Some(&glib::Bytes::from_owned(data.into_bytes())),
+        );
+
+        self.send(&message).await?;
+        Ok(message
+            .response_headers()
+            .and_then(|headers| headers.one("ETag"))
+            .map(String::from))
+    }
+
+    /// A resource already removed from the server is not an error.
+    async fn delete(&self, href: &str, etag: Option<&str>) -> Result<(), Error> {
+        let condition = etag.map(|etag| ("If-Match", etag));
+        match self
+            .request("DELETE", href, condition.as_slice(), None)
+            .await
+        {
+            Ok(_) | Err(Error::Http(404, _)) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn resource_href(&self, href: &str, uid: &str) -> String {
+        let name: String = uid
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "-_.@".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}/{name}.ics", href.trim_end_matches('/'))
+    }
+
+    fn is_conflict(error: &Error) -> bool {
+        matches!(error, Error::PreconditionFailed)
+    }
+}
+
+fn parse_multistatus(body: &str) -> Result<MultiStatus, Error> {
+    xml::parse_multistatus(body).map_err(|error| Error::InvalidResponse(error.to_string()))
+}
+
+fn is_same_path(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+fn last_segment(href: &str) -> String {
+    href.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(href)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use gtk::glib::clone;
+
+    use super::*;
+
+    /// A request received by the test server, with its `If-Match` and `If-None-Match` headers.
+    #[derive(Debug, PartialEq)]
+    struct Request {
+        method: String,
+        path: String,
+        if_match: Option<String>,
+        if_none_match: Option<String>,
+    }
+
+    /// The changes of `/calendars/work/` since the sync token `1`.
+    const SYNC_CHANGES: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/calendars/work/changed.ics</d:href>
+    <d:propstat>
+      <d:prop><d:getetag>"2"</d:getetag></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/calendars/work/removed.ics</d:href>
+    <d:status>HTTP/1.1 404 Not Found</d:status>
+  </d:response>
+  <d:sync-token>2</d:sync-token>
+</d:multistatus>"#;
+
+    /// The calendars of the test server, by path, with their name.
+    type Calendars = Rc<RefCell<Vec<(String, String)>>>;
+
+    const PRINCIPAL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/principal/</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:current-user-principal><d:href>/principal/</d:href></d:current-user-principal>
+        <c:calendar-home-set><d:href>/calendars/</d:href></c:calendar-home-set>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+    fn calendars_response(calendars: &[(String, String)]) -> String {
+        let responses: String = calendars
+            .iter()
+            .map(|(path, name)| {
+                format!(
+                    r#"
+  <d:response>
+    <d:href>{path}</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype><d:collection/><c:calendar/></d:resourcetype>
+        <d:displayname>{name}</d:displayname>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>"#
+                )
+            })
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">{responses}
+</d:multistatus>"#
+        )
+    }
+
+    /// Serve a CalDAV server on a local port, recording the requests it receives.
+    ///
+    /// Resources whose name starts with `taken` already exist, and the ETag `"stale"` is never
+    /// the current one. Like SabreDAV, sync-collection reports with another depth than 0 are
+    /// refused.
+    fn serve() -> (soup::Server, Client, Rc<RefCell<Vec<Request>>>, Calendars) {
+        let server = glib::Object::new::<soup::Server>();
+        let requests = Rc::new(RefCell::new(Vec::new()));
+        let calendars: Calendars = Rc::default();
+
+        server.add_handler(
+            None,
+            clone!(
+                #[strong]
+                requests,
+                #[strong]
+                calendars,
+                move |_, message, path, _| {
+                    let method = message.method().unwrap_or_default().to_string();
+                    let header = |name: &str| {
+                        message
+                            .request_headers()
+                            .and_then(|headers| headers.one(name))
+                            .map(String::from)
+                    };
+                    let request = Request {
+                        method: method.clone(),
+                        path: path.to_owned(),
+                        if_match: header("If-Match"),
+                        if_none_match: header("If-None-Match"),
+                    };
+
+                    let body = message
+                        .request_body()
+                        .and_then(|body| body.flatten())
+                        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                        .unwrap_or_default();
+                    let is_sync = body.contains("sync-collection");
+
+                    let status = match (method.as_str(), path) {
+                        ("PROPFIND", "/calendars/") => {
+                            let body = calendars_response(&calendars.borrow());
+                            message.set_response(
+                                Some("application/xml"),
+                                soup::MemoryUse::Copy,
+                                body.as_bytes(),
+                            );
+                            207
+                        }
+                        ("PROPFIND", _) => {
+                            message.set_response(
+                                Some("application/xml"),
+                                soup::MemoryUse::Copy,
+                                PRINCIPAL.as_bytes(),
+                            );
+                            207
+                        }
+                        ("MKCALENDAR", _) => {
+                            let name = body
+                                .split_once("<d:displayname>")
+                                .and_then(|(_, rest)| rest.split_once("</d:displayname>"))
+                                .map(|(name, _)| name.to_owned())
+                                .unwrap_or_default();
+                            calendars.borrow_mut().push((path.to_owned(), name));
+                            201
+                        }
+                        ("PUT", _) => {
+                            let exists = path.rsplit('/').next().unwrap_or_default();
+                            if (request.if_none_match.is_some() && exists.starts_with("taken"))
+                                || request.if_match.as_deref() == Some("\"stale\"")
+                            {
+                                412
+                            } else {
+                                if let Some(headers) = message.response_headers() {
+                                    headers.replace("ETag", "\"new\"");
+                                }
+                                201
+                            }
+                        }
+                        ("DELETE", _) => 204,
+                        ("REPORT", _) if is_sync && header("Depth").as_deref() != Some("0") => 400,
+                        ("REPORT", "/calendars/work/") if is_sync => {
+                            message.set_response(
+                                Some("application/xml"),
+                                soup::MemoryUse::Copy,
+                                SYNC_CHANGES.as_bytes(),
+                            );
+                            207
+                        }
+                        _ => 405,
+                    };
+                    message.set_status(status, None);
+                    requests.borrow_mut().push(request);
+                }
+            ),
+        );
+        server
+            .listen_local(0, soup::ServerListenOptions::IPV4_ONLY)
+            .expect("the server should listen on a local port");
+        let url = server
+            .uris()
+            .into_iter()
+            .next()
+            .expect("the server should have an address");
+        let client =
+            Client::new(&url.to_str(), "user", "password").expect("the server URL should be valid");
+        (server, client, requests, calendars)
+    }
+
+    fn request(
+        method: &str,
+        path: &str,
+        if_match: Option<&str>,
+        if_none_match: Option<&str>,
+    ) -> Request {
+        Request {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            if_match: if_match.map(String::from),
+            if_none_match: if_none_match.map(String::from),
+        }
+    }
+
+    #[test]
+    fn preconditions_are_sent() {
+        let (_server, client, requests, _) = serve();
+        let data = || "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n".to_owned();
+
+        glib::MainContext::default().block_on(async {
+            let etag = client
+                .put("/calendars/work/new.ics", Precondition::Absent, data())
+                .await
+                .expect("new resource should be created");
+            assert_eq!(etag.as_deref(), Some("\"new\""));
+            client
+                .put(
+                    "/calendars/work/a.ics",
+                    Precondition::Matches("\"1\""),
+                    data(),
+                )
+                .await
+                .expect("resource should be replaced");
+            client
+                .put("/calendars/work/b.ics", Precondition::Any, data())
+                .await
+                .expect("resource should be replaced");
+            client
+                .delete("/calendars/work/a.ics", Some("\"2\""))
+                .await
+                .expect("resource should be removed");
+            client
+                .delete("/calendars/work/b.ics", None)
+                .await
+                .expect("resource should be removed");
+
+            let error = client
+                .put("/calendars/work/taken.ics", Precondition::Absent, data())
+                .await
+                .expect_err("existing resource should not be replaced");
+            assert!(Client::is_conflict(&error));
+            let error = client
+                .put(
+                    "/calendars/work/a.ics",
+                    Precondition::Matches("\"stale\""),
+                    data(),
+                )
+                .await
+                .expect_err("modified resource should not be replaced");
+            assert!(Client::is_conflict(&error));
+        });
+
+        assert_eq!(
+            requests.borrow()[..5],
+            [
+                request("PUT", "/calendars/work/new.ics", None, Some("*")),
+                request("PUT", "/calendars/work/a.ics", Some("\"1\""), None),
+                request("PUT", "/calendars/work/b.ics", None, None),
+                request("DELETE", "/calendars/work/a.ics", Some("\"2\""), None),
+                request("DELETE", "/calendars/work/b.ics", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendars_are_created() {
+        let (_server, client, requests, calendars) = serve();
+
+        let created = glib::MainContext::default()
+            .block_on(client.create_calendar("Work & play", "#3584e4"))
+            .expect("calendar should be created")
+            .expect("the server should support creating calendars");
+
+        let (path, name) = calendars.borrow()[0].clone();
+        assert_eq!(created.href, path);
+        assert!(path.starts_with("/calendars/") && path.ends_with('/'));
+        assert_eq!(name, "Work &amp; play");
+        assert!(
+            requests
+                .borrow()
+                .iter()
+                .any(|request| request.method == "MKCALENDAR" && request.path == path)
+        );
+
+        let discovered = glib::MainContext::default()
+            .block_on(client.discover())
+            .expect("calendars should be listed");
+        assert_eq!(discovered.len(), 1);
+        assert_eq!(discovered[0].name, "Work & play");
+    }
+
+    #[test]
+    fn changes_use_sync_tokens() {
+        let (_server, client, requests, _) = serve();
+
+        let changes = glib::MainContext::default()
+            .block_on(client.changes("/calendars/work/", "1"))
+            .expect("changes should be listed");
+        assert_eq!(
+            changes.changed,
+            [("/calendars/work/changed.ics".to_owned(), "\"2\"".to_owned())]
+        );
+        assert_eq!(changes.removed, ["/calendars/work/removed.ics"]);
+        assert_eq!(changes.sync_token.as_deref(), Some("2"));
+        assert!(!changes.complete);
+
+        // No fallback to a complete listing
+        assert_eq!(
+            requests.borrow()[..],
+            [request("REPORT", "/calendars/work/", None, None)]
+        );
+    }
+}
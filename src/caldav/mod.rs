@@ -0,0 +1,262 @@
+//! CalDAV accounts, synced both ways with local collections.
+
+use std::{cell::OnceCell, collections::HashMap};
+
+use ccm::Collection;
+use gettextrs::gettext;
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use tracing::warn;
+
+mod account;
+mod client;
+mod xml;
+
+use crate::{
+    CalendarManagerApplication,
+    mirror::{self, Source, SyncState},
+    spawn,
+};
+
+pub use self::account::Account;
+use self::client::Client;
+
+/// The number of minutes between two syncs of an account.
+const SYNC_INTERVAL: u32 = 15;
+
+/// The attribute identifying the passwords of the accounts in the Secret Service.
+const SECRET_ATTRIBUTE: &str = "calendar-manager-caldav-account";
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Accounts {
+        pub settings: OnceCell<gio::Settings>,
+        pub model: OnceCell<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Accounts {
+        const NAME: &'static str = "CalDavAccounts";
+        type Type = super::Accounts;
+    }
+
+    impl ObjectImpl for Accounts {}
+}
+
+glib::wrapper! {
+    /// The CalDAV accounts of the user, saved in the settings and synced periodically.
+    pub struct Accounts(ObjectSubclass<imp::Accounts>);
+}
+
+impl Accounts {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+        imp.settings
+            .set(settings.clone())
+            .expect("settings should only be set once");
+
+        let model = gio::ListStore::new::<Account>();
+        for variant in settings.value("caldav-accounts").iter() {
+            match Account::from_variant(&variant) {
+                Some(account) => model.append(&account),
+                None => warn!("Ignoring invalid CalDAV account: {variant}"),
+            }
+        }
+        imp.model.set(model).expect("model should only be set once");
+
+        obj
+    }
+
+    pub fn model(&self) -> gio::ListStore {
+        self.imp()
+            .model
+            .get()
+            .expect("model should be initialized")
+            .clone()
+    }
+
+    /// The account synced with `collection`, if any.
+    pub fn find(&self, collection: &Collection) -> Option<Account> {
+        let collection_uri = collection.uri();
+        self.model()
+            .iter::<Account>()
+            .filter_map(Result::ok)
+            .find(|account| account.upcast_ref::<Source>().collection_uri() == collection_uri)
+    }
+
+    /// Check that the server can be reached with these credentials, then create a collection
+    /// for the account and sync it.
+    pub async fn add(
+        &self,
+        server_url: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<Account, String> {
+        let client =
+            Client::new(server_url, username, password).map_err(|error| error.to_string())?;
+        client.discover().await.map_err(|error| error.to_string())?;
+
+        let id = glib::uuid_string_random().to_string();
+        let host = glib::Uri::parse(server_url, glib::UriFlags::NONE)
+            .ok()
+            .and_then(|uri| uri.host())
+            .unwrap_or_default();
+        let name = format!("{username}@{host}");
+        store_password(&id, &name, password).await?;
+
+        let manager = CalendarManagerApplication::default().manager();
+        let collection = manager.create_collection(&name);
+        let account = Account::new(&id, server_url, username, &collection.uri());
+
+        self.model().append(&account);
+        self.save();
+        self.sync(&account);
+        Ok(account)
+    }
+
+    /// Stop syncing `account` and forget it with its password, leaving the server untouched.
+    pub fn remove(&self, account: &Account) {
+        let source = account.upcast_ref::<Source>();
+        source.unschedule();
+
+        let model = self.model();
+        if let Some(position) = model.find(account) {
+            model.remove(position);
+        }
+        self.save();
+        SyncState::delete(&source.id());
+
+        let id = source.id();
+        spawn!(async move {
+            if let Err(error) = delete_password(&id).await {
+                warn!("Could not delete the password of the CalDAV account {id}: {error}");
+            }
+        });
+    }
+
+    /// Sync all accounts, e.g. when the application starts.
+    pub fn sync_all(&self) {
+        for account in self.model().iter::<Account>().filter_map(Result::ok) {
+            self.sync(&account);
+        }
+    }
+
+    /// Sync `account` now, then schedule the next sync.
+    pub fn sync(&self, account: &Account) {
+        spawn!(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[strong]
+            account,
+            async move {
+                let source = account.upcast_ref::<Source>();
+                source.record_sync(sync_account(&account)).await;
+                // The account may have been removed during the sync
+                if obj.model().find(&account).is_none() {
+                    return;
+                }
+                obj.save();
+                source.schedule(
+                    SYNC_INTERVAL * 60,
+                    clone!(
+                        #[weak]
+                        obj,
+                        #[weak]
+                        account,
+                        move |_| obj.sync(&account)
+                    ),
+                );
+            }
+        ));
+    }
+
+    fn save(&self) {
+        let accounts = self
+            .model()
+            .iter::<Account>()
+            .filter_map(Result::ok)
+            .map(|account| account.to_variant())
+            .collect::<Vec<_>>();
+        let value = glib::Variant::array_from_iter_with_type(glib::VariantTy::VARDICT, accounts);
+
+        let settings = self
+            .imp()
+            .settings
+            .get()
+            .expect("settings should be initialized");
+        if let Err(error) = settings.set_value("caldav-accounts", &value) {
+            warn!("Could not save CalDAV accounts: {error}");
+        }
+    }
+}
+
+async fn sync_account(account: &Account) -> Result<usize, String> {
+    let source = account.upcast_ref::<Source>();
+    let manager = CalendarManagerApplication::default().manager();
+    let Some(ccm::Resource::Collection(collection)) =
+        manager.find_resource(&source.collection_uri())
+    else {
+        return Err(gettext("The collection does not exist anymore"));
+    };
+
+    let password = password(&source.id()).await?;
+    let client = Client::new(&account.server_url(), &account.username(), &password)
+        .map_err(|error| error.to_string())?;
+
+    let mut state = SyncState::load(&source.id());
+    let result = mirror::sync(&client, &collection, &mut state).await;
+    // Whatever was done before an error is kept
+    state.save(&source.id());
+    result.map_err(|error| error.to_string())
+}
+
+fn secret_attributes(account_id: &str) -> HashMap<&str, &str> {
+    HashMap::from([(SECRET_ATTRIBUTE, account_id)])
+}
+
+async fn store_password(account_id: &str, label: &str, password: &str) -> Result<(), String> {
+    let keyring = oo7::Keyring::new()
+        .await
+        .map_err(|error| error.to_string())?;
+    keyring
+        .create_item(
+            &format!("CalDAV {label}"),
+            &secret_attributes(account_id),
+            password.as_bytes(),
+            true,
+        )
+        .await
+        .map_err(|error| error.to_string())
+}
+
+async fn delete_password(account_id: &str) -> Result<(), String> {
+    let keyring = oo7::Keyring::new()
+        .await
+        .map_err(|error| error.to_string())?;
+    keyring
+        .delete(&secret_attributes(account_id))
+        .await
+        .map_err(|error| error.to_string())
+}
+
+async fn password(account_id: &str) -> Result<String, String> {
+    let keyring = oo7::Keyring::new()
+        .await
+        .map_err(|error| error.to_string())?;
+    let items = keyring
+        .search_items(&secret_attributes(account_id))
+        .await
+        .map_err(|error| error.to_string())?;
+    let item = items
+        .first()
+        .ok_or_else(|| gettext("The password of the account is missing"))?;
+    let secret = item.secret().await.map_err(|error| error.to_string())?;
+    Ok(String::from_utf8_lossy(secret.as_bytes()).into_owned())
+}
@@ -0,0 +1,224 @@
+//! The WebDAV and CalDAV (RFC 4791, RFC 6578) request bodies and responses.
+
+use roxmltree::{Document, Node};
+
+const DAV: &str = "DAV:";
+const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+const APPLE_ICAL: &str = "http://apple.com/ns/ical/";
+const CALENDAR_SERVER: &str = "http://calendarserver.org/ns/";
+
+pub const CURRENT_USER_PRINCIPAL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:current-user-principal/>
+  </d:prop>
+</d:propfind>"#;
+
+pub const CALENDAR_HOME_SET: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <c:calendar-home-set/>
+  </d:prop>
+</d:propfind>"#;
+
+pub const CALENDARS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:a="http://apple.com/ns/ical/" xmlns:cs="http://calendarserver.org/ns/">
+  <d:prop>
+    <d:resourcetype/>
+    <d:displayname/>
+    <d:sync-token/>
+    <cs:getctag/>
+    <a:calendar-color/>
+    <c:supported-calendar-component-set/>
+  </d:prop>
+</d:propfind>"#;
+
+pub const ETAGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+</d:propfind>"#;
+
+/// Ask for the resources changed since `sync_token`, or all of them if it is empty.
+pub fn sync_collection(sync_token: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>{}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+</d:sync-collection>"#,
+        escape(sync_token)
+    )
+}
+
+/// Ask for the data of the resources at `hrefs`.
+pub fn calendar_multiget<'a>(hrefs: impl IntoIterator<Item = &'a str>) -> String {
+    let hrefs: String = hrefs
+        .into_iter()
+        .map(|href| format!("\n  <d:href>{}</d:href>", escape(href)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data/>
+  </d:prop>{hrefs}
+</c:calendar-multiget>"#
+    )
+}
+
+/// Create a calendar of events called `name`, with `color` as `#rrggbb`.
+pub fn mkcalendar(name: &str, color: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:mkcalendar xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:a="http://apple.com/ns/ical/">
+  <d:set>
+    <d:prop>
+      <d:displayname>{}</d:displayname>
+      <a:calendar-color>{}</a:calendar-color>
+      <c:supported-calendar-component-set>
+        <c:comp name="VEVENT"/>
+      </c:supported-calendar-component-set>
+    </d:prop>
+  </d:set>
+</c:mkcalendar>"#,
+        escape(name),
+        escape(color)
+    )
+}
+
+/// A `response` element of a `multistatus`, with the properties the server found.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Response {
+    pub href: String,
+    /// The status of the whole response, e.g. 404 for resources removed since the sync token.
+    pub status: Option<u16>,
+    pub etag: Option<String>,
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+    pub ctag: Option<String>,
+    pub sync_token: Option<String>,
+    pub is_calendar: bool,
+    /// Whether the calendar accepts events. Calendars not listing their components accept them.
+    pub supports_events: bool,
+    pub calendar_data: Option<String>,
+    pub current_user_principal: Option<String>,
+    pub calendar_home_set: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MultiStatus {
+    pub responses: Vec<Response>,
+    /// The new sync token of a `sync-collection` report.
+    pub sync_token: Option<String>,
+}
+
+pub fn parse_multistatus(text: &str) -> Result<MultiStatus, roxmltree::Error> {
+    let document = Document::parse(text)?;
+    let root = document.root_element();
+
+    let responses = children(root, DAV, "response")
+        .map(parse_response)
+        .collect();
+    let sync_token = child(root, DAV, "sync-token").map(text_of);
+
+    Ok(MultiStatus {
+        responses,
+        sync_token,
+    })
+}
+
+fn parse_response(node: Node) -> Response {
+    let mut response = Response {
+        href: child(node, DAV, "href").map(text_of).unwrap_or_default(),
+        status: child(node, DAV, "status").and_then(|status| parse_status(&text_of(status))),
+        supports_events: true,
+        ..Response::default()
+    };
+
+    let found_props = children(node, DAV, "propstat")
+        .filter(|propstat| {
+            child(*propstat, DAV, "status")
+                .and_then(|status| parse_status(&text_of(status)))
+                .is_some_and(|status| (200..300).contains(&status))
+        })
+        .filter_map(|propstat| child(propstat, DAV, "prop"));
+
+    for prop in found_props.flat_map(|prop| prop.children().filter(Node::is_element)) {
+        let name = prop.tag_name();
+        match (name.namespace().unwrap_or_default(), name.name()) {
+            (DAV, "getetag") => response.etag = Some(text_of(prop)),
+            (DAV, "displayname") => response.display_name = Some(text_of(prop)),
+            (DAV, "sync-token") => response.sync_token = Some(text_of(prop)),
+            (DAV, "resourcetype") => {
+                response.is_calendar = child(prop, CALDAV, "calendar").is_some();
+            }
+            (DAV, "current-user-principal") => {
+                response.current_user_principal = child(prop, DAV, "href").map(text_of);
+            }
+            (CALDAV, "calendar-home-set") => {
+                response.calendar_home_set = child(prop, DAV, "href").map(text_of);
+            }
+            (CALDAV, "supported-calendar-component-set") => {
+                response.supports_events = children(prop, CALDAV, "comp")
+                    .any(|comp| comp.attribute("name") == Some("VEVENT"));
+            }
+            (CALDAV, "calendar-data") => response.calendar_data = Some(text_of(prop)),
+            (APPLE_ICAL, "calendar-color") => response.color = Some(text_of(prop)),
+            (CALENDAR_SERVER, "getctag") => response.ctag = Some(text_of(prop)),
+            _ => (),
+        }
+    }
+
+    response
+}
+
+/// The code of an HTTP status line such as `HTTP/1.1 404 Not Found`.
+fn parse_status(line: &str) -> Option<u16> {
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: &str,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| has_name(*child, namespace, name))
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: &'static str,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| has_name(*child, namespace, name))
+}
+
+fn has_name(node: Node, namespace: &str, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().namespace() == Some(namespace)
+        && node.tag_name().name() == name
+}
+
+fn text_of(node: Node) -> String {
+    node.descendants()
+        .filter(Node::is_text)
+        .filter_map(|text| text.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
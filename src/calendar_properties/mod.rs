@@ -0,0 +1,177 @@
+//! The properties of calendars that ccm does not store, kept in the settings by calendar URI.
+//!
+//! As they are not stored with the calendars, only this application knows them: other
+//! applications using ccm and other devices syncing the calendars do not see the description,
+//! can still change the events of a locked calendar and do not add the reminders or use the
+//! default duration. They are also lost if the settings are reset, or if a calendar is deleted and
+//! added again, as it then has another URI.
+
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+};
+
+use ccm::{Calendar, jiff::SignedDuration};
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use tracing::warn;
+
+use crate::CalendarManagerApplication;
+
+/// The reminders that can be chosen, in minutes before the start of the events.
+pub const REMINDERS: [u32; 6] = [0, 5, 15, 30, 60, 1440];
+
+/// The default durations of events that can be chosen, in minutes.
+pub const DURATIONS: [u32; 5] = [15, 30, 60, 90, 120];
+
+/// The properties of a calendar.
+#[derive(Debug, Clone, PartialEq, Eq, glib::Variant)]
+pub struct Properties {
+    pub description: String,
+    /// Whether the events of the calendar cannot be created, changed or deleted.
+    pub locked: bool,
+    /// The reminders of new events, in minutes before their start.
+    pub reminders: Vec<u32>,
+    /// The duration of new events, in minutes.
+    pub default_duration: u32,
+    /// Whether the events are busy time, rather than free time. It is written as `TRANSP` in
+    /// exported and uploaded events.
+    pub busy: bool,
+}
+
+impl Default for Properties {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            locked: false,
+            reminders: Vec::new(),
+            default_duration: 60,
+            busy: true,
+        }
+    }
+}
+
+impl Properties {
+    pub fn default_duration(&self) -> SignedDuration {
+        SignedDuration::from_mins(i64::from(self.default_duration.max(1)))
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct CalendarProperties {
+        pub settings: OnceCell<gio::Settings>,
+        /// The URI of the calendar events were last created in or imported to since startup.
+        pub last_used: RefCell<Option<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CalendarProperties {
+        const NAME: &'static str = "CalendarProperties";
+        type Type = super::CalendarProperties;
+    }
+
+    impl ObjectImpl for CalendarProperties {}
+}
+
+glib::wrapper! {
+    /// The properties of all calendars, saved in the settings.
+    pub struct CalendarProperties(ObjectSubclass<imp::CalendarProperties>);
+}
+
+impl CalendarProperties {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        obj.imp()
+            .settings
+            .set(settings.clone())
+            .expect("settings should only be set once");
+        obj
+    }
+
+    fn settings(&self) -> &gio::Settings {
+        self.imp()
+            .settings
+            .get()
+            .expect("settings should be initialized")
+    }
+
+    /// The properties of `calendar`, or the default ones if they were never changed.
+    pub fn get(&self, calendar: &Calendar) -> Properties {
+        let mut properties: HashMap<String, Properties> =
+            self.settings().get("calendar-properties");
+        properties.remove(&calendar.uri()).unwrap_or_default()
+    }
+
+    pub fn set(&self, calendar: &Calendar, properties: Properties) {
+        self.save(calendar.uri().to_string(), Some(properties));
+    }
+
+    /// Forget the properties of a deleted calendar.
+    pub fn forget(&self, calendar: &Calendar) {
+        self.save(calendar.uri().to_string(), None);
+        self.set_default(calendar, false);
+        let mut last_used = self.imp().last_used.borrow_mut();
+        if last_used.as_deref() == Some(calendar.uri().as_str()) {
+            *last_used = None;
+        }
+    }
+
+    /// Whether `calendar` is preselected when creating or importing events.
+    pub fn is_default(&self, calendar: &Calendar) -> bool {
+        self.settings().string("default-calendar").as_str() == calendar.uri().as_str()
+    }
+
+    /// Make `calendar` the default one, or stop it being the default one.
+    pub fn set_default(&self, calendar: &Calendar, default: bool) {
+        let uri = if default {
+            calendar.uri().to_string()
+        } else if self.is_default(calendar) {
+            String::new()
+        } else {
+            return;
+        };
+        if let Err(error) = self.settings().set_string("default-calendar", &uri) {
+            warn!("Could not save the default calendar: {error}");
+        }
+    }
+
+    /// Whether events were last created in or imported to `calendar`.
+    pub fn is_last_used(&self, calendar: &Calendar) -> bool {
+        self.imp().last_used.borrow().as_deref() == Some(calendar.uri().as_str())
+    }
+
+    /// Remember that events were created in or imported to `calendar`, to preselect it next time.
+    pub fn set_last_used(&self, calendar: &Calendar) {
+        self.imp()
+            .last_used
+            .replace(Some(calendar.uri().to_string()));
+    }
+
+    /// Whether the events of `calendar` cannot be changed, because it is locked or its events
+    /// come from a subscription.
+    pub fn is_read_only(&self, calendar: &Calendar) -> bool {
+        self.get(calendar).locked
+            || CalendarManagerApplication::default()
+                .subscriptions()
+                .find(calendar)
+                .is_some()
+    }
+
+    fn save(&self, uri: String, calendar_properties: Option<Properties>) {
+        let settings = self.settings();
+        let mut properties: HashMap<String, Properties> = settings.get("calendar-properties");
+        match calendar_properties {
+            Some(calendar_properties) if calendar_properties != Properties::default() => {
+                properties.insert(uri, calendar_properties);
+            }
+            _ => {
+                properties.remove(&uri);
+            }
+        }
+        if let Err(error) = settings.set("calendar-properties", properties) {
+            warn!("Could not save the properties of the calendars: {error}");
+        }
+    }
+}
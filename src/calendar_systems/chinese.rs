@@ -0,0 +1,140 @@
+//! The Chinese lunisolar calendar, from the table of its months from 1900 to 2100 used by most
+//! calendar applications.
+
+use ccm::jiff::civil;
+
+/// The months of each year from 1900, in bits: the lowest 4 bits are the leap month, or 0 if there
+/// is none, the next 12 bits are the months from the 12th to the 1st, set for months of 30 days
+/// rather than 29, and the 17th bit is set for a leap month of 30 days.
+const YEARS: [u32; 201] = [
+    0x04bd8, 0x04ae0, 0x0a570, 0x054d5, 0x0d260, 0x0d950, 0x16554, 0x056a0, 0x09ad0, 0x055d2,
+    0x04ae0, 0x0a5b6, 0x0a4d0, 0x0d250, 0x1d255, 0x0b540, 0x0d6a0, 0x0ada2, 0x095b0, 0x14977,
+    0x04970, 0x0a4b0, 0x0b4b5, 0x06a50, 0x06d40, 0x1ab54, 0x02b60, 0x09570, 0x052f2, 0x04970,
+    0x06566, 0x0d4a0, 0x0ea50, 0x16a95, 0x05ad0, 0x02b60, 0x186e3, 0x092e0, 0x1c8d7, 0x0c950,
+    0x0d4a0, 0x1d8a6, 0x0b550, 0x056a0, 0x1a5b4, 0x025d0, 0x092d0, 0x0d2b2, 0x0a950, 0x0b557,
+    0x06ca0, 0x0b550, 0x15355, 0x04da0, 0x0a5b0, 0x14573, 0x052b0, 0x0a9a8, 0x0e950, 0x06aa0,
+    0x0aea6, 0x0ab50, 0x04b60, 0x0aae4, 0x0a570, 0x05260, 0x0f263, 0x0d950, 0x05b57, 0x056a0,
+    0x096d0, 0x04dd5, 0x04ad0, 0x0a4d0, 0x0d4d4, 0x0d250, 0x0d558, 0x0b540, 0x0b6a0, 0x195a6,
+    0x095b0, 0x049b0, 0x0a974, 0x0a4b0, 0x0b27a, 0x06a50, 0x06d40, 0x0af46, 0x0ab60, 0x09570,
+    0x04af5, 0x04970, 0x064b0, 0x074a3, 0x0ea50, 0x06b58, 0x05ac0, 0x0ab60, 0x096d5, 0x092e0,
+    0x0c960, 0x0d954, 0x0d4a0, 0x0da50, 0x07552, 0x056a0, 0x0abb7, 0x025d0, 0x092d0, 0x0cab5,
+    0x0a950, 0x0b4a0, 0x0baa4, 0x0ad50, 0x055d9, 0x04ba0, 0x0a5b0, 0x15176, 0x052b0, 0x0a930,
+    0x07954, 0x06aa0, 0x0ad50, 0x05b52, 0x04b60, 0x0a6e6, 0x0a4e0, 0x0d260, 0x0ea65, 0x0d530,
+    0x05aa0, 0x076a3, 0x096d0, 0x04afb, 0x04ad0, 0x0a4d0, 0x1d0b6, 0x0d250, 0x0d520, 0x0dd45,
+    0x0b5a0, 0x056d0, 0x055b2, 0x049b0, 0x0a577, 0x0a4b0, 0x0aa50, 0x1b255, 0x06d20, 0x0ada0,
+    0x14b63, 0x09370, 0x049f8, 0x04970, 0x064b0, 0x168a6, 0x0ea50, 0x06b20, 0x1a6c4, 0x0aae0,
+    0x0a2e0, 0x0d2e3, 0x0c960, 0x0d557, 0x0d4a0, 0x0da50, 0x05d55, 0x056a0, 0x0a6d0, 0x055d4,
+    0x052d0, 0x0a9b8, 0x0a950, 0x0b4a0, 0x0b6a6, 0x0ad50, 0x055a0, 0x0aba4, 0x0a5b0, 0x052b0,
+    0x0b273, 0x06930, 0x07337, 0x06aa0, 0x0ad50, 0x14b55, 0x04b60, 0x0a570, 0x054e4, 0x0d160,
+    0x0e968, 0x0d520, 0x0daa0, 0x16aa6, 0x056d0, 0x04ae0, 0x0a9d4, 0x0a2d0, 0x0d150, 0x0f252,
+    0x0d520,
+];
+
+/// The year, month, whether the month is a leap month and day of `date`, if it is in the table.
+pub fn from_gregorian(date: civil::Date) -> Option<(i64, u8, bool, u8)> {
+    // The first day of the table, the new year of 1900
+    let mut days = i64::from(civil::date(1900, 1, 31).until(date).ok()?.get_days());
+    if days < 0 {
+        return None;
+    }
+
+    let mut year = 1900;
+    let months = loop {
+        let months = YEARS.get(year as usize - 1900)?;
+        let length = year_length(*months);
+        if days < length {
+            break *months;
+        }
+        days -= length;
+        year += 1;
+    };
+
+    let leap_month = (months & 0xf) as u8;
+    for month in 1..=12 {
+        let length = month_length(months, month);
+        if days < length {
+            return Some((year, month, false, days as u8 + 1));
+        }
+        days -= length;
+
+        if month == leap_month {
+            let length = leap_month_length(months);
+            if days < length {
+                return Some((year, month, true, days as u8 + 1));
+            }
+            days -= length;
+        }
+    }
+    None
+}
+
+fn month_length(months: u32, month: u8) -> i64 {
+    if months & (0x10000 >> month) != 0 {
+        30
+    } else {
+        29
+    }
+}
+
+fn leap_month_length(months: u32) -> i64 {
+    match months & 0xf {
+        0 => 0,
+        _ if months & 0x10000 != 0 => 30,
+        _ => 29,
+    }
+}
+
+fn year_length(months: u32) -> i64 {
+    (1..=12)
+        .map(|month| month_length(months, month))
+        .sum::<i64>()
+        + leap_month_length(months)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_are_converted() {
+        assert_eq!(
+            from_gregorian(civil::date(1900, 1, 31)),
+            Some((1900, 1, false, 1))
+        );
+        // The Spring Festival and the Mid-Autumn Festival
+        assert_eq!(
+            from_gregorian(civil::date(2024, 2, 10)),
+            Some((2024, 1, false, 1))
+        );
+        assert_eq!(
+            from_gregorian(civil::date(2024, 2, 9)),
+            Some((2023, 12, false, 30))
+        );
+        assert_eq!(
+            from_gregorian(civil::date(2025, 10, 6)),
+            Some((2025, 8, false, 15))
+        );
+    }
+
+    #[test]
+    fn leap_months_follow_their_month() {
+        assert_eq!(
+            from_gregorian(civil::date(2023, 3, 22)),
+            Some((2023, 2, true, 1))
+        );
+        assert_eq!(
+            from_gregorian(civil::date(2023, 4, 20)),
+            Some((2023, 3, false, 1))
+        );
+        assert_eq!(
+            from_gregorian(civil::date(2025, 7, 25)),
+            Some((2025, 6, true, 1))
+        );
+    }
+
+    #[test]
+    fn dates_out_of_range() {
+        assert_eq!(from_gregorian(civil::date(1900, 1, 30)), None);
+        assert_eq!(from_gregorian(civil::date(2101, 12, 31)), None);
+    }
+}
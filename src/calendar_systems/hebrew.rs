@@ -0,0 +1,138 @@
+//! The Hebrew calendar, with the arithmetic of the molad of Calendrical Calculations.
+//!
+//! Months are numbered from Nisan, so Tishri, the first month of the year, is the 7th and Adar II
+//! of leap years is the 13th.
+
+use ccm::jiff::civil;
+
+use super::fixed;
+
+/// The fixed day of 1 Tishri of year 1.
+const EPOCH: i64 = -1_373_427;
+
+/// The year, month and day of `date`.
+pub fn from_gregorian(date: civil::Date) -> (i64, u8, u8) {
+    let date = fixed(date);
+    // The mean length of a year is 35975351/98496 days
+    let approx = (98_496 * (date - EPOCH)).div_euclid(35_975_351) + 1;
+    let mut year = approx - 1;
+    while new_year(year + 1) <= date {
+        year += 1;
+    }
+    let start = if date < fixed_from_hebrew(year, 1, 1) {
+        7
+    } else {
+        1
+    };
+    let month = (start..=last_month(year))
+        .find(|month| date <= fixed_from_hebrew(year, *month, last_day_of_month(year, *month)))
+        .unwrap_or(start);
+    let day = date - fixed_from_hebrew(year, month, 1) + 1;
+    (year, month, day as u8)
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    (7 * year + 1).rem_euclid(19) < 7
+}
+
+fn last_month(year: i64) -> u8 {
+    if is_leap_year(year) { 13 } else { 12 }
+}
+
+fn elapsed_days(year: i64) -> i64 {
+    let months_elapsed = (235 * year - 234).div_euclid(19);
+    let parts_elapsed = 12_084 + 13_753 * months_elapsed;
+    let days = 29 * months_elapsed + parts_elapsed.div_euclid(25_920);
+    // The new year is delayed so that Yom Kippur is not next to Shabbat
+    if (3 * (days + 1)).rem_euclid(7) < 3 {
+        days + 1
+    } else {
+        days
+    }
+}
+
+fn year_length_correction(year: i64) -> i64 {
+    let ny0 = elapsed_days(year - 1);
+    let ny1 = elapsed_days(year);
+    let ny2 = elapsed_days(year + 1);
+    if ny2 - ny1 == 356 {
+        2
+    } else if ny1 - ny0 == 382 {
+        1
+    } else {
+        0
+    }
+}
+
+fn new_year(year: i64) -> i64 {
+    EPOCH + elapsed_days(year) + year_length_correction(year)
+}
+
+fn days_in_year(year: i64) -> i64 {
+    new_year(year + 1) - new_year(year)
+}
+
+fn last_day_of_month(year: i64, month: u8) -> u8 {
+    let short = match month {
+        2 | 4 | 6 | 10 | 13 => true,
+        12 => !is_leap_year(year),
+        8 => !matches!(days_in_year(year), 355 | 385),
+        9 => matches!(days_in_year(year), 353 | 383),
+        _ => false,
+    };
+    if short { 29 } else { 30 }
+}
+
+fn fixed_from_hebrew(year: i64, month: u8, day: u8) -> i64 {
+    let days_in_months = |months: &mut dyn Iterator<Item = u8>| -> i64 {
+        months
+            .map(|month| i64::from(last_day_of_month(year, month)))
+            .sum()
+    };
+    let days_before_month = if month < 7 {
+        days_in_months(&mut (7..=last_month(year))) + days_in_months(&mut (1..month))
+    } else {
+        days_in_months(&mut (7..month))
+    };
+    new_year(year) + days_before_month + i64::from(day) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_are_converted() {
+        // Rosh Hashanah, Hanukkah and Passover
+        assert_eq!(from_gregorian(civil::date(2025, 9, 23)), (5786, 7, 1));
+        assert_eq!(from_gregorian(civil::date(2025, 12, 15)), (5786, 9, 25));
+        assert_eq!(from_gregorian(civil::date(2024, 4, 23)), (5784, 1, 15));
+        // The last day of a year
+        assert_eq!(from_gregorian(civil::date(2025, 9, 22)), (5785, 6, 29));
+    }
+
+    #[test]
+    fn leap_years_have_two_adars() {
+        assert!(is_leap_year(5784));
+        assert!(!is_leap_year(5785));
+        assert!(!is_leap_year(5786));
+        assert!(is_leap_year(5787));
+
+        // Purim Katan and Purim
+        assert_eq!(from_gregorian(civil::date(2024, 2, 23)), (5784, 12, 14));
+        assert_eq!(from_gregorian(civil::date(2024, 3, 24)), (5784, 13, 14));
+        assert_eq!(from_gregorian(civil::date(2025, 3, 14)), (5785, 12, 14));
+    }
+
+    #[test]
+    fn years_have_valid_lengths() {
+        for year in 5700..5900 {
+            let length = days_in_year(year);
+            if is_leap_year(year) {
+                assert!(matches!(length, 383..=385), "{year} has {length} days");
+            } else {
+                assert!(matches!(length, 353..=355), "{year} has {length} days");
+            }
+        }
+    }
+}
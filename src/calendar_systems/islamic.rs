@@ -0,0 +1,68 @@
+//! The arithmetical Islamic calendar, whose months may start a day before or after the sighting
+//! of the crescent moon.
+
+use ccm::jiff::civil;
+
+use super::fixed;
+
+/// The fixed day of 1 Muharram of year 1.
+const EPOCH: i64 = 227_015;
+
+/// The year, month and day of `date`.
+pub fn from_gregorian(date: civil::Date) -> (i64, u8, u8) {
+    let date = fixed(date);
+    let year = (30 * (date - EPOCH) + 10_646).div_euclid(10_631);
+    let prior_days = date - fixed_from_islamic(year, 1, 1);
+    let month = (11 * prior_days + 330).div_euclid(325);
+    let day = date - fixed_from_islamic(year, month, 1) + 1;
+    (year, month as u8, day as u8)
+}
+
+fn fixed_from_islamic(year: i64, month: i64, day: i64) -> i64 {
+    day + 29 * (month - 1)
+        + (6 * month - 1).div_euclid(11)
+        + (year - 1) * 354
+        + (3 + 11 * year).div_euclid(30)
+        + EPOCH
+        - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_are_converted() {
+        // The epoch, 16 July 622 in the Julian calendar
+        assert_eq!(from_gregorian(civil::date(622, 7, 19)), (1, 1, 1));
+        // 1445 is a leap year, with 30 days in its last month
+        assert_eq!(from_gregorian(civil::date(2024, 7, 7)), (1445, 12, 30));
+        assert_eq!(from_gregorian(civil::date(2024, 7, 8)), (1446, 1, 1));
+        assert_eq!(from_gregorian(civil::date(2025, 3, 1)), (1446, 9, 1));
+        assert_eq!(from_gregorian(civil::date(2026, 2, 18)), (1447, 9, 1));
+    }
+
+    #[test]
+    fn months_follow_each_other() {
+        let mut previous = from_gregorian(civil::date(2000, 1, 1));
+        let mut date = civil::date(2000, 1, 2);
+        while date.year() < 2040 {
+            let (year, month, day) = from_gregorian(date);
+            if day == 1 {
+                assert!(
+                    matches!(previous.2, 29 | 30),
+                    "{previous:?} is before {date}"
+                );
+                if month == 1 {
+                    assert_eq!((year - 1, 12), (previous.0, previous.1));
+                } else {
+                    assert_eq!((year, month - 1), (previous.0, previous.1));
+                }
+            } else {
+                assert_eq!((year, month, day - 1), previous);
+            }
+            previous = (year, month, day);
+            date = date.tomorrow().unwrap();
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Other calendar systems whose dates can be shown next to the Gregorian ones, computed locally.
+
+use ccm::jiff::civil;
+use gettextrs::gettext;
+use gtk::glib;
+
+mod chinese;
+mod hebrew;
+mod islamic;
+mod persian;
+
+/// The days of the Chinese calendar, from the 1st to the 30th.
+const CHINESE_DAYS: [&str; 30] = [
+    "初一", "初二", "初三", "初四", "初五", "初六", "初七", "初八", "初九", "初十", "十一", "十二",
+    "十三", "十四", "十五", "十六", "十七", "十八", "十九", "二十", "廿一", "廿二", "廿三", "廿四",
+    "廿五", "廿六", "廿七", "廿八", "廿九", "三十",
+];
+
+/// The months of the Chinese calendar, from the 1st to the 12th.
+const CHINESE_MONTHS: [&str; 12] = [
+    "正月", "二月", "三月", "四月", "五月", "六月", "七月", "八月", "九月", "十月", "冬月", "腊月",
+];
+
+#[derive(Debug, Default, Hash, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[enum_type(name = "CalendarSystem")]
+pub enum CalendarSystem {
+    #[default]
+    #[enum_value(name = "None", nick = "none")]
+    None,
+    #[enum_value(name = "Hebrew", nick = "hebrew")]
+    Hebrew,
+    #[enum_value(name = "Islamic", nick = "islamic")]
+    Islamic,
+    #[enum_value(name = "Persian", nick = "persian")]
+    Persian,
+    #[enum_value(name = "Chinese", nick = "chinese")]
+    Chinese,
+}
+
+impl CalendarSystem {
+    /// All calendar systems, in the order they are offered to the user.
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::Hebrew,
+        Self::Islamic,
+        Self::Persian,
+        Self::Chinese,
+    ];
+
+    /// The date of `date` in this calendar system, if it has one and `date` is in its range.
+    pub fn date(self, date: civil::Date) -> Option<OtherDate> {
+        let (year, month, leap_month, day) = match self {
+            Self::None => return None,
+            Self::Hebrew => {
+                let (year, month, day) = hebrew::from_gregorian(date);
+                (year, month, false, day)
+            }
+            Self::Islamic => {
+                let (year, month, day) = islamic::from_gregorian(date);
+                (year, month, false, day)
+            }
+            Self::Persian => {
+                let (year, month, day) = persian::from_gregorian(date)?;
+                (year, month, false, day)
+            }
+            Self::Chinese => chinese::from_gregorian(date)?,
+        };
+        Some(OtherDate {
+            system: self,
+            year,
+            month,
+            leap_month,
+            day,
+        })
+    }
+}
+
+/// A date in another calendar system than the Gregorian one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OtherDate {
+    system: CalendarSystem,
+    year: i64,
+    /// The month, from 1, in the numbering of the calendar system.
+    month: u8,
+    /// Whether the month is the leap month repeating `month`, in the Chinese calendar.
+    leap_month: bool,
+    day: u8,
+}
+
+impl OtherDate {
+    pub fn is_month_start(&self) -> bool {
+        self.day == 1
+    }
+
+    /// The day of the month, e.g. `25`.
+    pub fn day(&self) -> String {
+        if self.system == CalendarSystem::Chinese {
+            chinese_day(self.day)
+        } else {
+            self.day.to_string()
+        }
+    }
+
+    /// The day, or the month on its first day, to mark where months start in the views.
+    pub fn day_label(&self) -> String {
+        if self.is_month_start() {
+            self.month_name()
+        } else {
+            self.day()
+        }
+    }
+
+    pub fn month_name(&self) -> String {
+        match self.system {
+            CalendarSystem::None => String::new(),
+            CalendarSystem::Hebrew => hebrew_month_name(self.year, self.month),
+            CalendarSystem::Islamic => islamic_month_name(self.month),
+            CalendarSystem::Persian => persian_month_name(self.month),
+            CalendarSystem::Chinese => {
+                let name = CHINESE_MONTHS
+                    .get(usize::from(self.month.saturating_sub(1)))
+                    .copied()
+                    .unwrap_or_default();
+                if self.leap_month {
+                    format!("闰{name}")
+                } else {
+                    name.to_owned()
+                }
+            }
+        }
+    }
+
+    /// The full date, e.g. `25 Kislev 5786`.
+    pub fn long(&self) -> String {
+        if self.system == CalendarSystem::Chinese {
+            return format!("{}{}", self.month_name(), self.day());
+        }
+        // Translators: A date in another calendar system, e.g. “25 Kislev 5786”
+        gettext("{day} {month} {year}")
+            .replace("{day}", &self.day())
+            .replace("{month}", &self.month_name())
+            .replace("{year}", &self.year.to_string())
+    }
+}
+
+/// The number of days since the first day of the first year of the Gregorian calendar, counted
+/// from 1, which the algorithms of Calendrical Calculations use.
+fn fixed(date: civil::Date) -> i64 {
+    civil::date(1, 1, 1)
+        .until(date)
+        .map(|span| i64::from(span.get_days()))
+        .unwrap_or_default()
+        + 1
+}
+
+fn chinese_day(day: u8) -> String {
+    CHINESE_DAYS
+        .get(usize::from(day.saturating_sub(1)))
+        .copied()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn hebrew_month_name(year: i64, month: u8) -> String {
+    match month {
+        1 => gettext("Nisan"),
+        2 => gettext("Iyar"),
+        3 => gettext("Sivan"),
+        4 => gettext("Tammuz"),
+        5 => gettext("Av"),
+        6 => gettext("Elul"),
+        7 => gettext("Tishrei"),
+        8 => gettext("Cheshvan"),
+        9 => gettext("Kislev"),
+        10 => gettext("Tevet"),
+        11 => gettext("Shevat"),
+        12 if hebrew::is_leap_year(year) => gettext("Adar I"),
+        12 => gettext("Adar"),
+        13 => gettext("Adar II"),
+        _ => String::new(),
+    }
+}
+
+fn islamic_month_name(month: u8) -> String {
+    match month {
+        1 => gettext("Muharram"),
+        2 => gettext("Safar"),
+        3 => gettext("Rabi al-Awwal"),
+        4 => gettext("Rabi al-Thani"),
+        5 => gettext("Jumada al-Awwal"),
+        6 => gettext("Jumada al-Thani"),
+        7 => gettext("Rajab"),
+        8 => gettext("Shaban"),
+        9 => gettext("Ramadan"),
+        10 => gettext("Shawwal"),
+        11 => gettext("Dhu al-Qadah"),
+        12 => gettext("Dhu al-Hijjah"),
+        _ => String::new(),
+    }
+}
+
+fn persian_month_name(month: u8) -> String {
+    match month {
+        1 => gettext("Farvardin"),
+        2 => gettext("Ordibehesht"),
+        3 => gettext("Khordad"),
+        4 => gettext("Tir"),
+        5 => gettext("Mordad"),
+        6 => gettext("Shahrivar"),
+        7 => gettext("Mehr"),
+        8 => gettext("Aban"),
+        9 => gettext("Azar"),
+        10 => gettext("Dey"),
+        11 => gettext("Bahman"),
+        12 => gettext("Esfand"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_days() {
+        assert_eq!(fixed(civil::date(1, 1, 1)), 1);
+        assert_eq!(fixed(civil::date(1945, 11, 12)), 710_347);
+    }
+
+    #[test]
+    fn dates_are_shown() {
+        assert_eq!(CalendarSystem::None.date(civil::date(2025, 12, 15)), None);
+
+        let date = CalendarSystem::Hebrew
+            .date(civil::date(2025, 12, 15))
+            .unwrap();
+        assert_eq!(date.long(), "25 Kislev 5786");
+        assert_eq!(date.day_label(), "25");
+
+        let date = CalendarSystem::Hebrew
+            .date(civil::date(2024, 3, 11))
+            .unwrap();
+        assert!(date.is_month_start());
+        assert_eq!(date.day_label(), "Adar II");
+
+        let date = CalendarSystem::Chinese
+            .date(civil::date(2023, 3, 22))
+            .unwrap();
+        assert_eq!(date.long(), "闰二月初一");
+    }
+}
@@ -0,0 +1,109 @@
+//! The Persian (Solar Hijri) calendar, with the leap years of Borkowski's algorithm, which follow
+//! the astronomical calendar from 1800 to 2256.
+
+use ccm::jiff::civil;
+
+/// The years at which the 33-year cycles of leap years are broken.
+const BREAKS: [i64; 20] = [
+    -61, 9, 38, 199, 426, 686, 756, 818, 1111, 1181, 1210, 1635, 2060, 2097, 2192, 2262, 2324,
+    2394, 2456, 3178,
+];
+
+/// The year, month and day of `date`, if it is in the supported years.
+pub fn from_gregorian(date: civil::Date) -> Option<(i64, u8, u8)> {
+    let gregorian_year = i64::from(date.year());
+    let mut year = gregorian_year - 621;
+    let (leap, march) = new_year(year)?;
+    let first_day = civil::Date::new(date.year(), 3, march).ok()?;
+    let mut days = i64::from(first_day.until(date).ok()?.get_days());
+
+    if days >= 0 {
+        // The first six months have 31 days
+        if days <= 185 {
+            return Some((year, (1 + days / 31) as u8, (days % 31 + 1) as u8));
+        }
+        days -= 186;
+    } else {
+        year -= 1;
+        days += 179;
+        if leap == 1 {
+            days += 1;
+        }
+    }
+    Some((year, (7 + days / 30) as u8, (days % 30 + 1) as u8))
+}
+
+/// The number of years since the last leap year, 0 for a leap year, and the day of March of the
+/// new year, Nowruz, in the Gregorian year it starts.
+fn new_year(year: i64) -> Option<(i64, i8)> {
+    if year < BREAKS[0] || year >= BREAKS[BREAKS.len() - 1] {
+        return None;
+    }
+
+    let gregorian_year = year + 621;
+    let mut leap_years = -14;
+    let mut previous_break = BREAKS[0];
+    let mut jump = 0;
+    for &next_break in &BREAKS[1..] {
+        jump = next_break - previous_break;
+        if year < next_break {
+            break;
+        }
+        leap_years += jump / 33 * 8 + jump % 33 / 4;
+        previous_break = next_break;
+    }
+    let mut years = year - previous_break;
+    leap_years += years / 33 * 8 + (years % 33 + 3) / 4;
+    if jump % 33 == 4 && jump - years == 4 {
+        leap_years += 1;
+    }
+
+    let gregorian_leap_years = gregorian_year / 4 - (gregorian_year / 100 + 1) * 3 / 4 - 150;
+    let march = 20 + leap_years - gregorian_leap_years;
+
+    if jump - years < 6 {
+        years = years - jump + (jump + 4) / 33 * 33;
+    }
+    let mut leap = ((years + 1) % 33 - 1) % 4;
+    if leap == -1 {
+        leap = 4;
+    }
+    Some((leap, march as i8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_are_converted() {
+        // Nowruz
+        assert_eq!(from_gregorian(civil::date(2024, 3, 20)), Some((1403, 1, 1)));
+        assert_eq!(from_gregorian(civil::date(2025, 3, 21)), Some((1404, 1, 1)));
+        assert_eq!(from_gregorian(civil::date(2026, 3, 21)), Some((1405, 1, 1)));
+        // The first month of 30 days
+        assert_eq!(from_gregorian(civil::date(2024, 9, 22)), Some((1403, 7, 1)));
+        assert_eq!(
+            from_gregorian(civil::date(2025, 1, 1)),
+            Some((1403, 10, 12))
+        );
+    }
+
+    #[test]
+    fn leap_years_end_on_the_30th() {
+        assert_eq!(
+            from_gregorian(civil::date(2025, 3, 20)),
+            Some((1403, 12, 30))
+        );
+        assert_eq!(
+            from_gregorian(civil::date(2026, 3, 20)),
+            Some((1404, 12, 29))
+        );
+    }
+
+    #[test]
+    fn dates_out_of_range() {
+        assert_eq!(from_gregorian(civil::date(3800, 1, 1)), None);
+        assert_eq!(from_gregorian(civil::date(500, 1, 1)), None);
+    }
+}
@@ -0,0 +1,369 @@
+//! The command-line interface, to read and add events from scripts without opening a window.
+
+use std::collections::HashMap;
+
+use ccm::{
+    Calendar, Event, Manager, Timeframe,
+    jiff::{Span, Timestamp, Zoned, tz::TimeZone},
+};
+use gettextrs::gettext;
+use gtk::{gio, glib, prelude::*};
+
+mod output;
+
+use crate::{
+    CalendarManagerApplication, search, spawn, time_zones,
+    utils::{all_calendars, events_between, sort_chronologically, wait_for_event},
+};
+
+use self::output::Format;
+
+/// The first arguments handled as commands, any other argument is a file to import.
+const COMMANDS: [&str; 5] = ["list", "add", "calendars", "search", "help"];
+
+/// Whether `arguments`, without the program name, are a command rather than files to open.
+pub fn is_command(arguments: &[String]) -> bool {
+    arguments
+        .first()
+        .is_some_and(|first| COMMANDS.contains(&first.as_str()) || first == "--help")
+}
+
+/// Run the command in `arguments`, printing its output on the terminal of `command_line`.
+///
+/// Commands finish asynchronously, as adding an event only completes once it is in its calendar,
+/// so the application is held until then and the exit status is set at the end.
+pub fn run(command_line: &gio::ApplicationCommandLine, arguments: &[String]) -> glib::ExitCode {
+    let command = match Command::parse(arguments) {
+        Ok(command) => command,
+        Err(error) => return report(command_line, Err(error)),
+    };
+
+    let application = CalendarManagerApplication::default();
+    let guard = application.hold();
+    let command_line = command_line.clone();
+    spawn!(async move {
+        let result = command.run(&application.manager()).await;
+        let exit_code = report(&command_line, result);
+        // Sent to a remote invocation when the command line is released
+        command_line.set_exit_status(exit_code.value());
+        if !command_line.is_remote() {
+            application.set_command_exit_code(exit_code);
+        }
+        drop(guard);
+    });
+    glib::ExitCode::SUCCESS
+}
+
+/// Print the output or the error of a command on the terminal of `command_line`.
+fn report(
+    command_line: &gio::ApplicationCommandLine,
+    result: Result<String, String>,
+) -> glib::ExitCode {
+    match result {
+        Ok(output) => {
+            command_line.print_literal(&output);
+            glib::ExitCode::SUCCESS
+        }
+        Err(error) => {
+            command_line.printerr_literal(&format!("calendar-manager: {error}\n"));
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+enum Command {
+    List {
+        from: Timestamp,
+        to: Timestamp,
+        calendar: Option<String>,
+        format: Format,
+    },
+    Add {
+        name: String,
+        description: String,
+        timeframe: Timeframe,
+        /// The IANA name of the time zone the times were given in.
+        time_zone: Option<String>,
+        calendar: String,
+    },
+    Calendars,
+    Search {
+        text: String,
+        format: Format,
+    },
+    Help,
+}
+
+impl Command {
+    fn parse(arguments: &[String]) -> Result<Self, String> {
+        let Some((command, arguments)) = arguments.split_first() else {
+            return Ok(Self::Help);
+        };
+
+        match command.as_str() {
+            "list" => {
+                let arguments = Arguments::parse(arguments, &["from", "to", "calendar", "format"])?;
+                arguments.expect_positionals(0)?;
+                let from = match arguments.option("from") {
+                    Some(from) => parse_time(from)?.0,
+                    None => CalendarManagerApplication::default()
+                        .time_zones()
+                        .now()
+                        .start_of_day()
+                        .map_err(|error| error.to_string())?,
+                };
+                let to = match arguments.option("to") {
+                    // A date includes the whole day
+                    Some(to) => match parse_time(to)? {
+                        (to, true) => to.tomorrow().map_err(|error| error.to_string())?,
+                        (to, false) => to,
+                    },
+                    None => from
+                        .checked_add(Span::new().days(7))
+                        .map_err(|error| error.to_string())?,
+                };
+                Ok(Self::List {
+                    from: from.timestamp(),
+                    to: to.timestamp(),
+                    calendar: arguments.option("calendar").map(String::from),
+                    format: arguments.format()?,
+                })
+            }
+            "add" => {
+                let arguments =
+                    Arguments::parse(arguments, &["start", "end", "calendar", "description"])?;
+                arguments.expect_positionals(1)?;
+                let name = arguments.positionals[0].clone();
+                let (start, all_day) = parse_time(arguments.required_option("start")?)?;
+                let end = match arguments.option("end") {
+                    Some(end) => match parse_time(end)? {
+                        // The end date of all-day events is inclusive on the command line
+                        (end, true) if all_day => day_end(&end)?,
+                        (end, _) => end.timestamp(),
+                    },
+                    None if all_day => day_end(&start)?,
+                    None => start
+                        .checked_add(Span::new().hours(1))
+                        .map_err(|error| error.to_string())?
+                        .timestamp(),
+                };
+                // All-day events are the same days in every time zone
+                let (start, time_zone) = if all_day {
+                    (day_bounds(&start)?.0, None)
+                } else {
+                    let time_zone = start.time_zone().iana_name().map(String::from);
+                    (start.timestamp(), time_zone)
+                };
+                if end <= start {
+                    return Err(gettext("The end must be after the start"));
+                }
+
+                Ok(Self::Add {
+                    name,
+                    description: arguments
+                        .option("description")
+                        .unwrap_or_default()
+                        .to_owned(),
+                    timeframe: Timeframe::new(start, end, all_day),
+                    time_zone,
+                    calendar: arguments.required_option("calendar")?.to_owned(),
+                })
+            }
+            "calendars" => {
+                Arguments::parse(arguments, &[])?.expect_positionals(0)?;
+                Ok(Self::Calendars)
+            }
+            "search" => {
+                let arguments = Arguments::parse(arguments, &["format"])?;
+                if arguments.positionals.is_empty() {
+                    return Err(gettext("Missing the text to search for"));
+                }
+                Ok(Self::Search {
+                    text: arguments.positionals.join(" "),
+                    format: arguments.format()?,
+                })
+            }
+            "help" | "--help" => Ok(Self::Help),
+            command => Err(gettext("Unknown command “{}”").replace("{}", command)),
+        }
+    }
+
+    async fn run(self, manager: &Manager) -> Result<String, String> {
+        match self {
+            Self::List {
+                from,
+                to,
+                calendar,
+                format,
+            } => {
+                let calendars = match calendar {
+                    Some(name) => find_calendars(manager, &name)?,
+                    None => all_calendars(manager),
+                };
+                Ok(format.events(&events_between(&calendars, from, to)))
+            }
+            Self::Add {
+                name,
+                description,
+                timeframe,
+                time_zone,
+                calendar,
+            } => {
+                let calendar = match find_calendars(manager, &calendar)?.as_slice() {
+                    [calendar] => calendar.clone(),
+                    _ => {
+                        return Err(gettext(
+                            "Several calendars are named “{}”, use the URI of one of them",
+                        )
+                        .replace("{}", &calendar));
+                    }
+                };
+                let calendar_properties =
+                    CalendarManagerApplication::default().calendar_properties();
+                if calendar_properties.is_read_only(&calendar) {
+                    return Err(gettext("This calendar is read-only"));
+                }
+
+                let uid = glib::uuid_string_random().to_string();
+                calendar.import_event(&uid, &name, &description, timeframe);
+                // The event is added asynchronously, so only report it once it is there
+                let event = wait_for_event(&calendar, &uid)
+                    .await
+                    .ok_or_else(|| gettext("The event could not be added"))?;
+                if let Some(time_zone) = time_zone {
+                    CalendarManagerApplication::default()
+                        .time_zones()
+                        .set_event(&event.uri(), Some(&time_zone));
+                }
+                Ok(format!("{uid}\n"))
+            }
+            Self::Calendars => Ok(output::calendars(&all_calendars(manager))),
+            Self::Search { text, format } => {
+                let mut events: Vec<Event> = manager
+                    .search_events(&text)
+                    .iter::<Event>()
+                    .filter_map(Result::ok)
+                    .collect();
+                sort_chronologically(&mut events);
+                Ok(format.events(&events))
+            }
+            Self::Help => Ok(help()),
+        }
+    }
+}
+
+/// The positional arguments and the `--name value` options of a command.
+struct Arguments<'a> {
+    positionals: Vec<&'a String>,
+    options: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Arguments<'a> {
+    fn parse(arguments: &'a [String], names: &[&str]) -> Result<Self, String> {
+        let mut positionals = Vec::new();
+        let mut options = HashMap::new();
+
+        let mut arguments = arguments.iter();
+        while let Some(argument) = arguments.next() {
+            let Some(option) = argument.strip_prefix("--") else {
+                positionals.push(argument);
+                continue;
+            };
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name, value),
+                None => {
+                    let value = arguments
+                        .next()
+                        .ok_or_else(|| gettext("Missing a value for --{}").replace("{}", option))?;
+                    (option, value.as_str())
+                }
+            };
+            if !names.contains(&name) {
+                return Err(gettext("Unknown option --{}").replace("{}", name));
+            }
+            options.insert(name, value);
+        }
+
+        Ok(Self {
+            positionals,
+            options,
+        })
+    }
+
+    fn expect_positionals(&self, count: usize) -> Result<(), String> {
+        if self.positionals.len() == count {
+            Ok(())
+        } else {
+            Err(gettext(
+                "Wrong number of arguments, see calendar-manager help",
+            ))
+        }
+    }
+
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options.get(name).copied()
+    }
+
+    fn required_option(&self, name: &str) -> Result<&'a str, String> {
+        self.option(name)
+            .ok_or_else(|| gettext("Missing the --{} option").replace("{}", name))
+    }
+
+    fn format(&self) -> Result<Format, String> {
+        match self.option("format") {
+            None | Some("text") => Ok(Format::Text),
+            Some("json") => Ok(Format::Json),
+            Some("ics") => Ok(Format::Ics),
+            Some(format) => Err(gettext("Unknown format “{}”").replace("{}", format)),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<(Zoned, bool), String> {
+    search::parse_time(value)
+        .ok_or_else(|| gettext("Invalid date or time “{}”").replace("{}", value))
+}
+
+/// When the day of `date` starts and ends in the system time zone, where all-day events are
+/// stored.
+fn day_bounds(date: &Zoned) -> Result<(Timestamp, Timestamp), String> {
+    time_zones::day_bounds(date.date(), &TimeZone::system())
+        .ok_or_else(|| gettext("Invalid date “{}”").replace("{}", &date.date().to_string()))
+}
+
+fn day_end(date: &Zoned) -> Result<Timestamp, String> {
+    day_bounds(date).map(|(_, end)| end)
+}
+
+/// The calendars with the URI or the name `name`, ignoring the case.
+fn find_calendars(manager: &Manager, name: &str) -> Result<Vec<Calendar>, String> {
+    let lowercase_name = name.to_lowercase();
+    let calendars: Vec<Calendar> = all_calendars(manager)
+        .into_iter()
+        .filter(|calendar| {
+            calendar.uri() == name || calendar.name().to_lowercase() == lowercase_name
+        })
+        .collect();
+
+    if calendars.is_empty() {
+        Err(gettext("No calendar named “{}”").replace("{}", name))
+    } else {
+        Ok(calendars)
+    }
+}
+
+fn help() -> String {
+    gettext(
+        "Usage:
+  calendar-manager [FILE…]
+  calendar-manager list [--from DATE] [--to DATE] [--calendar NAME] [--format text|json|ics]
+  calendar-manager add NAME --start TIME [--end TIME] --calendar NAME [--description TEXT]
+  calendar-manager calendars
+  calendar-manager search TEXT [--format text|json|ics]
+
+Dates are written as 2026-10-05, and times as 2026-10-05T14:00 in the display time zone,
+2026-10-05T14:00+02:00 or 2026-10-05T14:00[Europe/Paris]. Events added with only dates last
+all day. Calendars are found by name or by URI, as shown by the calendars command.
+",
+    )
+}
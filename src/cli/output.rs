@@ -0,0 +1,121 @@
+use ccm::{Calendar, Event};
+use gettextrs::gettext;
+
+use crate::{
+    CalendarManagerApplication, format,
+    ics::{self, VCalendar, VEvent},
+};
+
+/// How events are printed.
+pub enum Format {
+    /// One line per event, for humans.
+    Text,
+    /// An array of objects, for scripts.
+    Json,
+    /// An iCalendar file, for other calendar applications.
+    Ics,
+}
+
+impl Format {
+    pub fn events(&self, events: &[Event]) -> String {
+        match self {
+            Self::Text => events.iter().filter_map(text_line).collect(),
+            Self::Json => {
+                let objects: Vec<String> = events.iter().filter_map(json_object).collect();
+                if objects.is_empty() {
+                    "[]\n".to_owned()
+                } else {
+                    format!("[\n  {}\n]\n", objects.join(",\n  "))
+                }
+            }
+            Self::Ics => ics::write(&VCalendar {
+                events: events
+                    .iter()
+                    .filter_map(|event| VEvent::from_event(event, None))
+                    .collect(),
+                ..VCalendar::default()
+            }),
+        }
+    }
+}
+
+/// One line per calendar with its URI, to use in the other commands.
+pub fn calendars(calendars: &[Calendar]) -> String {
+    calendars
+        .iter()
+        .map(|calendar| format!("{}\t{}\n", calendar.name(), calendar.uri()))
+        .collect()
+}
+
+fn text_line(event: &Event) -> Option<String> {
+    let timeframe = event.timeframe()?;
+    let mut time = format::timeframe(&timeframe);
+    if timeframe.all_day() {
+        time = format!("{time} {}", gettext("all day"));
+    }
+
+    Some(format!(
+        "{time}\t{}\t{}\n",
+        event.name(),
+        event.calendar().name()
+    ))
+}
+
+fn json_object(event: &Event) -> Option<String> {
+    let timeframe = event.timeframe()?;
+    let time_zones = CalendarManagerApplication::default().time_zones();
+    let time_zone = time_zones.for_timeframe(&timeframe);
+    let start = timeframe.start().timestamp().to_zoned(time_zone.clone());
+    let end = timeframe.end().timestamp().to_zoned(time_zone);
+    // All-day events have dates, other events have RFC 3339 times
+    let (start, end) = if timeframe.all_day() {
+        (start.date().to_string(), end.date().to_string())
+    } else {
+        (
+            start
+                .timestamp()
+                .display_with_offset(start.offset())
+                .to_string(),
+            end.timestamp()
+                .display_with_offset(end.offset())
+                .to_string(),
+        )
+    };
+    let calendar = event.calendar();
+    let event_time_zone = time_zones
+        .event_name(event)
+        .map_or_else(|| "null".to_owned(), |name| json_string(&name));
+
+    Some(format!(
+        "{{\"uid\": {}, \"name\": {}, \"description\": {}, \"calendar\": {}, \
+         \"calendar_uri\": {}, \"start\": {}, \"end\": {}, \"all_day\": {}, \
+         \"time_zone\": {}}}",
+        json_string(&event.uid()),
+        json_string(&event.name()),
+        json_string(&event.description()),
+        json_string(&calendar.name()),
+        json_string(&calendar.uri()),
+        json_string(&start),
+        json_string(&end),
+        timeframe.all_day(),
+        event_time_zone,
+    ))
+}
+
+fn json_string(value: &str) -> String {
+    let mut string = String::with_capacity(value.len() + 2);
+    string.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            c if c.is_control() => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c => string.push(c),
+        }
+    }
+    string.push('"');
+    string
+}
@@ -0,0 +1,802 @@
+//! A D-Bus interface for other applications to read and change events.
+//!
+//! Calendars and events are identified by the URIs that [`ccm::Manager::find_resource`]
+//! resolves, and times are Unix times in seconds.
+
+use std::{collections::HashMap, rc::Rc};
+
+use ccm::{
+    Calendar, Collection, Event, Resource, Timeframe,
+    jiff::{Timestamp, tz::TimeZone},
+};
+use gtk::{gio, glib, prelude::*};
+use tracing::warn;
+
+use crate::{
+    CalendarManagerApplication,
+    ics::format_color,
+    spawn,
+    utils::{all_calendars, events_between, wait_for_event},
+};
+
+const INTERFACE_NAME: &str = "io.gitlab.TitouanReal.CalendarManager.Events";
+
+const INTERFACE: &str = r#"<node>
+  <interface name="io.gitlab.TitouanReal.CalendarManager.Events">
+    <method name="ListCalendars">
+      <arg type="aa{sv}" name="calendars" direction="out"/>
+    </method>
+    <method name="ListEvents">
+      <arg type="x" name="from" direction="in"/>
+      <arg type="x" name="to" direction="in"/>
+      <arg type="as" name="calendars" direction="in"/>
+      <arg type="aa{sv}" name="events" direction="out"/>
+    </method>
+    <method name="CreateEvent">
+      <arg type="s" name="calendar" direction="in"/>
+      <arg type="a{sv}" name="properties" direction="in"/>
+      <arg type="s" name="uri" direction="out"/>
+    </method>
+    <method name="DeleteEvent">
+      <arg type="s" name="uri" direction="in"/>
+    </method>
+    <signal name="CalendarsChanged"/>
+    <signal name="EventsChanged">
+      <arg type="s" name="calendar"/>
+    </signal>
+  </interface>
+</node>"#;
+
+/// The D-Bus errors replied by the methods.
+#[derive(Debug)]
+enum Error {
+    InvalidArgs(&'static str),
+    NotFound(String),
+    ReadOnly(String),
+}
+
+impl Error {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::InvalidArgs(_) => "org.freedesktop.DBus.Error.InvalidArgs",
+            Self::NotFound(_) => "io.gitlab.TitouanReal.CalendarManager.Error.NotFound",
+            Self::ReadOnly(_) => "io.gitlab.TitouanReal.CalendarManager.Error.ReadOnly",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::InvalidArgs(message) => (*message).to_owned(),
+            Self::NotFound(uri) => format!("No resource at {uri}"),
+            Self::ReadOnly(uri) => format!("The calendar {uri} is read-only"),
+        }
+    }
+}
+
+/// A calendar, as listed by the interface.
+#[derive(Debug, Clone)]
+struct CalendarInfo {
+    uri: String,
+    name: String,
+    /// The color of the calendar, as `#rrggbb`.
+    color: String,
+    /// Subscribed calendars are only changed by their feed, and locked ones not at all.
+    read_only: bool,
+    description: String,
+}
+
+impl CalendarInfo {
+    fn properties(&self) -> HashMap<String, glib::Variant> {
+        HashMap::from([
+            ("uri".to_owned(), self.uri.to_variant()),
+            ("name".to_owned(), self.name.to_variant()),
+            ("color".to_owned(), self.color.to_variant()),
+            ("read-only".to_owned(), self.read_only.to_variant()),
+            ("description".to_owned(), self.description.to_variant()),
+        ])
+    }
+}
+
+/// An event, as listed by the interface.
+#[derive(Debug, Clone)]
+struct EventInfo {
+    uri: String,
+    uid: String,
+    name: String,
+    description: String,
+    /// The URI of the calendar of the event.
+    calendar: String,
+    start: Timestamp,
+    end: Timestamp,
+    all_day: bool,
+    /// The IANA name of the time zone the event is planned in, if it is known.
+    time_zone: Option<String>,
+}
+
+impl EventInfo {
+    fn properties(&self) -> HashMap<String, glib::Variant> {
+        let mut properties = HashMap::from([
+            ("uri".to_owned(), self.uri.to_variant()),
+            ("uid".to_owned(), self.uid.to_variant()),
+            ("name".to_owned(), self.name.to_variant()),
+            ("description".to_owned(), self.description.to_variant()),
+            ("calendar".to_owned(), self.calendar.to_variant()),
+            ("start".to_owned(), self.start.as_second().to_variant()),
+            ("end".to_owned(), self.end.as_second().to_variant()),
+            ("all-day".to_owned(), self.all_day.to_variant()),
+        ]);
+        if let Some(time_zone) = &self.time_zone {
+            properties.insert("time-zone".to_owned(), time_zone.to_variant());
+        }
+        properties
+    }
+}
+
+/// An event to create, from the properties given to `CreateEvent`.
+#[derive(Debug, Clone, PartialEq)]
+struct NewEvent {
+    name: String,
+    description: String,
+    start: Timestamp,
+    end: Timestamp,
+    all_day: bool,
+    /// The IANA name of the time zone the event is planned in.
+    time_zone: Option<String>,
+}
+
+impl NewEvent {
+    /// The properties are `name`, `start` and `end`, and optionally `description`, `all-day` and
+    /// `time-zone`, the IANA name of the time zone the event is planned in.
+    fn from_properties(properties: &glib::VariantDict) -> Result<Self, Error> {
+        let lookup_time = |key: &str| {
+            properties
+                .lookup::<i64>(key)
+                .ok()
+                .flatten()
+                .and_then(|seconds| Timestamp::from_second(seconds).ok())
+        };
+        let name = properties
+            .lookup::<String>("name")
+            .ok()
+            .flatten()
+            .ok_or(Error::InvalidArgs("Missing the name of the event"))?;
+        let description = properties
+            .lookup::<String>("description")
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let start =
+            lookup_time("start").ok_or(Error::InvalidArgs("Missing the start of the event"))?;
+        let end = lookup_time("end").ok_or(Error::InvalidArgs("Missing the end of the event"))?;
+        if end <= start {
+            return Err(Error::InvalidArgs("The end must be after the start"));
+        }
+        let all_day = properties
+            .lookup::<bool>("all-day")
+            .ok()
+            .flatten()
+            .unwrap_or(false);
+        let time_zone = properties.lookup::<String>("time-zone").ok().flatten();
+        if time_zone
+            .as_deref()
+            .is_some_and(|time_zone| TimeZone::get(time_zone).is_err())
+        {
+            return Err(Error::InvalidArgs("Unknown time zone"));
+        }
+
+        Ok(Self {
+            name,
+            description,
+            start,
+            end,
+            all_day,
+            time_zone,
+        })
+    }
+}
+
+/// Where the calendars and events of the interface are read and changed.
+trait Backend {
+    fn calendars(&self) -> Vec<CalendarInfo>;
+
+    fn calendar(&self, uri: &str) -> Option<CalendarInfo>;
+
+    /// The events of the calendars at `calendar_uris` happening at least partly between `from`
+    /// and `to`, in chronological order.
+    fn events(&self, calendar_uris: &[String], from: Timestamp, to: Timestamp) -> Vec<EventInfo>;
+
+    /// The URI of the calendar of the event at `uri`, if it exists.
+    fn event_calendar(&self, uri: &str) -> Option<String>;
+
+    /// Create `event` in the calendar at `calendar_uri`, returning its URI once it was added.
+    async fn create_event(&self, calendar_uri: &str, event: NewEvent) -> Result<String, Error>;
+
+    fn delete_event(&self, uri: &str);
+}
+
+pub fn register(
+    connection: &gio::DBusConnection,
+    object_path: &str,
+) -> Result<gio::RegistrationId, glib::Error> {
+    let id = export(connection, object_path, Rc::new(ApplicationBackend))?;
+
+    Signals {
+        connection: connection.clone(),
+        object_path: object_path.to_owned(),
+    }
+    .watch();
+
+    Ok(id)
+}
+
+/// Export the interface at `object_path` on `connection`, for the calendars and events of
+/// `backend`.
+fn export<B: Backend + 'static>(
+    connection: &gio::DBusConnection,
+    object_path: &str,
+    backend: Rc<B>,
+) -> Result<gio::RegistrationId, glib::Error> {
+    let node = gio::DBusNodeInfo::for_xml(INTERFACE)?;
+    let interface = node
+        .lookup_interface(INTERFACE_NAME)
+        .expect("interface should be in the node");
+
+    connection
+        .register_object(object_path, &interface)
+        .method_call(move |_, _, _, _, method, parameters, invocation| {
+            let reply = match method {
+                "ListCalendars" => Ok((list_calendars(&*backend),).to_variant()),
+                "ListEvents" => parameters
+                    .get::<(i64, i64, Vec<String>)>()
+                    .ok_or(Error::InvalidArgs("Expected (xxas)"))
+                    .and_then(|(from, to, calendars)| list_events(&*backend, from, to, &calendars))
+                    .map(|events| (events,).to_variant()),
+                "CreateEvent" => match parameters.get::<(String, glib::VariantDict)>() {
+                    Some((calendar, properties)) => {
+                        let backend = backend.clone();
+                        // The event is added asynchronously, reply once it is there
+                        spawn!(async move {
+                            let reply = create_event(&*backend, &calendar, &properties)
+                                .await
+                                .map(|uri| (uri,).to_variant());
+                            return_reply(invocation, reply);
+                        });
+                        return;
+                    }
+                    None => Err(Error::InvalidArgs("Expected (sa{sv})")),
+                },
+                "DeleteEvent" => parameters
+                    .get::<(String,)>()
+                    .ok_or(Error::InvalidArgs("Expected (s)"))
+                    .and_then(|(uri,)| delete_event(&*backend, &uri))
+                    .map(|()| ().to_variant()),
+                // GDBus replies UnknownMethod itself to the methods the interface does not have
+                _ => {
+                    warn!("Unexpected call to {method}");
+                    return;
+                }
+            };
+            return_reply(invocation, reply);
+        })
+        .build()
+}
+
+fn return_reply(invocation: gio::DBusMethodInvocation, reply: Result<glib::Variant, Error>) {
+    match reply {
+        Ok(reply) => invocation.return_value(Some(&reply)),
+        Err(error) => invocation.return_dbus_error(error.name(), &error.message()),
+    }
+}
+
+fn list_calendars(backend: &impl Backend) -> Vec<HashMap<String, glib::Variant>> {
+    backend
+        .calendars()
+        .iter()
+        .map(CalendarInfo::properties)
+        .collect()
+}
+
+/// The events between `from` and `to` in the calendars with the URIs `calendar_uris`, or in all
+/// calendars if it is empty.
+fn list_events(
+    backend: &impl Backend,
+    from: i64,
+    to: i64,
+    calendar_uris: &[String],
+) -> Result<Vec<HashMap<String, glib::Variant>>, Error> {
+    let from = Timestamp::from_second(from).map_err(|_| Error::InvalidArgs("Invalid from"))?;
+    let to = Timestamp::from_second(to).map_err(|_| Error::InvalidArgs("Invalid to"))?;
+
+    let calendar_uris = if calendar_uris.is_empty() {
+        backend
+            .calendars()
+            .into_iter()
+            .map(|calendar| calendar.uri)
+            .collect()
+    } else {
+        if let Some(missing) = calendar_uris
+            .iter()
+            .find(|uri| backend.calendar(uri).is_none())
+        {
+            return Err(Error::NotFound(missing.clone()));
+        }
+        calendar_uris.to_vec()
+    };
+
+    Ok(backend
+        .events(&calendar_uris, from, to)
+        .iter()
+        .map(EventInfo::properties)
+        .collect())
+}
+
+/// Create an event in the calendar at `calendar_uri`, returning its URI.
+async fn create_event(
+    backend: &impl Backend,
+    calendar_uri: &str,
+    properties: &glib::VariantDict,
+) -> Result<String, Error> {
+    let calendar = backend
+        .calendar(calendar_uri)
+        .ok_or_else(|| Error::NotFound(calendar_uri.to_owned()))?;
+    if calendar.read_only {
+        return Err(Error::ReadOnly(calendar.uri));
+    }
+
+    let event = NewEvent::from_properties(properties)?;
+    backend.create_event(calendar_uri, event).await
+}
+
+fn delete_event(backend: &impl Backend, uri: &str) -> Result<(), Error> {
+    let calendar = backend
+        .event_calendar(uri)
+        .and_then(|calendar_uri| backend.calendar(&calendar_uri))
+        .ok_or_else(|| Error::NotFound(uri.to_owned()))?;
+    if calendar.read_only {
+        return Err(Error::ReadOnly(calendar.uri));
+    }
+
+    backend.delete_event(uri);
+    Ok(())
+}
+
+/// The calendars and events of the application.
+struct ApplicationBackend;
+
+impl ApplicationBackend {
+    fn find_calendar(uri: &str) -> Option<Calendar> {
+        let manager = CalendarManagerApplication::default().manager();
+        match manager.find_resource(uri) {
+            Some(Resource::Calendar(calendar)) => Some(calendar),
+            _ => None,
+        }
+    }
+
+    fn find_event(uri: &str) -> Option<Event> {
+        let manager = CalendarManagerApplication::default().manager();
+        match manager.find_resource(uri) {
+            Some(Resource::Event(event)) => Some(event),
+            _ => None,
+        }
+    }
+
+    fn calendar_info(calendar: &Calendar) -> CalendarInfo {
+        let calendar_properties = CalendarManagerApplication::default().calendar_properties();
+        CalendarInfo {
+            uri: calendar.uri(),
+            name: calendar.name(),
+            color: format_color(&calendar.color()),
+            read_only: calendar_properties.is_read_only(calendar),
+            description: calendar_properties.get(calendar).description,
+        }
+    }
+
+    /// Returns `None` for events without a timeframe, which cannot be described.
+    fn event_info(event: &Event) -> Option<EventInfo> {
+        let timeframe = event.timeframe()?;
+        Some(EventInfo {
+            uri: event.uri(),
+            uid: event.uid(),
+            name: event.name(),
+            description: event.description(),
+            calendar: event.calendar().uri(),
+            start: timeframe.start().timestamp(),
+            end: timeframe.end().timestamp(),
+            all_day: timeframe.all_day(),
+            time_zone: CalendarManagerApplication::default()
+                .time_zones()
+                .event_name(event),
+        })
+    }
+}
+
+impl Backend for ApplicationBackend {
+    fn calendars(&self) -> Vec<CalendarInfo> {
+        all_calendars(&CalendarManagerApplication::default().manager())
+            .iter()
+            .map(Self::calendar_info)
+            .collect()
+    }
+
+    fn calendar(&self, uri: &str) -> Option<CalendarInfo> {
+        Self::find_calendar(uri).map(|calendar| Self::calendar_info(&calendar))
+    }
+
+    fn events(&self, calendar_uris: &[String], from: Timestamp, to: Timestamp) -> Vec<EventInfo> {
+        let calendars: Vec<Calendar> = calendar_uris
+            .iter()
+            .filter_map(|uri| Self::find_calendar(uri))
+            .collect();
+        events_between(&calendars, from, to)
+            .iter()
+            .filter_map(Self::event_info)
+            .collect()
+    }
+
+    fn event_calendar(&self, uri: &str) -> Option<String> {
+        Self::find_event(uri).map(|event| event.calendar().uri())
+    }
+
+    async fn create_event(&self, calendar_uri: &str, event: NewEvent) -> Result<String, Error> {
+        let calendar = Self::find_calendar(calendar_uri)
+            .ok_or_else(|| Error::NotFound(calendar_uri.to_owned()))?;
+        let uid = glib::uuid_string_random();
+        calendar.import_event(
+            &uid,
+            &event.name,
+            &event.description,
+            Timeframe::new(event.start, event.end, event.all_day),
+        );
+        let uri = wait_for_event(&calendar, &uid)
+            .await
+            .map(|event| event.uri())
+            .ok_or_else(|| Error::NotFound(uid.to_string()))?;
+        if let Some(time_zone) = &event.time_zone {
+            CalendarManagerApplication::default()
+                .time_zones()
+                .set_event(&uri, Some(time_zone));
+        }
+        Ok(uri)
+    }
+
+    fn delete_event(&self, uri: &str) {
+        if let Some(event) = Self::find_event(uri) {
+            event.delete();
+        }
+    }
+}
+
+/// Emits the signals of the interface when the collections, calendars or events change.
+#[derive(Clone)]
+struct Signals {
+    connection: gio::DBusConnection,
+    object_path: String,
+}
+
+impl Signals {
+    fn watch(self) {
+        let collections = CalendarManagerApplication::default()
+            .manager()
+            .collections_model();
+        for collection in collections.iter::<Collection>().filter_map(Result::ok) {
+            self.watch_collection(&collection);
+        }
+
+        collections.connect_items_changed(move |collections, position, _, added| {
+            for collection in (position..position + added)
+                .filter_map(|position| collections.item(position).and_downcast::<Collection>())
+            {
+                self.watch_collection(&collection);
+            }
+            self.emit("CalendarsChanged", None);
+        });
+    }
+
+    fn watch_collection(&self, collection: &Collection) {
+        let calendars = collection.calendars();
+        for calendar in calendars.iter::<Calendar>().filter_map(Result::ok) {
+            self.watch_calendar(&calendar);
+        }
+
+        let signals = self.clone();
+        calendars.connect_items_changed(move |calendars, position, _, added| {
+            for calendar in (position..position + added)
+                .filter_map(|position| calendars.item(position).and_downcast::<Calendar>())
+            {
+                signals.watch_calendar(&calendar);
+            }
+            signals.emit("CalendarsChanged", None);
+        });
+    }
+
+    fn watch_calendar(&self, calendar: &Calendar) {
+        let signals = self.clone();
+        let uri = calendar.uri();
+        calendar.events().connect_items_changed(move |_, _, _, _| {
+            signals.emit("EventsChanged", Some(&(uri.as_str(),).to_variant()));
+        });
+    }
+
+    fn emit(&self, signal: &str, parameters: Option<&glib::Variant>) {
+        if let Err(error) =
+            self.connection
+                .emit_signal(None, &self.object_path, INTERFACE_NAME, signal, parameters)
+        {
+            warn!("Could not emit {signal}: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::dbus::peer_connections;
+
+    const OBJECT_PATH: &str = "/io/gitlab/TitouanReal/CalendarManager";
+
+    /// Calendars kept in memory, with the events created in them.
+    struct MemoryBackend {
+        calendars: Vec<CalendarInfo>,
+        events: RefCell<Vec<EventInfo>>,
+    }
+
+    impl MemoryBackend {
+        fn new() -> Self {
+            let calendar = |uri: &str, read_only| CalendarInfo {
+                uri: uri.to_owned(),
+                name: uri.to_owned(),
+                color: "#3584e4".to_owned(),
+                read_only,
+                description: String::new(),
+            };
+            Self {
+                calendars: vec![calendar("work", false), calendar("holidays", true)],
+                events: RefCell::new(vec![EventInfo {
+                    uri: "holidays/new-year".to_owned(),
+                    uid: "new-year".to_owned(),
+                    name: "New Year".to_owned(),
+                    description: String::new(),
+                    calendar: "holidays".to_owned(),
+                    start: Timestamp::from_second(1_798_761_600).unwrap(),
+                    end: Timestamp::from_second(1_798_848_000).unwrap(),
+                    all_day: true,
+                    time_zone: None,
+                }]),
+            }
+        }
+    }
+
+    impl Backend for MemoryBackend {
+        fn calendars(&self) -> Vec<CalendarInfo> {
+            self.calendars.clone()
+        }
+
+        fn calendar(&self, uri: &str) -> Option<CalendarInfo> {
+            self.calendars
+                .iter()
+                .find(|calendar| calendar.uri == uri)
+                .cloned()
+        }
+
+        fn events(
+            &self,
+            calendar_uris: &[String],
+            from: Timestamp,
+            to: Timestamp,
+        ) -> Vec<EventInfo> {
+            self.events
+                .borrow()
+                .iter()
+                .filter(|event| calendar_uris.contains(&event.calendar))
+                .filter(|event| event.start < to && event.end > from)
+                .cloned()
+                .collect()
+        }
+
+        fn event_calendar(&self, uri: &str) -> Option<String> {
+            self.events
+                .borrow()
+                .iter()
+                .find(|event| event.uri == uri)
+                .map(|event| event.calendar.clone())
+        }
+
+        async fn create_event(&self, calendar_uri: &str, event: NewEvent) -> Result<String, Error> {
+            let uid = format!("event-{}", self.events.borrow().len());
+            let uri = format!("{calendar_uri}/{uid}");
+            self.events.borrow_mut().push(EventInfo {
+                uri: uri.clone(),
+                uid,
+                name: event.name,
+                description: event.description,
+                calendar: calendar_uri.to_owned(),
+                start: event.start,
+                end: event.end,
+                all_day: event.all_day,
+                time_zone: event.time_zone,
+            });
+            Ok(uri)
+        }
+
+        fn delete_event(&self, uri: &str) {
+            self.events.borrow_mut().retain(|event| event.uri != uri);
+        }
+    }
+
+    /// Run `test` with a connection to the interface exported for a new [`MemoryBackend`].
+    fn run<F: Future<Output = ()>>(test: impl FnOnce(gio::DBusConnection, Rc<MemoryBackend>) -> F) {
+        let context = glib::MainContext::default();
+        context.block_on(async {
+            let (service, client) = peer_connections().await;
+            let backend = Rc::new(MemoryBackend::new());
+            let id = export(&service, OBJECT_PATH, backend.clone())
+                .expect("interface should be exported");
+            test(client, backend).await;
+            service
+                .unregister_object(id)
+                .expect("interface should be unexported");
+        });
+        // GDBus frees the method call handler from the main context, which must happen in the
+        // thread that created it
+        while context.iteration(false) {}
+    }
+
+    async fn call(
+        client: &gio::DBusConnection,
+        method: &str,
+        parameters: glib::Variant,
+    ) -> Result<glib::Variant, glib::Error> {
+        client
+            .call_future(
+                None,
+                OBJECT_PATH,
+                INTERFACE_NAME,
+                method,
+                Some(&parameters),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+            )
+            .await
+    }
+
+    /// Whether `result` is the D-Bus error `name`.
+    fn is_error(result: Result<glib::Variant, glib::Error>, name: &str) -> bool {
+        result.is_err_and(|error| {
+            gio::DBusError::remote_error(&error).is_some_and(|remote| remote == name)
+        })
+    }
+
+    fn new_event(name: &str, start: i64, end: i64) -> HashMap<String, glib::Variant> {
+        HashMap::from([
+            ("name".to_owned(), name.to_variant()),
+            ("start".to_owned(), start.to_variant()),
+            ("end".to_owned(), end.to_variant()),
+        ])
+    }
+
+    #[test]
+    fn calendars_and_events_are_listed() {
+        run(|client, _| async move {
+            let reply = call(&client, "ListCalendars", ().to_variant())
+                .await
+                .expect("calendars should be listed");
+            let (calendars,) = reply
+                .get::<(Vec<HashMap<String, glib::Variant>>,)>()
+                .unwrap();
+            let read_only: Vec<(String, bool)> = calendars
+                .iter()
+                .map(|calendar| {
+                    (
+                        calendar["uri"].get().unwrap(),
+                        calendar["read-only"].get().unwrap(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                read_only,
+                [("work".to_owned(), false), ("holidays".to_owned(), true)]
+            );
+
+            let list = |from: i64, to: i64, calendars: &[&str]| {
+                let calendars: Vec<String> = calendars.iter().map(|uri| uri.to_string()).collect();
+                call(&client, "ListEvents", (from, to, calendars).to_variant())
+            };
+            let reply = list(1_798_800_000, 1_798_900_000, &[])
+                .await
+                .expect("events should be listed");
+            let (events,) = reply
+                .get::<(Vec<HashMap<String, glib::Variant>>,)>()
+                .unwrap();
+            assert_eq!(events.len(), 1);
+            assert_eq!(
+                events[0]["uri"].get::<String>().as_deref(),
+                Some("holidays/new-year")
+            );
+            assert_eq!(events[0]["start"].get::<i64>(), Some(1_798_761_600));
+            assert_eq!(events[0]["all-day"].get::<bool>(), Some(true));
+            assert!(!events[0].contains_key("time-zone"));
+
+            let reply = list(1_798_800_000, 1_798_900_000, &["work"]).await.unwrap();
+            let (events,) = reply
+                .get::<(Vec<HashMap<String, glib::Variant>>,)>()
+                .unwrap();
+            assert!(events.is_empty());
+
+            assert!(is_error(
+                list(0, 1, &["missing"]).await,
+                "io.gitlab.TitouanReal.CalendarManager.Error.NotFound"
+            ));
+        });
+    }
+
+    #[test]
+    fn events_are_created() {
+        run(|client, backend| async move {
+            let mut properties = new_event("Review", 1_800_000_000, 1_800_003_600);
+            properties.insert("time-zone".to_owned(), "Europe/Paris".to_variant());
+            let reply = call(&client, "CreateEvent", ("work", properties).to_variant())
+                .await
+                .expect("event should be created");
+            let (uri,) = reply.get::<(String,)>().unwrap();
+
+            let events = backend.events.borrow();
+            let event = events
+                .iter()
+                .find(|event| event.uri == uri)
+                .expect("event should be in the backend");
+            assert_eq!(event.name, "Review");
+            assert_eq!(event.calendar, "work");
+            assert_eq!(event.end.as_second(), 1_800_003_600);
+            assert_eq!(event.time_zone.as_deref(), Some("Europe/Paris"));
+        });
+    }
+
+    #[test]
+    fn invalid_events_are_refused() {
+        run(|client, backend| async move {
+            let invalid_args = "org.freedesktop.DBus.Error.InvalidArgs";
+
+            let backwards = new_event("Review", 1_800_003_600, 1_800_000_000);
+            let reply = call(&client, "CreateEvent", ("work", backwards).to_variant()).await;
+            assert!(is_error(reply, invalid_args));
+
+            let mut unknown_zone = new_event("Review", 1_800_000_000, 1_800_003_600);
+            unknown_zone.insert("time-zone".to_owned(), "Mars/Olympus_Mons".to_variant());
+            let reply = call(&client, "CreateEvent", ("work", unknown_zone).to_variant()).await;
+            assert!(is_error(reply, invalid_args));
+
+            let mut unnamed = new_event("Review", 1_800_000_000, 1_800_003_600);
+            unnamed.remove("name");
+            let reply = call(&client, "CreateEvent", ("work", unnamed).to_variant()).await;
+            assert!(is_error(reply, invalid_args));
+
+            assert_eq!(backend.events.borrow().len(), 1);
+        });
+    }
+
+    #[test]
+    fn read_only_calendars_are_not_changed() {
+        run(|client, backend| async move {
+            let read_only = "io.gitlab.TitouanReal.CalendarManager.Error.ReadOnly";
+
+            let properties = new_event("Party", 1_800_000_000, 1_800_003_600);
+            let reply = call(
+                &client,
+                "CreateEvent",
+                ("holidays", properties).to_variant(),
+            )
+            .await;
+            assert!(is_error(reply, read_only));
+
+            let reply = call(&client, "DeleteEvent", ("holidays/new-year",).to_variant()).await;
+            assert!(is_error(reply, read_only));
+
+            let events = backend.events.borrow();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].uri, "holidays/new-year");
+        });
+    }
+}
@@ -0,0 +1,55 @@
+//! The D-Bus interfaces exported by the application, next to the ones of `GApplication`.
+
+use gtk::gio;
+use tracing::warn;
+
+mod events;
+mod search_provider;
+
+/// Export the interfaces on `connection`, the one the application is registered on at
+/// `object_path`.
+pub fn register(connection: &gio::DBusConnection, object_path: &str) {
+    if let Err(error) = search_provider::register(connection) {
+        warn!("Could not export the search provider: {error}");
+    }
+    if let Err(error) = events::register(connection, object_path) {
+        warn!("Could not export the events interface: {error}");
+    }
+}
+
+/// Two D-Bus connections to each other, to call the interfaces without a message bus.
+#[cfg(test)]
+async fn peer_connections() -> (gio::DBusConnection, gio::DBusConnection) {
+    use std::os::unix::net::UnixStream;
+
+    use gtk::{glib, prelude::*};
+
+    let (service, client) = UnixStream::pair().expect("sockets should be created");
+    let stream = |socket: UnixStream| {
+        // SAFETY: The socket is owned by the GSocket from now on.
+        unsafe { gio::Socket::from_fd(socket) }
+            .expect("socket should be wrapped")
+            .connection_factory_create_connection()
+    };
+
+    let guid = gio::dbus_generate_guid();
+    let service = glib::MainContext::default().spawn_local(gio::DBusConnection::new_future(
+        &stream(service),
+        Some(&guid),
+        gio::DBusConnectionFlags::AUTHENTICATION_SERVER,
+        None,
+    ));
+    let client = gio::DBusConnection::new_future(
+        &stream(client),
+        None,
+        gio::DBusConnectionFlags::AUTHENTICATION_CLIENT,
+        None,
+    )
+    .await
+    .expect("client should connect");
+    let service = service
+        .await
+        .expect("service should not be cancelled")
+        .expect("service should accept the client");
+    (service, client)
+}
@@ -0,0 +1,211 @@
+//! The search provider of GNOME Shell, to find events from the overview.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use ccm::{Event, Resource};
+use gtk::{gio, glib, prelude::*};
+use tracing::warn;
+
+use crate::{
+    CalendarManagerApplication, format, ics::format_color, widgets::CalendarManagerWindow,
+};
+
+/// Must match the search provider file installed in `gnome-shell/search-providers`.
+const OBJECT_PATH: &str = "/io/gitlab/TitouanReal/CalendarManager/SearchProvider";
+
+const INTERFACE_NAME: &str = "org.gnome.Shell.SearchProvider2";
+
+/// How long the application keeps running after a call, when it was started for the search.
+///
+/// GNOME Shell calls the provider again at each key press, which should not start it again.
+const INACTIVITY_TIMEOUT: Duration = Duration::from_secs(20);
+
+const INTERFACE: &str = r#"<node>
+  <interface name="org.gnome.Shell.SearchProvider2">
+    <method name="GetInitialResultSet">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetSubsearchResultSet">
+      <arg type="as" name="previous_results" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetResultMetas">
+      <arg type="as" name="identifiers" direction="in"/>
+      <arg type="aa{sv}" name="metas" direction="out"/>
+    </method>
+    <method name="ActivateResult">
+      <arg type="s" name="identifier" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+    <method name="LaunchSearch">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+  </interface>
+</node>"#;
+
+pub fn register(connection: &gio::DBusConnection) -> Result<gio::RegistrationId, glib::Error> {
+    let node = gio::DBusNodeInfo::for_xml(INTERFACE)?;
+    let interface = node
+        .lookup_interface(INTERFACE_NAME)
+        .expect("interface should be in the node");
+
+    connection
+        .register_object(OBJECT_PATH, &interface)
+        .method_call(|_, _, _, _, method, parameters, invocation| {
+            keep_running(&CalendarManagerApplication::default(), INACTIVITY_TIMEOUT);
+
+            let reply = match method {
+                "GetInitialResultSet" => parameters
+                    .get::<(Vec<String>,)>()
+                    .map(|(terms,)| (result_set(&terms),).to_variant()),
+                "GetSubsearchResultSet" => parameters.get::<(Vec<String>, Vec<String>)>().map(
+                    |(previous_results, terms)| {
+                        (narrow_results(previous_results, result_set(&terms)),).to_variant()
+                    },
+                ),
+                "GetResultMetas" => parameters
+                    .get::<(Vec<String>,)>()
+                    .map(|(ids,)| (result_metas(&ids),).to_variant()),
+                "ActivateResult" => {
+                    parameters
+                        .get::<(String, Vec<String>, u32)>()
+                        .map(|(id, _, _)| {
+                            activate_result(&id);
+                            ().to_variant()
+                        })
+                }
+                "LaunchSearch" => parameters.get::<(Vec<String>, u32)>().map(|(terms, _)| {
+                    window().search(&terms.join(" "));
+                    ().to_variant()
+                }),
+                // GDBus replies UnknownMethod itself to the methods the interface does not have
+                _ => {
+                    warn!("Unexpected call to {method}");
+                    return;
+                }
+            };
+
+            match reply {
+                Some(reply) => invocation.return_value(Some(&reply)),
+                None => {
+                    invocation.return_dbus_error("org.freedesktop.DBus.Error.InvalidArgs", method)
+                }
+            }
+        })
+        .build()
+}
+
+/// Keep `application` running for `timeout`, even if it has no window.
+fn keep_running(application: &impl IsA<gio::Application>, timeout: Duration) {
+    let guard = application.hold();
+    glib::timeout_add_local_once(timeout, move || drop(guard));
+}
+
+/// The results of a previous search that are still results, in the order they were shown.
+fn narrow_results(previous_results: Vec<String>, results: Vec<String>) -> Vec<String> {
+    let results: HashSet<String> = results.into_iter().collect();
+    previous_results
+        .into_iter()
+        .filter(|id| results.contains(id))
+        .collect()
+}
+
+/// The URIs of the events matching all `terms`.
+fn result_set(terms: &[String]) -> Vec<String> {
+    CalendarManagerApplication::default()
+        .manager()
+        .search_events(&terms.join(" "))
+        .iter::<Event>()
+        .filter_map(Result::ok)
+        .map(|event| event.uri())
+        .collect()
+}
+
+fn result_metas(ids: &[String]) -> Vec<HashMap<String, glib::Variant>> {
+    let manager = CalendarManagerApplication::default().manager();
+    ids.iter()
+        .filter_map(|id| {
+            let Some(Resource::Event(event)) = manager.find_resource(id) else {
+                return None;
+            };
+            let calendar = event.calendar();
+            let description = match event.timeframe() {
+                Some(timeframe) => {
+                    format!("{} · {}", format::timeframe(&timeframe), calendar.name())
+                }
+                None => calendar.name(),
+            };
+
+            let mut meta = HashMap::from([
+                ("id".to_owned(), id.to_variant()),
+                ("name".to_owned(), event.name().to_variant()),
+                ("description".to_owned(), description.to_variant()),
+            ]);
+            if let Some(icon) = color_icon(&format_color(&calendar.color())).serialize() {
+                meta.insert("icon".to_owned(), icon);
+            }
+            Some(meta)
+        })
+        .collect()
+}
+
+/// A disc of the color of the calendar, like in the views.
+fn color_icon(color: &str) -> gio::BytesIcon {
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16"><circle cx="8" cy="8" r="7" fill="{color}"/></svg>"#
+    );
+    gio::BytesIcon::new(&glib::Bytes::from_owned(svg.into_bytes()))
+}
+
+fn activate_result(id: &str) {
+    let window = window();
+    let manager = CalendarManagerApplication::default().manager();
+    if let Some(Resource::Event(event)) = manager.find_resource(id) {
+        window.show_event(&event);
+    }
+}
+
+/// The window of the application, opened if needed.
+fn window() -> CalendarManagerWindow {
+    let application = CalendarManagerApplication::default();
+    application.activate();
+    application
+        .active_window()
+        .and_downcast()
+        .expect("activate should have created a window")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn application_keeps_running() {
+        let application = gio::Application::new(None, gio::ApplicationFlags::NON_UNIQUE);
+        application.connect_activate(|application| {
+            keep_running(application, Duration::from_millis(200));
+        });
+
+        let start = Instant::now();
+        application.run_with_args::<&str>(&[]);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn results_are_narrowed_in_order() {
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            narrow_results(ids(&["c", "a", "b"]), ids(&["a", "c", "d"])),
+            ids(&["c", "a"])
+        );
+    }
+}
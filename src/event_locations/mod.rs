@@ -0,0 +1,105 @@
+//! The locations of events, which ccm does not store, kept by calendar URI and event UID in a
+//! data file loaded once on startup.
+//!
+//! They come from imported files, subscriptions and synced calendars, so events can be searched by
+//! location and keep it when they are exported.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use ccm::{Calendar, Event};
+use gtk::{glib, subclass::prelude::*};
+
+use crate::utils;
+
+/// The data file holding the locations of the events.
+const EVENT_LOCATIONS_FILE: &str = "event-locations";
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct EventLocations {
+        /// The locations of the events, by event UID, by calendar URI.
+        pub locations: RefCell<HashMap<String, HashMap<String, String>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EventLocations {
+        const NAME: &'static str = "EventLocations";
+        type Type = super::EventLocations;
+    }
+
+    impl ObjectImpl for EventLocations {}
+}
+
+glib::wrapper! {
+    /// The locations of all events, saved in a data file.
+    pub struct EventLocations(ObjectSubclass<imp::EventLocations>);
+}
+
+impl EventLocations {
+    pub fn new() -> Self {
+        let obj: Self = glib::Object::new();
+        obj.imp()
+            .locations
+            .replace(utils::load_data(EVENT_LOCATIONS_FILE));
+        obj
+    }
+
+    /// The location of `event`, if it has one.
+    pub fn event(&self, event: &Event) -> Option<String> {
+        self.imp()
+            .locations
+            .borrow()
+            .get(&event.calendar().uri())?
+            .get(&event.uid())
+            .cloned()
+    }
+
+    /// The locations of the events of `calendar`, by event UID.
+    pub fn calendar(&self, calendar: &Calendar) -> HashMap<String, String> {
+        self.imp()
+            .locations
+            .borrow()
+            .get(&calendar.uri())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Remember the locations of events of `calendar` by UID, or forget the ones that are `None`
+    /// or blank.
+    pub fn set_events(
+        &self,
+        calendar: &Calendar,
+        events: impl IntoIterator<Item = (String, Option<String>)>,
+    ) {
+        let mut locations = self.imp().locations.borrow_mut();
+        let uri = calendar.uri();
+        let calendar_locations = locations.entry(uri.clone()).or_default();
+        for (uid, location) in events {
+            match location.map(|location| location.trim().to_owned()) {
+                Some(location) if !location.is_empty() => calendar_locations.insert(uid, location),
+                _ => calendar_locations.remove(&uid),
+            };
+        }
+        if calendar_locations.is_empty() {
+            locations.remove(&uri);
+        }
+        utils::save_data(EVENT_LOCATIONS_FILE, &*locations);
+    }
+
+    /// Forget the locations of the events of a deleted calendar.
+    pub fn forget(&self, calendar: &Calendar) {
+        let mut locations = self.imp().locations.borrow_mut();
+        if locations.remove(&calendar.uri()).is_none() {
+            return;
+        }
+        utils::save_data(EVENT_LOCATIONS_FILE, &*locations);
+    }
+}
+
+impl Default for EventLocations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,206 @@
+//! Dates and times as shown to the user, in their language and with the 12 or 24-hour clock of
+//! the GNOME `clock-format` setting.
+//!
+//! Names and numbers come from the locale through `GDateTime`, with formats that translators can
+//! adapt to their language.
+
+use ccm::{
+    Timeframe,
+    jiff::{Timestamp, Zoned, civil, tz::TimeZone},
+};
+use gettextrs::{gettext, ngettext};
+use gtk::{gio, glib, prelude::*};
+
+use crate::CalendarManagerApplication;
+
+const INTERFACE_SCHEMA: &str = "org.gnome.desktop.interface";
+
+/// The name of `month`, from 1 to 12, as used on its own, e.g. in a title.
+pub fn month_name(month: i8) -> String {
+    strftime(civil::date(2000, month.clamp(1, 12), 1).into(), "%OB")
+}
+
+/// A month and its year, e.g. `October 2026`.
+pub fn month_year(year: i16, month: i8) -> String {
+    // Translators: A month and its year, see the GDateTime documentation for the format
+    strftime(
+        civil::date(year, month.clamp(1, 12), 1).into(),
+        &gettext("%OB %Y"),
+    )
+}
+
+/// A date with its weekday, e.g. `Mon 5 Oct 2026`.
+pub fn date(date: civil::Date) -> String {
+    // Translators: A date with its weekday, see the GDateTime documentation for the format
+    strftime(date.into(), &gettext("%a %-d %b %Y"))
+}
+
+/// A time of the day, e.g. `14:00` or `2:00 PM`.
+pub fn time(time: civil::Time) -> String {
+    let format = if uses_12_hour_clock() {
+        // Translators: A time with the 12-hour clock, see the GDateTime documentation for the
+        // format
+        gettext("%-l:%M %p")
+    } else {
+        // Translators: A time with the 24-hour clock, see the GDateTime documentation for the
+        // format
+        gettext("%H:%M")
+    };
+    strftime(civil::date(2000, 1, 1).to_datetime(time), &format)
+}
+
+/// A day relative to `today`, e.g. `Tomorrow` or `In 3 days`, or its date if it is a week away
+/// or more.
+pub fn relative_day(day: civil::Date, today: civil::Date) -> String {
+    let days = today
+        .until(day)
+        .map(|span| span.get_days())
+        .unwrap_or(i32::MAX);
+    match days {
+        0 => gettext("Today"),
+        1 => gettext("Tomorrow"),
+        -1 => gettext("Yesterday"),
+        2..=6 => ngettext("In {} day", "In {} days", days as u32).replace("{}", &days.to_string()),
+        -6..=-2 => {
+            ngettext("{} day ago", "{} days ago", -days as u32).replace("{}", &(-days).to_string())
+        }
+        _ => date(day),
+    }
+}
+
+/// A time on a day relative to today, e.g. `Tomorrow, 14:00`.
+pub fn relative_date_time(date_time: &Zoned) -> String {
+    let today = Zoned::now()
+        .with_time_zone(date_time.time_zone().clone())
+        .date();
+    // Translators: A day, e.g. “Tomorrow” or a date, and a time on that day
+    gettext("{day}, {time}")
+        .replace("{day}", &relative_day(date_time.date(), today))
+        .replace("{time}", &time(date_time.time()))
+}
+
+/// The Unix time of a sync in the display time zone, e.g. `Today, 14:00`, which is 0 if it never
+/// happened.
+pub fn sync_time(seconds: i64) -> String {
+    if seconds == 0 {
+        return gettext("Never");
+    }
+    Timestamp::from_second(seconds)
+        .map(|timestamp| {
+            relative_date_time(
+                &CalendarManagerApplication::default()
+                    .time_zones()
+                    .to_display(timestamp),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// A duration of `minutes`, e.g. `1 hour 30 minutes`.
+pub fn duration(minutes: u32) -> String {
+    let days = minutes / (24 * 60);
+    let hours = minutes / 60 % 24;
+    let minutes = minutes % 60;
+    let parts = [
+        (days > 0).then(|| ngettext("{} day", "{} days", days).replace("{}", &days.to_string())),
+        (hours > 0)
+            .then(|| ngettext("{} hour", "{} hours", hours).replace("{}", &hours.to_string())),
+        (minutes > 0).then(|| {
+            ngettext("{} minute", "{} minutes", minutes).replace("{}", &minutes.to_string())
+        }),
+    ];
+    let parts: Vec<String> = parts.into_iter().flatten().collect();
+    if parts.is_empty() {
+        ngettext("{} minute", "{} minutes", 0).replace("{}", "0")
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// When a reminder goes off, `minutes` before the start of its event, e.g. `15 minutes before`.
+pub fn reminder(minutes: u32) -> String {
+    if minutes == 0 {
+        gettext("At the start")
+    } else {
+        // Translators: When a reminder goes off, e.g. “15 minutes before”
+        gettext("{} before").replace("{}", &duration(minutes))
+    }
+}
+
+/// When an event happens in the time zone it is shown in, e.g. `Mon 5 Oct 2026, 14:00 – 15:00`.
+pub fn timeframe(timeframe: &Timeframe) -> String {
+    let time_zone = CalendarManagerApplication::default()
+        .time_zones()
+        .for_timeframe(timeframe);
+    timeframe_in(timeframe, &time_zone)
+}
+
+/// When an event happens in `time_zone`.
+pub fn timeframe_in(timeframe: &Timeframe, time_zone: &TimeZone) -> String {
+    let start = timeframe.start().timestamp().to_zoned(time_zone.clone());
+    let end = timeframe.end().timestamp().to_zoned(time_zone.clone());
+
+    if timeframe.all_day() {
+        // The end of all-day events is the start of the next day
+        let last_day = end.yesterday().unwrap_or_else(|_| end.clone()).date();
+        if last_day > start.date() {
+            range(&date(start.date()), &date(last_day))
+        } else {
+            date(start.date())
+        }
+    } else if end.date() == start.date() {
+        // Translators: A date and the times an event starts and ends on that day
+        gettext("{date}, {start} – {end}")
+            .replace("{date}", &date(start.date()))
+            .replace("{start}", &time(start.time()))
+            .replace("{end}", &time(end.time()))
+    } else {
+        range(&date_time(&start), &date_time(&end))
+    }
+}
+
+fn date_time(date_time: &Zoned) -> String {
+    // Translators: A date and a time on that date
+    gettext("{date}, {time}")
+        .replace("{date}", &date(date_time.date()))
+        .replace("{time}", &time(date_time.time()))
+}
+
+fn range(start: &str, end: &str) -> String {
+    // Translators: When something starts and ends
+    gettext("{start} – {end}")
+        .replace("{start}", start)
+        .replace("{end}", end)
+}
+
+/// Format `date_time` with the names of the locale.
+///
+/// The time is formatted as is, without any time zone.
+fn strftime(date_time: civil::DateTime, format: &str) -> String {
+    glib::DateTime::from_utc(
+        date_time.year().into(),
+        date_time.month().into(),
+        date_time.day().into(),
+        date_time.hour().into(),
+        date_time.minute().into(),
+        date_time.second().into(),
+    )
+    .and_then(|date_time| date_time.format(format))
+    .map(String::from)
+    .unwrap_or_default()
+}
+
+/// Whether the user chose the 12-hour clock in the GNOME settings, which may not be installed.
+fn uses_12_hour_clock() -> bool {
+    thread_local! {
+        static INTERFACE_SETTINGS: Option<gio::Settings> = gio::SettingsSchemaSource::default()
+            .and_then(|source| source.lookup(INTERFACE_SCHEMA, true))
+            .map(|_| gio::Settings::new(INTERFACE_SCHEMA));
+    }
+
+    INTERFACE_SETTINGS.with(|settings| {
+        settings
+            .as_ref()
+            .is_some_and(|settings| settings.string("clock-format") == "12h")
+    })
+}
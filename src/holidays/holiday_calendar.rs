@@ -0,0 +1,78 @@
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+};
+
+use ccm::jiff::civil;
+use gtk::{glib, prelude::*, subclass::prelude::*};
+
+use super::rules::{self, Holiday, Rule};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::HolidayCalendar)]
+    pub struct HolidayCalendar {
+        /// The code of the region, e.g. `fr`, which is the name of its file in the resources.
+        #[property(get, set, construct_only)]
+        code: RefCell<String>,
+        #[property(get, set, construct_only)]
+        name: RefCell<String>,
+        /// Whether the holidays are shown in the views.
+        #[property(get, set)]
+        visible: Cell<bool>,
+        pub rules: OnceCell<Vec<Rule>>,
+        /// The holidays of the years already computed.
+        pub years: RefCell<HashMap<i16, Vec<Holiday>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for HolidayCalendar {
+        const NAME: &'static str = "HolidayCalendar";
+        type Type = super::HolidayCalendar;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for HolidayCalendar {}
+}
+
+glib::wrapper! {
+    /// The public holidays of a region, computed from their rules for any year.
+    pub struct HolidayCalendar(ObjectSubclass<imp::HolidayCalendar>);
+}
+
+impl HolidayCalendar {
+    pub fn new(code: &str, name: &str, rules: Vec<Rule>) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("code", code)
+            .property("name", name)
+            .build();
+        obj.imp()
+            .rules
+            .set(rules)
+            .expect("rules should only be set once");
+        obj
+    }
+
+    /// The holidays from `start` to `end`, excluded, by date.
+    pub fn between(&self, start: civil::Date, end: civil::Date) -> Vec<Holiday> {
+        // Substitute days can fall in the year before or after the holiday
+        (start.year() - 1..=end.year() + 1)
+            .flat_map(|year| self.year(year))
+            .filter(|holiday| start <= holiday.date && holiday.date < end)
+            .collect()
+    }
+
+    fn year(&self, year: i16) -> Vec<Holiday> {
+        let imp = self.imp();
+        imp.years
+            .borrow_mut()
+            .entry(year)
+            .or_insert_with(|| {
+                let rules = imp.rules.get().expect("rules should be initialized");
+                rules::holidays(rules, year)
+            })
+            .clone()
+    }
+}
@@ -0,0 +1,167 @@
+//! Public holidays of countries and regions, computed offline from rules bundled in the resources.
+
+use std::{cell::OnceCell, sync::LazyLock};
+
+use ccm::jiff::civil;
+use gtk::{
+    gio,
+    glib::{self, clone, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+use tracing::warn;
+
+mod holiday_calendar;
+mod rules;
+
+pub use self::holiday_calendar::HolidayCalendar;
+
+/// Where the rules of each region are bundled, as `<code>.holidays` key files.
+const RESOURCE_PATH: &str = "/io/gitlab/TitouanReal/CalendarManager/holidays/";
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Holidays {
+        pub settings: OnceCell<gio::Settings>,
+        pub model: OnceCell<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Holidays {
+        const NAME: &'static str = "Holidays";
+        type Type = super::Holidays;
+    }
+
+    impl ObjectImpl for Holidays {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("changed").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The holiday calendars of all bundled regions, and the ones the user shows.
+    pub struct Holidays(ObjectSubclass<imp::Holidays>);
+}
+
+impl Holidays {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+        imp.settings
+            .set(settings.clone())
+            .expect("settings should only be set once");
+
+        let shown = settings.strv("holiday-calendars");
+        let mut calendars = bundled_calendars();
+        calendars.sort_by_key(|calendar| calendar.name().to_lowercase());
+
+        let model = gio::ListStore::new::<HolidayCalendar>();
+        for calendar in calendars {
+            calendar.set_visible(shown.iter().any(|code| *code == calendar.code()));
+            calendar.connect_visible_notify(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.save();
+                    obj.emit_by_name::<()>("changed", &[]);
+                }
+            ));
+            model.append(&calendar);
+        }
+        imp.model.set(model).expect("model should only be set once");
+
+        obj
+    }
+
+    pub fn model(&self) -> gio::ListStore {
+        self.imp()
+            .model
+            .get()
+            .expect("model should be initialized")
+            .clone()
+    }
+
+    /// The holidays of the shown calendars from `start` to `end`, excluded, by date.
+    pub fn between(&self, start: civil::Date, end: civil::Date) -> Vec<Holiday> {
+        let mut holidays: Vec<Holiday> = self
+            .model()
+            .iter::<HolidayCalendar>()
+            .filter_map(Result::ok)
+            .filter(|calendar| calendar.visible())
+            .flat_map(|calendar| calendar.between(start, end))
+            .collect();
+        holidays.sort_by_key(|holiday| holiday.date);
+        // Regions sharing a holiday show it once
+        holidays.dedup();
+        holidays
+    }
+
+    /// Call `f` when calendars are shown or hidden.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            false,
+            closure_local!(move |obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    fn save(&self) {
+        let codes: Vec<String> = self
+            .model()
+            .iter::<HolidayCalendar>()
+            .filter_map(Result::ok)
+            .filter(|calendar| calendar.visible())
+            .map(|calendar| calendar.code())
+            .collect();
+
+        let settings = self
+            .imp()
+            .settings
+            .get()
+            .expect("settings should be initialized");
+        if let Err(error) = settings.set_strv("holiday-calendars", codes) {
+            warn!("Could not save the shown holiday calendars: {error}");
+        }
+    }
+}
+
+/// The calendars of the regions whose rules are in the resources.
+fn bundled_calendars() -> Vec<HolidayCalendar> {
+    let files =
+        match gio::resources_enumerate_children(RESOURCE_PATH, gio::ResourceLookupFlags::NONE) {
+            Ok(files) => files,
+            Err(error) => {
+                warn!("Could not list the holiday calendars: {error}");
+                return Vec::new();
+            }
+        };
+
+    files
+        .iter()
+        .filter_map(|file| {
+            let code = file.strip_suffix(".holidays")?;
+            let key_file = glib::KeyFile::new();
+            let loaded = gio::resources_lookup_data(
+                &format!("{RESOURCE_PATH}{file}"),
+                gio::ResourceLookupFlags::NONE,
+            )
+            .and_then(|data| key_file.load_from_bytes(&data, glib::KeyFileFlags::NONE));
+            if let Err(error) = loaded {
+                warn!("Could not load the holidays of {code}: {error}");
+                return None;
+            }
+            let Some((name, rules)) = rules::parse(&key_file) else {
+                warn!("Ignoring the holidays of {code} without a region name");
+                return None;
+            };
+            Some(HolidayCalendar::new(code, &name, rules))
+        })
+        .collect()
+}
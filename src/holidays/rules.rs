@@ -0,0 +1,370 @@
+use ccm::jiff::{
+    Span,
+    civil::{self, Weekday},
+};
+use gettextrs::gettext;
+use gtk::glib;
+
+use crate::ics;
+
+/// When a holiday falls in a year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Day {
+    /// The same date each year.
+    Fixed { month: i8, day: i8 },
+    /// A number of days after Easter Sunday, or before it if negative.
+    Easter { offset: i16 },
+    /// The nth weekday of a month, counted from the end of the month if negative.
+    NthWeekday {
+        month: i8,
+        nth: i8,
+        weekday: Weekday,
+    },
+}
+
+/// The day off given instead of a holiday falling on a weekend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Substitute {
+    /// The next weekday that is not already a holiday.
+    NextWeekday,
+    /// The Friday before a Saturday, or the Monday after a Sunday.
+    NearestWeekday,
+}
+
+/// A holiday of a region, as written in its key file.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub day: Day,
+    pub substitute: Option<Substitute>,
+    /// The first year the holiday was observed.
+    pub since: Option<i16>,
+    /// The last year the holiday was observed.
+    pub until: Option<i16>,
+}
+
+/// A holiday in a given year.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holiday {
+    pub date: civil::Date,
+    pub name: String,
+}
+
+/// The name of a region and the rules of its holidays, read from a key file like:
+///
+/// ```ini
+/// [Region]
+/// Name=United Kingdom
+///
+/// [Holiday christmas]
+/// Name=Christmas Day
+/// Month=12
+/// Day=25
+/// Substitute=next-weekday
+///
+/// [Holiday good-friday]
+/// Name=Good Friday
+/// Easter=-2
+///
+/// [Holiday spring-bank-holiday]
+/// Name=Spring Bank Holiday
+/// Month=5
+/// Day=-1MO
+/// Since=1971
+/// ```
+///
+/// `Day` is either a day of the month or a weekday with its ordinal, as in the `BYDAY` of
+/// iCalendar recurrence rules. Names can be translated with the usual `Name[fr]=` keys.
+pub fn parse(key_file: &glib::KeyFile) -> Option<(String, Vec<Rule>)> {
+    let name = key_file.locale_string("Region", "Name", None).ok()?;
+    let rules = key_file
+        .groups()
+        .iter()
+        .filter(|group| group.starts_with("Holiday "))
+        .filter_map(|group| parse_rule(key_file, group))
+        .collect();
+    Some((name.into(), rules))
+}
+
+fn parse_rule(key_file: &glib::KeyFile, group: &str) -> Option<Rule> {
+    let string = |key: &str| key_file.string(group, key).ok();
+    let integer = |key: &str| key_file.integer(group, key).ok();
+
+    let day = if let Some(offset) = integer("Easter") {
+        Day::Easter {
+            offset: offset.try_into().ok()?,
+        }
+    } else {
+        let month = integer("Month")?.try_into().ok()?;
+        let day = string("Day")?;
+        match day.parse() {
+            Ok(day) => Day::Fixed { month, day },
+            Err(_) => {
+                let (nth, weekday) = ics::parse_weekday_num(&day)?;
+                Day::NthWeekday {
+                    month,
+                    nth: nth.unwrap_or(1),
+                    weekday,
+                }
+            }
+        }
+    };
+    let substitute = match string("Substitute").as_deref() {
+        None => None,
+        Some("next-weekday") => Some(Substitute::NextWeekday),
+        Some("nearest-weekday") => Some(Substitute::NearestWeekday),
+        Some(_) => return None,
+    };
+
+    Some(Rule {
+        name: key_file.locale_string(group, "Name", None).ok()?.into(),
+        day,
+        substitute,
+        since: integer("Since").and_then(|year| year.try_into().ok()),
+        until: integer("Until").and_then(|year| year.try_into().ok()),
+    })
+}
+
+/// The holidays of `year` following `rules`, by date, with their substitute days.
+pub fn holidays(rules: &[Rule], year: i16) -> Vec<Holiday> {
+    let observed = rules.iter().filter(|rule| {
+        rule.since.is_none_or(|since| since <= year) && rule.until.is_none_or(|until| year <= until)
+    });
+
+    let mut holidays: Vec<(Holiday, Option<Substitute>)> = observed
+        .filter_map(|rule| {
+            let date = date(rule.day, year)?;
+            Some((
+                Holiday {
+                    date,
+                    name: rule.name.clone(),
+                },
+                rule.substitute,
+            ))
+        })
+        .collect();
+    holidays.sort_by_key(|(holiday, _)| holiday.date);
+
+    let mut days_off: Vec<civil::Date> = holidays.iter().map(|(holiday, _)| holiday.date).collect();
+    let mut substitutes = Vec::new();
+    for (holiday, substitute) in &holidays {
+        let Some(substitute) = substitute else {
+            continue;
+        };
+        let Some(date) = substitute_date(holiday.date, *substitute, &days_off) else {
+            continue;
+        };
+        days_off.push(date);
+        substitutes.push(Holiday {
+            date,
+            // Translators: The day off given instead of a holiday falling on a weekend
+            name: gettext("{} (substitute day)").replace("{}", &holiday.name),
+        });
+    }
+
+    let mut holidays: Vec<Holiday> = holidays
+        .into_iter()
+        .map(|(holiday, _)| holiday)
+        .chain(substitutes)
+        .collect();
+    holidays.sort_by_key(|holiday| holiday.date);
+    holidays
+}
+
+fn date(day: Day, year: i16) -> Option<civil::Date> {
+    match day {
+        Day::Fixed { month, day } => civil::Date::new(year, month, day).ok(),
+        Day::Easter { offset } => easter(year)?.checked_add(Span::new().days(offset)).ok(),
+        Day::NthWeekday {
+            month,
+            nth,
+            weekday,
+        } => civil::Date::new(year, month, 1)
+            .ok()?
+            .nth_weekday_of_month(nth, weekday)
+            .ok(),
+    }
+}
+
+/// The day off for a holiday on `date`, if it falls on a weekend.
+fn substitute_date(
+    date: civil::Date,
+    substitute: Substitute,
+    days_off: &[civil::Date],
+) -> Option<civil::Date> {
+    let is_weekend =
+        |date: civil::Date| matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday);
+    if !is_weekend(date) {
+        return None;
+    }
+    match substitute {
+        Substitute::NextWeekday => {
+            std::iter::successors(date.tomorrow().ok(), |date| date.tomorrow().ok())
+                .find(|date| !is_weekend(*date) && !days_off.contains(date))
+        }
+        Substitute::NearestWeekday => match date.weekday() {
+            Weekday::Saturday => date.yesterday().ok(),
+            _ => date.tomorrow().ok(),
+        },
+    }
+}
+
+/// Easter Sunday of `year` in the Gregorian calendar, with the anonymous Gregorian algorithm.
+fn easter(year: i16) -> Option<civil::Date> {
+    let year = i32::from(year);
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    civil::Date::new(year as i16, month as i8, day as i8).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::civil::date;
+
+    use super::*;
+
+    fn region(file: &str) -> Vec<Rule> {
+        let key_file = glib::KeyFile::new();
+        key_file
+            .load_from_data(file, glib::KeyFileFlags::NONE)
+            .expect("holidays should be a key file");
+        parse(&key_file).expect("holidays should parse").1
+    }
+
+    fn names_between(
+        rules: &[Rule],
+        start: civil::Date,
+        end: civil::Date,
+    ) -> Vec<(civil::Date, String)> {
+        holidays(rules, start.year())
+            .into_iter()
+            .filter(|holiday| start <= holiday.date && holiday.date <= end)
+            .map(|holiday| (holiday.date, holiday.name))
+            .collect()
+    }
+
+    #[test]
+    fn easter_is_computed() {
+        assert_eq!(easter(1818), Some(date(1818, 3, 22)));
+        assert_eq!(easter(2019), Some(date(2019, 4, 21)));
+        assert_eq!(easter(2024), Some(date(2024, 3, 31)));
+        assert_eq!(easter(2026), Some(date(2026, 4, 5)));
+        assert_eq!(easter(2038), Some(date(2038, 4, 25)));
+    }
+
+    #[test]
+    fn rules_are_parsed() {
+        let rules = region(include_str!("../../data/resources/holidays/gb.holidays"));
+        let spring = rules
+            .iter()
+            .find(|rule| rule.name == "Spring Bank Holiday")
+            .unwrap();
+        assert_eq!(
+            spring.day,
+            Day::NthWeekday {
+                month: 5,
+                nth: -1,
+                weekday: Weekday::Monday
+            }
+        );
+        assert_eq!(spring.since, Some(1971));
+
+        let good_friday = rules
+            .iter()
+            .find(|rule| rule.name == "Good Friday")
+            .unwrap();
+        assert_eq!(good_friday.day, Day::Easter { offset: -2 });
+
+        let invalid = region(
+            "[Region]\nName=Test\n\n[Holiday x]\nName=X\nMonth=1\nDay=1\nSubstitute=never\n",
+        );
+        assert!(invalid.is_empty());
+    }
+
+    #[test]
+    fn english_holidays() {
+        let rules = region(include_str!("../../data/resources/holidays/gb.holidays"));
+        let dates: Vec<civil::Date> = holidays(&rules, 2026)
+            .into_iter()
+            .map(|holiday| holiday.date)
+            .collect();
+        assert_eq!(
+            dates,
+            [
+                date(2026, 1, 1),
+                date(2026, 4, 3),
+                date(2026, 4, 6),
+                date(2026, 5, 4),
+                date(2026, 5, 25),
+                date(2026, 8, 31),
+                date(2026, 12, 25),
+                // Boxing Day is a Saturday
+                date(2026, 12, 26),
+                date(2026, 12, 28),
+            ]
+        );
+
+        // Bank holidays that did not exist yet
+        assert!(
+            holidays(&rules, 1970)
+                .iter()
+                .all(|holiday| holiday.date.month() != 5)
+        );
+    }
+
+    #[test]
+    fn christmas_on_a_weekend_has_substitutes() {
+        let rules = region(include_str!("../../data/resources/holidays/gb.holidays"));
+
+        // Christmas on a Saturday and Boxing Day on a Sunday
+        assert_eq!(
+            names_between(&rules, date(2027, 12, 24), date(2027, 12, 31)),
+            [
+                (date(2027, 12, 25), "Christmas Day"),
+                (date(2027, 12, 26), "Boxing Day"),
+                (date(2027, 12, 27), "Christmas Day (substitute day)"),
+                (date(2027, 12, 28), "Boxing Day (substitute day)"),
+            ]
+            .map(|(date, name)| (date, name.to_owned()))
+        );
+
+        // Christmas on a Sunday, the next Monday is already Boxing Day
+        assert_eq!(
+            names_between(&rules, date(2022, 12, 24), date(2022, 12, 31)),
+            [
+                (date(2022, 12, 25), "Christmas Day"),
+                (date(2022, 12, 26), "Boxing Day"),
+                (date(2022, 12, 27), "Christmas Day (substitute day)"),
+            ]
+            .map(|(date, name)| (date, name.to_owned()))
+        );
+    }
+
+    #[test]
+    fn nearest_weekday_substitutes() {
+        let rules = region(include_str!("../../data/resources/holidays/us.holidays"));
+        // Independence Day on a Saturday, then on a Sunday
+        assert!(
+            names_between(&rules, date(2026, 7, 3), date(2026, 7, 3))
+                .iter()
+                .any(|(_, name)| name == "Independence Day (substitute day)")
+        );
+        assert!(
+            names_between(&rules, date(2027, 7, 5), date(2027, 7, 5))
+                .iter()
+                .any(|(_, name)| name == "Independence Day (substitute day)")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use ccm::{
+    Calendar, Collection, Event,
+    jiff::{SignedDuration, tz::TimeZone},
+};
+use gtk::{gdk, prelude::*};
+
+use super::{AlarmTrigger, VAlarm, VCalendar, VEvent};
+use crate::CalendarManagerApplication;
+
+impl VCalendar {
+    pub fn from_calendar(calendar: &Calendar) -> Self {
+        Self {
+            name: Some(calendar.name()),
+            color: Some(format_color(&calendar.color())),
+            events: calendar_events(calendar, None),
+            ..Default::default()
+        }
+    }
+
+    /// All the events of the collection. Since they all end up in the same file, the color of
+    /// their calendar is kept on each event.
+    pub fn from_collection(collection: &Collection) -> Self {
+        let events = collection
+            .calendars()
+            .iter::<Calendar>()
+            .filter_map(Result::ok)
+            .flat_map(|calendar| {
+                let color = format_color(&calendar.color());
+                calendar_events(&calendar, Some(color))
+            })
+            .collect();
+
+        Self {
+            name: Some(collection.name()),
+            color: None,
+            events,
+            ..Default::default()
+        }
+    }
+}
+
+impl VEvent {
+    /// Returns `None` for events without a timeframe, which cannot be represented.
+    ///
+    /// Events do not say whether they are busy time, so the setting of their calendar is used.
+    pub fn from_event(event: &Event, color: Option<String>) -> Option<Self> {
+        let timeframe = event.timeframe()?;
+        // All-day events are the same days in every time zone
+        let time_zone = if timeframe.all_day() {
+            TimeZone::system()
+        } else {
+            CalendarManagerApplication::default()
+                .time_zones()
+                .event(event)
+        };
+
+        Some(Self {
+            uid: event.uid(),
+            summary: event.name(),
+            description: event.description(),
+            location: CalendarManagerApplication::default()
+                .event_locations()
+                .event(event)
+                .unwrap_or_default(),
+            color,
+            start: timeframe.start().timestamp().to_zoned(time_zone.clone()),
+            end: timeframe.end().timestamp().to_zoned(time_zone),
+            all_day: timeframe.all_day(),
+            transparent: !CalendarManagerApplication::default()
+                .calendar_properties()
+                .get(&event.calendar())
+                .busy,
+            rrule: None,
+            exdates: Vec::new(),
+            recurrence_id: None,
+            alarms: Vec::new(),
+        })
+    }
+}
+
+/// The events of `calendar`, with the default reminders of the calendar, as events do not have
+/// their own.
+fn calendar_events(calendar: &Calendar, color: Option<String>) -> Vec<VEvent> {
+    let reminders = CalendarManagerApplication::default()
+        .calendar_properties()
+        .get(calendar)
+        .reminders;
+    calendar
+        .events()
+        .iter::<Event>()
+        .filter_map(Result::ok)
+        .filter_map(|event| VEvent::from_event(&event, color.clone()))
+        .map(|mut event| {
+            event.alarms = reminders
+                .iter()
+                .map(|minutes| VAlarm {
+                    action: "DISPLAY".to_owned(),
+                    description: event.summary.clone(),
+                    trigger: AlarmTrigger::Relative(SignedDuration::from_mins(-i64::from(
+                        *minutes,
+                    ))),
+                })
+                .collect();
+            event
+        })
+        .collect()
+}
+
+/// Format `color` as `#rrggbb`, the format used by `X-APPLE-CALENDAR-COLOR` and other calendar
+/// stores.
+pub fn format_color(color: &gdk::RGBA) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue())
+    )
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+
+use ccm::{Calendar, Event, Timeframe, jiff};
+use gtk::prelude::*;
+use tracing::warn;
+
+use super::{VCalendar, VEvent};
+use crate::{CalendarManagerApplication, utils::wait_for_event};
+
+/// How far in the future recurring events without an end are expanded.
+const RECURRENCE_HORIZON_YEARS: i64 = 2;
+
+/// How far in the past the occurrences of recurring events are kept.
+const RECURRENCE_HISTORY_YEARS: i64 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub skipped: usize,
+    /// The number of added or updated events whose alarms are left out, as ccm does not store
+    /// alarms.
+    pub dropped_alarms: usize,
+    /// The number of recurring events left out because their repetition rule is not supported.
+    pub unsupported_recurrences: usize,
+}
+
+/// The changes importing a file into a calendar would make.
+///
+/// Events are matched with the ones already in the calendar by UID, so importing the same file
+/// twice updates the events instead of duplicating them.
+#[derive(Debug)]
+pub struct ImportPlan {
+    calendar: Calendar,
+    additions: Vec<VEvent>,
+    updates: Vec<(Event, VEvent)>,
+    removals: Vec<Event>,
+    skipped: usize,
+    unsupported_recurrences: usize,
+}
+
+impl ImportPlan {
+    pub fn new(vcalendar: &VCalendar, calendar: &Calendar) -> Self {
+        let existing_events: HashMap<String, Event> = calendar
+            .events()
+            .iter::<Event>()
+            .filter_map(Result::ok)
+            .map(|event| (event.uid(), event))
+            .collect();
+        let locations = CalendarManagerApplication::default()
+            .event_locations()
+            .calendar(calendar);
+
+        let mut plan = Self {
+            calendar: calendar.clone(),
+            additions: Vec::new(),
+            updates: Vec::new(),
+            removals: Vec::new(),
+            skipped: vcalendar.invalid_events,
+            unsupported_recurrences: vcalendar.unsupported_recurrences,
+        };
+
+        for event in expand_recurrences(vcalendar) {
+            match existing_events.get(&event.uid) {
+                None => plan.additions.push(event),
+                Some(existing) if !is_up_to_date(existing, &event, &locations) => {
+                    plan.updates.push((existing.clone(), event))
+                }
+                Some(_) => plan.skipped += 1,
+            }
+        }
+
+        plan
+    }
+
+    /// Like [`ImportPlan::new`], but also remove the events of the calendar that are not in the
+    /// file anymore, so the calendar ends up with the same events as the file.
+    pub fn mirror(vcalendar: &VCalendar, calendar: &Calendar) -> Self {
+        let mut plan = Self::new(vcalendar, calendar);
+
+        let uids_in_file: HashSet<String> = expand_recurrences(vcalendar)
+            .into_iter()
+            .map(|event| event.uid)
+            .collect();
+        plan.removals = calendar
+            .events()
+            .iter::<Event>()
+            .filter_map(Result::ok)
+            .filter(|event| !uids_in_file.contains(&event.uid()))
+            .collect();
+
+        plan
+    }
+
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    pub fn summary(&self) -> ImportSummary {
+        let imported = || {
+            self.additions
+                .iter()
+                .chain(self.updates.iter().map(|(_, event)| event))
+        };
+        ImportSummary {
+            added: self.additions.len(),
+            updated: self.updates.len(),
+            removed: self.removals.len(),
+            skipped: self.skipped,
+            dropped_alarms: imported().filter(|event| !event.alarms.is_empty()).count(),
+            unsupported_recurrences: self.unsupported_recurrences,
+        }
+    }
+
+    /// Make the changes, returning once the added events are in the calendar.
+    pub async fn apply(self) {
+        // Saved first, so the locations are known once the events change
+        let locations = self
+            .additions
+            .iter()
+            .chain(self.updates.iter().map(|(_, event)| event))
+            .map(|event| (event.uid.clone(), Some(event.location.clone())))
+            .chain(self.removals.iter().map(|event| (event.uid(), None)));
+        CalendarManagerApplication::default()
+            .event_locations()
+            .set_events(&self.calendar, locations.collect::<Vec<_>>());
+
+        let mut time_zones = Vec::new();
+        let mut added_time_zones = Vec::new();
+        for event in self.additions {
+            added_time_zones.push((event.uid.clone(), time_zone_name(&event)));
+            self.calendar.import_event(
+                &event.uid,
+                &event.summary,
+                &event.description,
+                timeframe(&event),
+            );
+        }
+
+        for (existing, event) in self.updates {
+            time_zones.push((existing.uri(), time_zone_name(&event)));
+            existing.update(
+                Some(&event.summary),
+                Some(&event.description),
+                Some(timeframe(&event)),
+            );
+        }
+
+        for event in self.removals {
+            time_zones.push((event.uri(), None));
+            event.delete();
+        }
+
+        // The URIs of the added events are only known once they are in the calendar, which
+        // happens after import_event returns
+        for (uid, name) in added_time_zones {
+            match wait_for_event(&self.calendar, &uid).await {
+                Some(event) => time_zones.push((event.uri(), name)),
+                None => warn!("Could not find the imported event {uid} to set its time zone"),
+            }
+        }
+        CalendarManagerApplication::default()
+            .time_zones()
+            .set_events(time_zones);
+    }
+}
+
+/// The IANA time zone of the times of `event`, if they are not all-day, UTC or floating times.
+fn time_zone_name(event: &VEvent) -> Option<String> {
+    if event.all_day {
+        return None;
+    }
+    event
+        .start
+        .time_zone()
+        .iana_name()
+        .filter(|name| *name != "UTC")
+        .map(String::from)
+}
+
+fn timeframe(event: &VEvent) -> Timeframe {
+    Timeframe::new(
+        event.start.timestamp(),
+        event.end.timestamp(),
+        event.all_day,
+    )
+}
+
+/// Whether `existing` has the properties of `event`, with `locations` the ones of its calendar by
+/// UID.
+fn is_up_to_date(existing: &Event, event: &VEvent, locations: &HashMap<String, String>) -> bool {
+    existing.name() == event.summary
+        && locations.get(&existing.uid()).map_or("", String::as_str) == event.location.trim()
+        && existing.description() == event.description
+        && existing.timeframe().is_some_and(|timeframe| {
+            timeframe.all_day() == event.all_day
+                && timeframe.start().timestamp() == event.start.timestamp()
+                && timeframe.end().timestamp() == event.end.timestamp()
+        })
+}
+
+/// Replace recurring events by their occurrences.
+///
+/// ccm stores single events, so each occurrence gets its own UID derived from the UID of the
+/// series and the start of the occurrence. Occurrences overridden with a `RECURRENCE-ID` use the
+/// overriding event. When a UID appears several times, the last event wins.
+///
+/// Only the occurrences from a year ago to two years from now are kept, so series that started
+/// long ago do not fill the calendar with past events.
+pub fn expand_recurrences(vcalendar: &VCalendar) -> Vec<VEvent> {
+    let now = jiff::Timestamp::now();
+    let from = now
+        .checked_sub(jiff::SignedDuration::from_hours(
+            24 * 366 * RECURRENCE_HISTORY_YEARS,
+        ))
+        .unwrap_or(jiff::Timestamp::MIN);
+    let limit = now
+        .checked_add(jiff::SignedDuration::from_hours(
+            24 * 366 * RECURRENCE_HORIZON_YEARS,
+        ))
+        .unwrap_or(jiff::Timestamp::MAX);
+
+    let overrides: HashMap<(&str, jiff::Timestamp), &VEvent> = vcalendar
+        .events
+        .iter()
+        .filter_map(|event| {
+            let recurrence_id = event.recurrence_id.as_ref()?;
+            Some(((event.uid.as_str(), recurrence_id.timestamp()), event))
+        })
+        .collect();
+
+    let mut events: Vec<VEvent> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut push = |event: VEvent| match positions.get(&event.uid) {
+        Some(position) => events[*position] = event,
+        None => {
+            positions.insert(event.uid.clone(), events.len());
+            events.push(event);
+        }
+    };
+
+    for event in &vcalendar.events {
+        if let Some(recurrence_id) = &event.recurrence_id {
+            // Overrides are imported with their series, unless the series is missing
+            let has_series = vcalendar
+                .events
+                .iter()
+                .any(|master| master.uid == event.uid && master.rrule.is_some());
+            if !has_series {
+                push(occurrence(event, event, recurrence_id));
+            }
+            continue;
+        }
+
+        let Some(rrule) = &event.rrule else {
+            push(event.clone());
+            continue;
+        };
+
+        for start in rrule.occurrences(&event.start, from, limit) {
+            if event.exdates.contains(&start) {
+                continue;
+            }
+            let source = overrides
+                .get(&(event.uid.as_str(), start.timestamp()))
+                .copied()
+                .unwrap_or(event);
+            push(occurrence(event, source, &start));
+        }
+    }
+
+    events
+}
+
+/// An occurrence of `series` starting at `start`, with the details of `source`.
+fn occurrence(series: &VEvent, source: &VEvent, start: &jiff::Zoned) -> VEvent {
+    let mut occurrence = source.clone();
+    occurrence.uid = format!("{}-{}", series.uid, start.strftime("%Y%m%dT%H%M%S"));
+    occurrence.rrule = None;
+    occurrence.exdates.clear();
+    occurrence.recurrence_id = None;
+
+    if std::ptr::eq(series, source) {
+        // Not overridden, move the series to this occurrence
+        let duration = series.end.duration_since(&series.start);
+        let days = series
+            .start
+            .date()
+            .until(series.end.date())
+            .unwrap_or_default();
+        occurrence.start = start.clone();
+        occurrence.end = if series.all_day {
+            start.checked_add(days).unwrap_or_else(|_| start.clone())
+        } else {
+            start
+                .checked_add(duration)
+                .unwrap_or_else(|_| start.clone())
+        };
+    }
+
+    occurrence
+}
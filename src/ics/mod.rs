@@ -0,0 +1,114 @@
+//! Reading and writing of iCalendar (RFC 5545) data.
+
+use ccm::jiff::{self, SignedDuration};
+
+mod color;
+mod export;
+mod import;
+mod parser;
+mod patch;
+mod recurrence;
+mod timezone;
+mod writer;
+
+pub use self::{
+    export::format_color,
+    import::{ImportPlan, ImportSummary, expand_recurrences},
+    parser::parse,
+    patch::patch_event,
+    recurrence::{RecurrenceRule, parse_weekday_num},
+    writer::write,
+};
+
+/// The content of an iCalendar file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VCalendar {
+    /// The calendar name, from `X-WR-CALNAME` or `NAME`.
+    pub name: Option<String>,
+    /// The calendar color, as found in `X-APPLE-CALENDAR-COLOR` or `COLOR`.
+    pub color: Option<String>,
+    pub events: Vec<VEvent>,
+    /// The number of `VEVENT` components that were left out because they could not be
+    /// understood, e.g. because they have no start date.
+    pub invalid_events: usize,
+    /// The number of recurring events that were left out, with their overridden occurrences,
+    /// because their `RRULE` cannot be expanded exactly.
+    pub unsupported_recurrences: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: String,
+    pub location: String,
+    /// The color of the event, as found in `COLOR`.
+    ///
+    /// Only CSS3 color names are written, so other colors are written as the closest name.
+    pub color: Option<String>,
+    pub start: jiff::Zoned,
+    /// The non-inclusive end of the event.
+    pub end: jiff::Zoned,
+    pub all_day: bool,
+    /// Whether the event is shown as free time rather than busy time, from `TRANSP`.
+    pub transparent: bool,
+    pub rrule: Option<RecurrenceRule>,
+    pub exdates: Vec<jiff::Zoned>,
+    /// Set on events that override a single occurrence of a recurring event.
+    pub recurrence_id: Option<jiff::Zoned>,
+    pub alarms: Vec<VAlarm>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VAlarm {
+    pub action: String,
+    pub description: String,
+    pub trigger: AlarmTrigger,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlarmTrigger {
+    /// An offset relative to the start of the event.
+    Relative(SignedDuration),
+    Absolute(jiff::Timestamp),
+}
+
+/// An event as servers store it, with a time zone, parameters and properties that are not read.
+#[cfg(test)]
+pub(crate) const TEST_OBJECT: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Example//Server//EN\r
+BEGIN:VTIMEZONE\r
+TZID:Europe/Paris\r
+BEGIN:STANDARD\r
+DTSTART:19701025T030000\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+END:STANDARD\r
+END:VTIMEZONE\r
+BEGIN:VEVENT\r
+UID:review@example.com\r
+DTSTAMP:20260101T000000Z\r
+DTSTART;TZID=Europe/Paris:20261020T100000\r
+DURATION:PT1H\r
+SUMMARY;LANGUAGE=en:Quarterly review\r
+LOCATION:Room 4\\; 2nd floor\r
+ATTENDEE;CN=\"Doe, Jane\";PARTSTAT=ACCEPTED:mailto:jane@example.com\r
+X-CUSTOM:kept\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+DESCRIPTION:Review\r
+TRIGGER:-PT15M\r
+END:VALARM\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+/// The event of [`TEST_OBJECT`].
+#[cfg(test)]
+pub(crate) fn test_event() -> VEvent {
+    parse(TEST_OBJECT)
+        .expect("test object should parse")
+        .events
+        .remove(0)
+}
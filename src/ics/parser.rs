@@ -0,0 +1,604 @@
+use std::{collections::HashSet, fmt};
+
+use ccm::jiff::{self, SignedDuration, Span, SpanRelativeTo, civil, tz::TimeZone};
+use tracing::warn;
+
+use super::{AlarmTrigger, RecurrenceRule, VAlarm, VCalendar, VEvent, timezone::Zones};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The data does not contain a `VCALENDAR` component.
+    MissingCalendar,
+    /// A component is opened but never closed.
+    UnterminatedComponent(String),
+    /// An `END` line does not close the innermost open component.
+    UnexpectedEnd { line: usize, name: String },
+    /// A content line has no value.
+    InvalidLine(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingCalendar => write!(f, "no VCALENDAR component found"),
+            Self::UnterminatedComponent(name) => write!(f, "component {name} is never closed"),
+            Self::UnexpectedEnd { line, name } => {
+                write!(f, "line {line}: unexpected end of component {name}")
+            }
+            Self::InvalidLine(line) => write!(f, "line {line}: invalid content line"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone)]
+pub(super) struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub children: Vec<Component>,
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    pub fn properties<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |property| property.name == name)
+    }
+
+    /// The unescaped value of a text property, or an empty string if it is absent.
+    pub fn text(&self, name: &str) -> String {
+        self.property(name)
+            .map(|property| unescape_text(&property.value))
+            .unwrap_or_default()
+    }
+}
+
+/// Parse the content of an iCalendar file.
+///
+/// Events that cannot be understood are counted in [`VCalendar::invalid_events`] instead of
+/// failing the whole file. Recurring events whose rule is not supported are counted in
+/// [`VCalendar::unsupported_recurrences`] rather than imported with the wrong occurrences.
+pub fn parse(input: &str) -> Result<VCalendar, ParseError> {
+    let calendar = parse_components(input)?
+        .into_iter()
+        .find(|component| component.name == "VCALENDAR")
+        .ok_or(ParseError::MissingCalendar)?;
+
+    let zones = Zones::new(
+        calendar
+            .children
+            .iter()
+            .filter(|component| component.name == "VTIMEZONE"),
+    );
+
+    let mut vcalendar = VCalendar {
+        name: calendar
+            .property("X-WR-CALNAME")
+            .or_else(|| calendar.property("NAME"))
+            .map(|property| unescape_text(&property.value)),
+        // The exact color, as COLOR only holds the closest CSS name
+        color: calendar
+            .property("X-APPLE-CALENDAR-COLOR")
+            .or_else(|| calendar.property("COLOR"))
+            .map(|property| property.value.clone()),
+        ..Default::default()
+    };
+
+    let mut unsupported_uids = HashSet::new();
+    for component in calendar
+        .children
+        .iter()
+        .filter(|component| component.name == "VEVENT")
+    {
+        match parse_event(component, &zones) {
+            Some(event) if event.rrule.is_none() && component.property("RRULE").is_some() => {
+                warn!("Skipping VEVENT {:?} with an unsupported RRULE", event.uid);
+                vcalendar.unsupported_recurrences += 1;
+                unsupported_uids.insert(event.uid);
+            }
+            Some(event) => vcalendar.events.push(event),
+            None => {
+                warn!("Skipping invalid VEVENT {:?}", component.text("UID"));
+                vcalendar.invalid_events += 1;
+            }
+        }
+    }
+    // The overridden occurrences of these series would be imported alone otherwise
+    vcalendar
+        .events
+        .retain(|event| !unsupported_uids.contains(&event.uid));
+
+    Ok(vcalendar)
+}
+
+pub(super) fn parse_components(input: &str) -> Result<Vec<Component>, ParseError> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Component> = Vec::new();
+
+    for (line_number, line) in unfold(input) {
+        let property = parse_content_line(&line).ok_or(ParseError::InvalidLine(line_number))?;
+
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: property.value.to_ascii_uppercase(),
+                ..Default::default()
+            }),
+            "END" => {
+                let name = property.value.to_ascii_uppercase();
+                let component = match stack.pop() {
+                    Some(component) if component.name == name => component,
+                    _ => {
+                        return Err(ParseError::UnexpectedEnd {
+                            line: line_number,
+                            name,
+                        });
+                    }
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(component),
+                    None => roots.push(component),
+                }
+            }
+            _ => {
+                // Lines outside of any component are ignored.
+                if let Some(component) = stack.last_mut() {
+                    component.properties.push(property);
+                }
+            }
+        }
+    }
+
+    match stack.pop() {
+        Some(component) => Err(ParseError::UnterminatedComponent(component.name)),
+        None => Ok(roots),
+    }
+}
+
+/// Join folded lines, returning each logical line with the number of its first physical line.
+fn unfold(input: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if let (Some(continuation), Some((_, last))) =
+            (line.strip_prefix([' ', '\t']), lines.last_mut())
+        {
+            last.push_str(continuation);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push((index + 1, line.to_owned()));
+        }
+    }
+
+    lines
+}
+
+fn parse_content_line(line: &str) -> Option<Property> {
+    let mut params = Vec::new();
+    let mut in_quotes = false;
+    let mut name_end = None;
+    let mut value_start = None;
+    let mut param_start = None;
+
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' | ':' if !in_quotes => {
+                match param_start {
+                    Some(start) => params.push(parse_param(&line[start..index])?),
+                    None => name_end = Some(index),
+                }
+                if c == ':' {
+                    value_start = Some(index + 1);
+                    break;
+                }
+                param_start = Some(index + 1);
+            }
+            _ => (),
+        }
+    }
+
+    let name = line[..name_end?].trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(Property {
+        name,
+        params,
+        value: line[value_start?..].to_owned(),
+    })
+}
+
+fn parse_param(param: &str) -> Option<(String, String)> {
+    let (name, value) = param.split_once('=')?;
+    Some((
+        name.trim().to_ascii_uppercase(),
+        value.trim_matches('"').to_owned(),
+    ))
+}
+
+pub(super) fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+pub(super) fn parse_event(component: &Component, zones: &Zones) -> Option<VEvent> {
+    let start_property = component.property("DTSTART")?;
+    let (start, all_day) = parse_date_time(start_property, &start_property.value, zones)?;
+
+    let end = if let Some(property) = component.property("DTEND") {
+        parse_date_time(property, &property.value, zones)?.0
+    } else if let Some(property) = component.property("DURATION") {
+        start
+            .checked_add(parse_duration_span(&property.value)?)
+            .ok()?
+    } else if all_day {
+        start.checked_add(Span::new().days(1)).ok()?
+    } else {
+        start.clone()
+    };
+
+    let summary = component.text("SUMMARY");
+    let uid = match component.text("UID") {
+        uid if !uid.is_empty() => uid,
+        // Keep a stable identifier so that the event is still deduplicated on re-import
+        _ => format!("{}-{}", start.timestamp().as_second(), summary),
+    };
+
+    let rrule = component
+        .property("RRULE")
+        .and_then(|property| RecurrenceRule::parse(&property.value, start.time_zone()));
+
+    let exdates = component
+        .properties("EXDATE")
+        .flat_map(|property| {
+            property
+                .value
+                .split(',')
+                .filter_map(|value| parse_date_time(property, value, zones))
+                .map(|(date_time, _)| date_time)
+        })
+        .collect();
+
+    let recurrence_id = component
+        .property("RECURRENCE-ID")
+        .and_then(|property| parse_date_time(property, &property.value, zones))
+        .map(|(date_time, _)| date_time);
+
+    let duration = end.duration_since(&start);
+    let alarms = component
+        .children
+        .iter()
+        .filter(|child| child.name == "VALARM")
+        .filter_map(|child| parse_alarm(child, duration))
+        .collect();
+
+    Some(VEvent {
+        uid,
+        summary,
+        description: component.text("DESCRIPTION"),
+        location: component.text("LOCATION"),
+        color: component
+            .property("COLOR")
+            .map(|property| property.value.clone()),
+        start,
+        end,
+        all_day,
+        transparent: component
+            .property("TRANSP")
+            .is_some_and(|property| property.value.eq_ignore_ascii_case("TRANSPARENT")),
+        rrule,
+        exdates,
+        recurrence_id,
+        alarms,
+    })
+}
+
+fn parse_alarm(component: &Component, event_duration: SignedDuration) -> Option<VAlarm> {
+    let property = component.property("TRIGGER")?;
+    let trigger = if property
+        .param("VALUE")
+        .is_some_and(|value| value.eq_ignore_ascii_case("DATE-TIME"))
+    {
+        let date_time = parse_civil_date_time(property.value.trim_end_matches('Z'))?;
+        AlarmTrigger::Absolute(date_time.to_zoned(TimeZone::UTC).ok()?.timestamp())
+    } else {
+        let mut offset = parse_duration(&property.value)?;
+        if property
+            .param("RELATED")
+            .is_some_and(|related| related.eq_ignore_ascii_case("END"))
+        {
+            offset = offset.checked_add(event_duration)?;
+        }
+        AlarmTrigger::Relative(offset)
+    };
+
+    Some(VAlarm {
+        action: component.text("ACTION"),
+        description: component.text("DESCRIPTION"),
+        trigger,
+    })
+}
+
+/// Parse a `DATE` or `DATE-TIME` value, returning whether it is a date.
+///
+/// Floating times, and times in a zone that is neither known nor described in the file, are
+/// interpreted in the system time zone.
+pub(super) fn parse_date_time(
+    property: &Property,
+    value: &str,
+    zones: &Zones,
+) -> Option<(jiff::Zoned, bool)> {
+    let value = value.trim();
+
+    let is_date = property
+        .param("VALUE")
+        .is_some_and(|value| value.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+    if is_date {
+        let date = civil::Date::strptime("%Y%m%d", value).ok()?;
+        return Some((date.to_zoned(TimeZone::system()).ok()?, true));
+    }
+
+    if let Some(value) = value.strip_suffix('Z') {
+        let date_time = parse_civil_date_time(value)?;
+        return Some((date_time.to_zoned(TimeZone::UTC).ok()?, false));
+    }
+
+    let date_time = parse_civil_date_time(value)?;
+    let zoned = match property.param("TZID") {
+        Some(tzid) => zones.to_zoned(tzid, date_time).or_else(|| {
+            warn!("Unknown time zone {tzid}, using the system time zone");
+            date_time.to_zoned(TimeZone::system()).ok()
+        })?,
+        None => date_time.to_zoned(TimeZone::system()).ok()?,
+    };
+    Some((zoned, false))
+}
+
+pub(super) fn parse_civil_date_time(value: &str) -> Option<civil::DateTime> {
+    civil::DateTime::strptime("%Y%m%dT%H%M%S", value).ok()
+}
+
+/// Parse an RFC 5545 duration such as `-PT15M` or `P1W` as a calendar span.
+fn parse_duration_span(value: &str) -> Option<Span> {
+    value.trim().parse().ok()
+}
+
+/// Parse an RFC 5545 duration, treating days as 24 hours long.
+fn parse_duration(value: &str) -> Option<SignedDuration> {
+    parse_duration_span(value)?
+        .to_duration(SpanRelativeTo::days_are_24_hours())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::Timestamp;
+
+    use super::*;
+
+    /// An iCalendar object with the lines of `events` in a `VCALENDAR`.
+    fn calendar(events: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nX-WR-CALNAME:Team\\, Paris\r\n{events}END:VCALENDAR\r\n"
+        )
+    }
+
+    fn timestamp(value: &str) -> Timestamp {
+        value.parse().expect("test timestamp should be valid")
+    }
+
+    #[test]
+    fn content_lines_are_unfolded_and_unescaped() {
+        let vcalendar = parse(&calendar(
+            "BEGIN:VEVENT\r
+UID:lines@example.com\r
+DTSTART:20260105T090000Z\r
+SUMMARY:Planning\\; budget\\, roadmap\r
+DESCRIPTION:First line\\nsecond \r
+ line, folded\r
+LOCATION;ALTREP=\"https://example.com/a;b:c\":Room 4\r
+END:VEVENT\r
+",
+        ))
+        .unwrap();
+
+        assert_eq!(vcalendar.name.as_deref(), Some("Team, Paris"));
+        let event = &vcalendar.events[0];
+        assert_eq!(event.summary, "Planning; budget, roadmap");
+        assert_eq!(event.description, "First line\nsecond line, folded");
+        assert_eq!(event.location, "Room 4");
+    }
+
+    #[test]
+    fn times_are_parsed() {
+        let vcalendar = parse(&calendar(
+            "BEGIN:VEVENT\r
+UID:utc\r
+DTSTART:20260105T090000Z\r
+DURATION:PT1H30M\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:zoned\r
+DTSTART;TZID=Europe/Paris:20260705T090000\r
+DTEND;TZID=/mozilla.org/20050126_1/America/New_York:20260705T090000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:all-day\r
+DTSTART;VALUE=DATE:20260105\r
+END:VEVENT\r
+",
+        ))
+        .unwrap();
+
+        let [utc, zoned, all_day] = vcalendar.events.as_slice() else {
+            panic!("all events should be parsed");
+        };
+        assert_eq!(utc.start.timestamp(), timestamp("2026-01-05T09:00:00Z"));
+        assert_eq!(utc.end.timestamp(), timestamp("2026-01-05T10:30:00Z"));
+        assert!(!utc.all_day);
+
+        assert_eq!(zoned.start.time_zone().iana_name(), Some("Europe/Paris"));
+        assert_eq!(zoned.start.timestamp(), timestamp("2026-07-05T07:00:00Z"));
+        assert_eq!(zoned.end.timestamp(), timestamp("2026-07-05T13:00:00Z"));
+
+        // All-day events without an end last one day
+        assert!(all_day.all_day);
+        assert_eq!(all_day.start.date(), civil::date(2026, 1, 5));
+        assert_eq!(all_day.end.date(), civil::date(2026, 1, 6));
+    }
+
+    #[test]
+    fn alarms_are_parsed() {
+        let vcalendar = parse(&calendar(
+            "BEGIN:VEVENT\r
+UID:alarms\r
+DTSTART:20260105T090000Z\r
+DTEND:20260105T100000Z\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+DESCRIPTION:Soon\r
+TRIGGER:-PT15M\r
+END:VALARM\r
+BEGIN:VALARM\r
+ACTION:AUDIO\r
+TRIGGER;RELATED=END:PT5M\r
+END:VALARM\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+TRIGGER;VALUE=DATE-TIME:20260104T180000Z\r
+END:VALARM\r
+END:VEVENT\r
+",
+        ))
+        .unwrap();
+
+        let triggers: Vec<&AlarmTrigger> = vcalendar.events[0]
+            .alarms
+            .iter()
+            .map(|alarm| &alarm.trigger)
+            .collect();
+        assert_eq!(
+            triggers,
+            [
+                &AlarmTrigger::Relative(SignedDuration::from_mins(-15)),
+                &AlarmTrigger::Relative(SignedDuration::from_mins(65)),
+                &AlarmTrigger::Absolute(timestamp("2026-01-04T18:00:00Z")),
+            ]
+        );
+        assert_eq!(vcalendar.events[0].alarms[0].description, "Soon");
+    }
+
+    #[test]
+    fn invalid_events_are_counted() {
+        let vcalendar = parse(&calendar(
+            "BEGIN:VEVENT\r
+UID:no-start\r
+SUMMARY:Missing DTSTART\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20260105T090000Z\r
+SUMMARY:No UID\r
+END:VEVENT\r
+",
+        ))
+        .unwrap();
+
+        assert_eq!(vcalendar.invalid_events, 1);
+        // Events without a UID get a stable one
+        assert_eq!(vcalendar.events[0].uid, "1767603600-No UID");
+    }
+
+    #[test]
+    fn unsupported_recurrences_are_left_out() {
+        let vcalendar = parse(&calendar(
+            "BEGIN:VEVENT\r
+UID:last-weekday\r
+DTSTART:20260130T090000Z\r
+RRULE:FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:last-weekday\r
+RECURRENCE-ID:20260227T090000Z\r
+DTSTART:20260227T100000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:weekly\r
+DTSTART:20260105T090000Z\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,TH\r
+EXDATE:20260108T090000Z,20260112T090000Z\r
+END:VEVENT\r
+",
+        ))
+        .unwrap();
+
+        assert_eq!(vcalendar.unsupported_recurrences, 1);
+        assert_eq!(vcalendar.invalid_events, 0);
+        let [weekly] = vcalendar.events.as_slice() else {
+            panic!("only the supported series should be kept");
+        };
+        assert!(weekly.rrule.is_some());
+        assert_eq!(weekly.exdates.len(), 2);
+    }
+
+    #[test]
+    fn malformed_data_is_an_error() {
+        assert_eq!(
+            parse("BEGIN:VEVENT\r\nEND:VEVENT\r\n"),
+            Err(ParseError::MissingCalendar)
+        );
+        assert_eq!(
+            parse("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VCALENDAR\r\n"),
+            Err(ParseError::UnexpectedEnd {
+                line: 3,
+                name: "VCALENDAR".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("BEGIN:VCALENDAR\r\n"),
+            Err(ParseError::UnterminatedComponent("VCALENDAR".to_owned()))
+        );
+        assert_eq!(
+            parse("BEGIN:VCALENDAR\r\nno colon\r\nEND:VCALENDAR\r\n"),
+            Err(ParseError::InvalidLine(2))
+        );
+    }
+}
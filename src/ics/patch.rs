@@ -0,0 +1,215 @@
+use ccm::jiff;
+
+use super::{
+    VEvent,
+    parser::{self, Component, Property},
+    timezone::Zones,
+    writer,
+};
+
+/// Apply the summary, description and times of `event` to the `VEVENT` with the same UID in the
+/// iCalendar object `data`.
+///
+/// Only the properties that changed are replaced, so the ones that local events cannot hold,
+/// like the location, the attendees and the alarms, are kept. Returns `None` if `data` cannot be
+/// parsed or does not contain the event.
+pub fn patch_event(data: &str, event: &VEvent) -> Option<String> {
+    let mut roots = parser::parse_components(data).ok()?;
+    let vcalendar = roots
+        .iter_mut()
+        .find(|component| component.name == "VCALENDAR")?;
+
+    let time_zones: Vec<&Component> = vcalendar
+        .children
+        .iter()
+        .filter(|component| component.name == "VTIMEZONE")
+        .collect();
+    let tzids: Vec<String> = time_zones
+        .iter()
+        .filter_map(|component| Some(component.property("TZID")?.value.clone()))
+        .collect();
+    let zones = Zones::new(time_zones.into_iter());
+
+    let (index, previous) =
+        vcalendar
+            .children
+            .iter()
+            .enumerate()
+            .find_map(|(index, component)| {
+                if component.name != "VEVENT" || component.property("RECURRENCE-ID").is_some() {
+                    return None;
+                }
+                let previous = parser::parse_event(component, &zones)?;
+                (previous.uid == event.uid).then_some((index, previous))
+            })?;
+    let vevent = &mut vcalendar.children[index];
+
+    let now = writer::format_utc(jiff::Timestamp::now());
+    set_property(vevent, "DTSTAMP", Vec::new(), now.clone());
+    if vevent.property("LAST-MODIFIED").is_some() {
+        set_property(vevent, "LAST-MODIFIED", Vec::new(), now);
+    }
+
+    if event.summary != previous.summary {
+        set_text(vevent, "SUMMARY", &event.summary);
+    }
+    if event.description != previous.description {
+        if event.description.is_empty() {
+            remove_property(vevent, "DESCRIPTION");
+        } else {
+            set_text(vevent, "DESCRIPTION", &event.description);
+        }
+    }
+
+    let times_changed = event.all_day != previous.all_day
+        || event.start.timestamp() != previous.start.timestamp()
+        || event.end.timestamp() != previous.end.timestamp();
+    if times_changed {
+        let (params, value) = date_time(&event.start, event.all_day, &tzids);
+        set_property(vevent, "DTSTART", params, value);
+        remove_property(vevent, "DURATION");
+        let (params, value) = date_time(&event.end, event.all_day, &tzids);
+        set_property(vevent, "DTEND", params, value);
+
+        // A new time is a significant change for the other attendees
+        let sequence = vevent
+            .property("SEQUENCE")
+            .and_then(|property| property.value.trim().parse::<u32>().ok())
+            .unwrap_or(0);
+        set_property(vevent, "SEQUENCE", Vec::new(), (sequence + 1).to_string());
+    }
+
+    Some(writer::write_components(&roots))
+}
+
+/// Replace the value and parameters of the first property called `name`, or add it.
+fn set_property(
+    component: &mut Component,
+    name: &str,
+    params: Vec<(String, String)>,
+    value: String,
+) {
+    let property = Property {
+        name: name.to_owned(),
+        params,
+        value,
+    };
+    match component
+        .properties
+        .iter()
+        .position(|property| property.name == name)
+    {
+        Some(index) => component.properties[index] = property,
+        None => component.properties.push(property),
+    }
+}
+
+/// Replace the value of a text property, keeping its parameters such as `LANGUAGE`.
+fn set_text(component: &mut Component, name: &str, text: &str) {
+    let params = component
+        .property(name)
+        .map(|property| property.params.clone())
+        .unwrap_or_default();
+    set_property(component, name, params, writer::escape_text(text));
+}
+
+fn remove_property(component: &mut Component, name: &str) {
+    component
+        .properties
+        .retain(|property| property.name != name);
+}
+
+/// The parameters and value of a `DATE` or `DATE-TIME` property.
+///
+/// Times in a zone described in the object keep it, others are written in UTC rather than
+/// adding a `VTIMEZONE`.
+fn date_time(
+    date_time: &jiff::Zoned,
+    all_day: bool,
+    tzids: &[String],
+) -> (Vec<(String, String)>, String) {
+    if all_day {
+        return (
+            vec![("VALUE".to_owned(), "DATE".to_owned())],
+            date_time.strftime("%Y%m%d").to_string(),
+        );
+    }
+
+    let tzid = writer::tzid(date_time.time_zone());
+    match tzid.filter(|tzid| tzids.iter().any(|known| known == tzid)) {
+        Some(tzid) => (
+            vec![("TZID".to_owned(), tzid.to_owned())],
+            date_time.strftime("%Y%m%dT%H%M%S").to_string(),
+        ),
+        None => (Vec::new(), writer::format_utc(date_time.timestamp())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::ToSpan;
+
+    use super::*;
+    use crate::ics::{TEST_OBJECT, parse, test_event};
+
+    #[test]
+    fn patch_keeps_other_properties() {
+        let event = VEvent {
+            summary: "Yearly review".to_owned(),
+            ..test_event()
+        };
+
+        let patched = patch_event(TEST_OBJECT, &event).expect("event should be patched");
+        assert!(patched.contains("SUMMARY;LANGUAGE=en:Yearly review\r\n"));
+        assert!(patched.contains("DTSTART;TZID=Europe/Paris:20261020T100000\r\n"));
+        assert!(patched.contains("DURATION:PT1H\r\n"));
+        assert!(patched.contains("LOCATION:Room 4\\; 2nd floor\r\n"));
+        assert!(
+            patched.contains(
+                "ATTENDEE;CN=\"Doe, Jane\";PARTSTAT=ACCEPTED:mailto:jane@example.com\r\n"
+            )
+        );
+        assert!(patched.contains("X-CUSTOM:kept\r\n"));
+        assert!(!patched.contains("SEQUENCE"));
+
+        let reparsed = parse(&patched).expect("patched object should parse");
+        assert_eq!(reparsed.events, vec![event]);
+    }
+
+    #[test]
+    fn patch_replaces_times() {
+        let previous = test_event();
+        let event = VEvent {
+            start: previous.start.checked_add(1.hour()).unwrap(),
+            end: previous.end.checked_add(2.hours()).unwrap(),
+            ..previous
+        };
+
+        let patched = patch_event(TEST_OBJECT, &event).expect("event should be patched");
+        assert!(patched.contains("DTSTART;TZID=Europe/Paris:20261020T110000\r\n"));
+        assert!(patched.contains("DTEND;TZID=Europe/Paris:20261020T130000\r\n"));
+        assert!(!patched.contains("DURATION"));
+        assert!(patched.contains("SEQUENCE:1\r\n"));
+        assert!(patched.contains("BEGIN:VALARM\r\n"));
+
+        // Zones the object does not describe are written in UTC
+        let event = VEvent {
+            start: event
+                .start
+                .with_time_zone(jiff::tz::TimeZone::get("Asia/Tokyo").unwrap()),
+            ..event
+        };
+        let patched = patch_event(TEST_OBJECT, &event).expect("event should be patched");
+        assert!(patched.contains("DTSTART:20261020T090000Z\r\n"));
+    }
+
+    #[test]
+    fn patch_needs_the_event() {
+        let event = VEvent {
+            uid: "other".to_owned(),
+            ..test_event()
+        };
+        assert_eq!(patch_event(TEST_OBJECT, &event), None);
+        assert_eq!(patch_event("not iCalendar", &event), None);
+    }
+}
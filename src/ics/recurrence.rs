@@ -0,0 +1,625 @@
+use ccm::jiff::{
+    self, Span, ToSpan, Unit,
+    civil::{self, Weekday},
+    tz::TimeZone,
+};
+
+use super::parser::parse_civil_date_time;
+
+/// Hard limit on the number of periods in a row without any occurrence when expanding a rule, to
+/// bound the work done for rules that never match anything.
+const MAX_PERIODS: i32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A `RRULE` value.
+///
+/// Only the parts commonly produced by calendar applications are supported: `FREQ` from `DAILY`
+/// to `YEARLY`, `INTERVAL`, `COUNT`, `UNTIL`, `BYDAY`, `BYMONTHDAY`, `BYMONTH` and `WKST`. Rules
+/// with other parts are rejected, as ignoring them would give other occurrences than intended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: i32,
+    pub count: Option<usize>,
+    pub until: Option<jiff::Timestamp>,
+    /// Weekdays, with the optional ordinal of `BYDAY=-1SU`.
+    pub by_day: Vec<(Option<i8>, Weekday)>,
+    pub by_month_day: Vec<i8>,
+    pub by_month: Vec<i8>,
+}
+
+impl RecurrenceRule {
+    /// Parse a `RRULE` value. A date-only `UNTIL` is interpreted in `time_zone`.
+    ///
+    /// Returns `None` if the rule is invalid or cannot be expanded exactly, like rules with
+    /// `BYSETPOS` or an `HOURLY` frequency.
+    pub fn parse(value: &str, time_zone: &TimeZone) -> Option<Self> {
+        let mut frequency = None;
+        let mut week_start = Weekday::Monday;
+        let mut rule = Self {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in value.trim().split(';') {
+            let Some((name, value)) = part.split_once('=') else {
+                continue;
+            };
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse().ok().filter(|i| *i > 0)?,
+                "COUNT" => rule.count = Some(value.parse().ok()?),
+                "UNTIL" => rule.until = Some(parse_until(value, time_zone)?),
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(parse_weekday_num)
+                        .collect::<Option<_>>()?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = value
+                        .split(',')
+                        .map(|day| day.parse().ok())
+                        .collect::<Option<_>>()?
+                }
+                "BYMONTH" => {
+                    rule.by_month = value
+                        .split(',')
+                        .map(|month| month.parse().ok())
+                        .collect::<Option<_>>()?
+                }
+                "WKST" => week_start = parse_weekday_num(value).filter(|(nth, _)| nth.is_none())?.1,
+                // Parts like BYSETPOS or BYWEEKNO, which would select other occurrences
+                _ => return None,
+            }
+        }
+
+        rule.frequency = frequency?;
+        let has_ordinals = rule.by_day.iter().any(|(nth, _)| nth.is_some());
+        let supported = match rule.frequency {
+            // Ordinals only make sense within months and years
+            Frequency::Daily => !has_ordinals,
+            Frequency::Weekly => !has_ordinals && rule.by_month_day.is_empty(),
+            Frequency::Monthly => true,
+            // The nth weekday of the year
+            Frequency::Yearly => !has_ordinals || !rule.by_month.is_empty(),
+        };
+        // Weeks are counted from Monday, which only matters for several days every other week
+        let week_start_matters =
+            rule.frequency == Frequency::Weekly && rule.interval > 1 && rule.by_day.len() > 1;
+        if !supported || (week_start != Weekday::Monday && week_start_matters) {
+            return None;
+        }
+        Some(rule)
+    }
+
+    /// The start of every occurrence of an event starting at `start`, from `from` up to `limit`.
+    ///
+    /// The first occurrence is `start` itself. Rules without `COUNT` are expanded from the period
+    /// before `from`, so old series do not cost anything for the years before it. Exceptions from
+    /// `EXDATE` are not applied here.
+    pub fn occurrences(
+        &self,
+        start: &jiff::Zoned,
+        from: jiff::Timestamp,
+        limit: jiff::Timestamp,
+    ) -> Vec<jiff::Zoned> {
+        let first = start.date();
+        let time = start.time();
+        let time_zone = start.time_zone();
+        let mut occurrences = Vec::new();
+        if start.timestamp() >= from {
+            occurrences.push(start.clone());
+        }
+        // Occurrences before `from` still count towards COUNT
+        let mut count = 1;
+        if self.count.is_some_and(|max| count >= max) {
+            return occurrences;
+        }
+
+        let first_period = match self.count {
+            Some(_) => 0,
+            None => self.periods_before(first, from.to_zoned(time_zone.clone()).date()),
+        };
+        let mut empty_periods = 0;
+        for period in first_period.. {
+            let Some((period_floor, mut dates)) = self.period_dates(first, period) else {
+                break;
+            };
+
+            let Ok(floor) = period_floor.to_datetime(time).to_zoned(time_zone.clone()) else {
+                break;
+            };
+            if floor.timestamp() > limit {
+                break;
+            }
+
+            dates.sort();
+            dates.dedup();
+            let mut empty = true;
+            for date in dates.into_iter().filter(|date| *date > first) {
+                let Ok(occurrence) = date.to_datetime(time).to_zoned(time_zone.clone()) else {
+                    continue;
+                };
+                if occurrence.timestamp() > limit
+                    || self
+                        .until
+                        .is_some_and(|until| occurrence.timestamp() > until)
+                {
+                    return occurrences;
+                }
+                empty = false;
+                count += 1;
+                if occurrence.timestamp() >= from {
+                    occurrences.push(occurrence);
+                }
+                if self.count.is_some_and(|max| count >= max) {
+                    return occurrences;
+                }
+            }
+
+            empty_periods = if empty { empty_periods + 1 } else { 0 };
+            if empty_periods >= MAX_PERIODS {
+                break;
+            }
+        }
+
+        occurrences
+    }
+
+    /// The number of whole periods of the rule between `first` and `date`, minus one so that no
+    /// occurrence on or after `date` is skipped.
+    fn periods_before(&self, first: civil::Date, date: civil::Date) -> i32 {
+        let unit = match self.frequency {
+            Frequency::Daily => Unit::Day,
+            Frequency::Weekly => Unit::Week,
+            Frequency::Monthly => Unit::Month,
+            Frequency::Yearly => Unit::Year,
+        };
+        let Ok(span) = first.until((unit, date)) else {
+            return 0;
+        };
+        let units = match self.frequency {
+            Frequency::Daily => span.get_days(),
+            Frequency::Weekly => span.get_weeks(),
+            Frequency::Monthly => span.get_months(),
+            Frequency::Yearly => i32::from(span.get_years()),
+        };
+        (units / self.interval - 1).max(0)
+    }
+
+    /// The candidate dates of the nth period of the rule, along with the first day of the period.
+    fn period_dates(
+        &self,
+        first: civil::Date,
+        period: i32,
+    ) -> Option<(civil::Date, Vec<civil::Date>)> {
+        let step = period * self.interval;
+        let (floor, dates) = match self.frequency {
+            Frequency::Daily => {
+                let day = first.checked_add(step.days()).ok()?;
+                (day, vec![day])
+            }
+            Frequency::Weekly => {
+                let day = first.checked_add(step.weeks()).ok()?;
+                let monday = day
+                    .checked_sub(Span::new().days(day.weekday().to_monday_zero_offset()))
+                    .ok()?;
+                let dates = if self.by_day.is_empty() {
+                    vec![day]
+                } else {
+                    self.by_day
+                        .iter()
+                        .filter_map(|(_, weekday)| {
+                            monday
+                                .checked_add(Span::new().days(weekday.to_monday_zero_offset()))
+                                .ok()
+                        })
+                        .collect()
+                };
+                (monday, dates)
+            }
+            Frequency::Monthly => {
+                let month = first.first_of_month().checked_add(step.months()).ok()?;
+                (month, self.month_dates(month, first.day()))
+            }
+            Frequency::Yearly => {
+                let year = civil::Date::new(first.year().checked_add(step.try_into().ok()?)?, 1, 1)
+                    .ok()?;
+                let months = if !self.by_month.is_empty() {
+                    self.by_month.clone()
+                } else if self.by_month_day.is_empty() && self.by_day.is_empty() {
+                    vec![first.month()]
+                } else {
+                    // The days are in every month
+                    (1..=12).collect()
+                };
+                let dates = months
+                    .into_iter()
+                    .filter_map(|month| civil::Date::new(year.year(), month, 1).ok())
+                    .flat_map(|month| self.month_dates(month, first.day()))
+                    .collect();
+                (year, dates)
+            }
+        };
+
+        let dates = dates
+            .into_iter()
+            .filter(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            .filter(|date| {
+                self.frequency != Frequency::Daily
+                    || self.by_day.is_empty()
+                    || self
+                        .by_day
+                        .iter()
+                        .any(|(_, weekday)| *weekday == date.weekday())
+            })
+            .filter(|date| {
+                self.frequency != Frequency::Daily
+                    || self.by_month_day.is_empty()
+                    || self
+                        .by_month_day
+                        .iter()
+                        .any(|day| month_day(date.first_of_month(), *day) == date.day())
+            })
+            .collect();
+        Some((floor, dates))
+    }
+
+    /// The candidate dates within the month starting at `first_of_month`.
+    fn month_dates(&self, first_of_month: civil::Date, default_day: i8) -> Vec<civil::Date> {
+        if !self.by_month_day.is_empty() {
+            return self
+                .by_month_day
+                .iter()
+                .map(|day| month_day(first_of_month, *day))
+                .filter_map(|day| first_of_month.with().day(day).build().ok())
+                .filter(|date| {
+                    self.by_day.is_empty()
+                        || self
+                            .by_day
+                            .iter()
+                            .any(|(_, weekday)| *weekday == date.weekday())
+                })
+                .collect();
+        }
+
+        if !self.by_day.is_empty() {
+            return self
+                .by_day
+                .iter()
+                .flat_map(|(nth, weekday)| match nth {
+                    Some(nth) => first_of_month
+                        .nth_weekday_of_month(*nth, *weekday)
+                        .ok()
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                    None => (1..=5)
+                        .filter_map(|nth| first_of_month.nth_weekday_of_month(nth, *weekday).ok())
+                        .collect(),
+                })
+                .collect();
+        }
+
+        // Months without that day, like February for the 30th, are skipped
+        first_of_month
+            .with()
+            .day(default_day)
+            .build()
+            .ok()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// The day of the month of a `BYMONTHDAY` value, counting negative ones from the end.
+fn month_day(first_of_month: civil::Date, day: i8) -> i8 {
+    if day < 0 {
+        first_of_month.days_in_month() + day + 1
+    } else {
+        day
+    }
+}
+
+fn parse_until(value: &str, time_zone: &TimeZone) -> Option<jiff::Timestamp> {
+    if let Some(value) = value.strip_suffix('Z') {
+        return Some(
+            parse_civil_date_time(value)?
+                .to_zoned(TimeZone::UTC)
+                .ok()?
+                .timestamp(),
+        );
+    }
+    if let Some(date_time) = parse_civil_date_time(value) {
+        return Some(date_time.to_zoned(time_zone.clone()).ok()?.timestamp());
+    }
+    // A date-only UNTIL includes the whole day
+    let date = civil::Date::strptime("%Y%m%d", value).ok()?;
+    Some(
+        date.to_datetime(civil::time(23, 59, 59, 0))
+            .to_zoned(time_zone.clone())
+            .ok()?
+            .timestamp(),
+    )
+}
+
+/// A weekday with its optional ordinal, as in `BYDAY=-1SU`.
+pub fn parse_weekday_num(value: &str) -> Option<(Option<i8>, Weekday)> {
+    let value = value.trim();
+    let (nth, weekday) = value.split_at_checked(value.len().checked_sub(2)?)?;
+    let weekday = match weekday.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return None,
+    };
+    let nth = if nth.is_empty() {
+        None
+    } else {
+        Some(nth.trim_start_matches('+').parse().ok()?)
+    };
+    Some((nth, weekday))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris() -> TimeZone {
+        TimeZone::get("Europe/Paris").unwrap()
+    }
+
+    fn rule(value: &str) -> RecurrenceRule {
+        RecurrenceRule::parse(value, &paris()).expect("test rule should be supported")
+    }
+
+    /// The dates of the occurrences of `value` for an event starting at `start` in Paris.
+    fn dates(value: &str, start: &str) -> Vec<civil::Date> {
+        let start = start.parse::<civil::DateTime>().unwrap();
+        let start = start.to_zoned(paris()).unwrap();
+        let limit = start.timestamp().checked_add(24 * 366 * 5.hours()).unwrap();
+        rule(value)
+            .occurrences(&start, start.timestamp(), limit)
+            .iter()
+            .map(jiff::Zoned::date)
+            .collect()
+    }
+
+    #[test]
+    fn rules_are_parsed() {
+        let rule = rule("FREQ=MONTHLY;INTERVAL=2;COUNT=6;BYDAY=-1FR,2MO;BYMONTH=1,7");
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(6));
+        assert_eq!(
+            rule.by_day,
+            [(Some(-1), Weekday::Friday), (Some(2), Weekday::Monday)]
+        );
+        assert_eq!(rule.by_month, [1, 7]);
+
+        // A date-only UNTIL includes the whole day
+        let rule = RecurrenceRule::parse("FREQ=DAILY;UNTIL=20260303", &paris()).unwrap();
+        assert_eq!(
+            rule.until,
+            Some("2026-03-03T23:59:59+01:00".parse().unwrap())
+        );
+
+        assert_eq!(RecurrenceRule::parse("INTERVAL=2", &paris()), None);
+        assert_eq!(
+            RecurrenceRule::parse("FREQ=DAILY;INTERVAL=0", &paris()),
+            None
+        );
+    }
+
+    #[test]
+    fn rules_that_cannot_be_expanded_are_rejected() {
+        for value in [
+            // The last weekday of the month
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            "FREQ=YEARLY;BYYEARDAY=100",
+            "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO",
+            "FREQ=HOURLY;INTERVAL=2",
+            "FREQ=DAILY;BYHOUR=9,17",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            // The 20th Monday of the year
+            "FREQ=YEARLY;BYDAY=20MO",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=SU,MO;WKST=SU",
+        ] {
+            assert_eq!(RecurrenceRule::parse(value, &paris()), None, "{value}");
+        }
+
+        // The start of the week only matters for several days every other week
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=SU,MO;WKST=SU", &paris()).is_some());
+    }
+
+    #[test]
+    fn weekly_occurrences() {
+        assert_eq!(
+            dates("FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=5", "2026-01-05T09:00"),
+            [
+                civil::date(2026, 1, 5),
+                civil::date(2026, 1, 7),
+                civil::date(2026, 1, 9),
+                civil::date(2026, 1, 12),
+                civil::date(2026, 1, 14),
+            ]
+        );
+        assert_eq!(
+            dates("FREQ=WEEKLY;INTERVAL=2;COUNT=3", "2026-01-05T09:00"),
+            [
+                civil::date(2026, 1, 5),
+                civil::date(2026, 1, 19),
+                civil::date(2026, 2, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_occurrences() {
+        // The last Friday of the month
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3", "2026-01-30T09:00"),
+            [
+                civil::date(2026, 1, 30),
+                civil::date(2026, 2, 27),
+                civil::date(2026, 3, 27),
+            ]
+        );
+        // Months without a 31st are skipped
+        assert_eq!(
+            dates("FREQ=MONTHLY;COUNT=3", "2026-01-31T09:00"),
+            [
+                civil::date(2026, 1, 31),
+                civil::date(2026, 3, 31),
+                civil::date(2026, 5, 31),
+            ]
+        );
+        // The last day of the month
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=3", "2026-01-31T09:00"),
+            [
+                civil::date(2026, 1, 31),
+                civil::date(2026, 2, 28),
+                civil::date(2026, 3, 31),
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_occurrences_are_limited_by_month_day() {
+        assert_eq!(
+            dates("FREQ=DAILY;BYMONTHDAY=1,15;COUNT=4", "2026-01-01T09:00"),
+            [
+                civil::date(2026, 1, 1),
+                civil::date(2026, 1, 15),
+                civil::date(2026, 2, 1),
+                civil::date(2026, 2, 15),
+            ]
+        );
+        assert_eq!(
+            dates("FREQ=DAILY;BYDAY=SA,SU;COUNT=3", "2026-01-03T09:00"),
+            [
+                civil::date(2026, 1, 3),
+                civil::date(2026, 1, 4),
+                civil::date(2026, 1, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn yearly_occurrences() {
+        assert_eq!(
+            dates("FREQ=YEARLY;COUNT=2", "2024-02-29T09:00"),
+            [civil::date(2024, 2, 29), civil::date(2028, 2, 29)]
+        );
+        // Without BYMONTH, the days are in every month
+        assert_eq!(
+            dates("FREQ=YEARLY;BYMONTHDAY=1;COUNT=3", "2026-01-01T09:00"),
+            [
+                civil::date(2026, 1, 1),
+                civil::date(2026, 2, 1),
+                civil::date(2026, 3, 1),
+            ]
+        );
+        // Thanksgiving
+        assert_eq!(
+            dates(
+                "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH;COUNT=2",
+                "2026-11-26T12:00"
+            ),
+            [civil::date(2026, 11, 26), civil::date(2027, 11, 25)]
+        );
+    }
+
+    #[test]
+    fn occurrences_keep_their_local_time() {
+        let start = civil::date(2026, 3, 28)
+            .at(9, 0, 0, 0)
+            .to_zoned(paris())
+            .unwrap();
+        let limit = start.timestamp().checked_add(60.hours()).unwrap();
+        let occurrences = rule("FREQ=DAILY").occurrences(&start, start.timestamp(), limit);
+        assert_eq!(occurrences.len(), 3);
+        // Across the change to summer time
+        assert!(occurrences.iter().all(|occurrence| occurrence.hour() == 9));
+        assert_eq!(occurrences[1].offset().seconds(), 7200);
+    }
+
+    #[test]
+    fn occurrences_end_at_until() {
+        assert_eq!(
+            dates("FREQ=DAILY;UNTIL=20260303", "2026-03-01T10:00"),
+            [
+                civil::date(2026, 3, 1),
+                civil::date(2026, 3, 2),
+                civil::date(2026, 3, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn occurrences_start_from() {
+        let start = civil::date(2000, 1, 3)
+            .at(9, 0, 0, 0)
+            .to_zoned(paris())
+            .unwrap();
+        let from = civil::date(2026, 1, 1)
+            .to_zoned(paris())
+            .unwrap()
+            .timestamp();
+        let limit = civil::date(2026, 2, 1)
+            .to_zoned(paris())
+            .unwrap()
+            .timestamp();
+        let occurrences = rule("FREQ=WEEKLY").occurrences(&start, from, limit);
+        let days: Vec<i8> = occurrences.iter().map(jiff::Zoned::day).collect();
+        assert_eq!(days, [5, 12, 19, 26]);
+
+        // Occurrences before `from` still count
+        let start = civil::date(2026, 1, 1)
+            .at(9, 0, 0, 0)
+            .to_zoned(paris())
+            .unwrap();
+        let from = civil::date(2026, 1, 8)
+            .to_zoned(paris())
+            .unwrap()
+            .timestamp();
+        let occurrences = rule("FREQ=DAILY;COUNT=10").occurrences(&start, from, limit);
+        let days: Vec<i8> = occurrences.iter().map(jiff::Zoned::day).collect();
+        assert_eq!(days, [8, 9, 10]);
+    }
+
+    #[test]
+    fn weekdays_are_parsed() {
+        assert_eq!(parse_weekday_num("-1SU"), Some((Some(-1), Weekday::Sunday)));
+        assert_eq!(parse_weekday_num("+2mo"), Some((Some(2), Weekday::Monday)));
+        assert_eq!(parse_weekday_num("FR"), Some((None, Weekday::Friday)));
+        assert_eq!(parse_weekday_num("XX"), None);
+        assert_eq!(parse_weekday_num("M"), None);
+    }
+}
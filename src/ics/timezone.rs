@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use ccm::jiff::{
+    self, civil,
+    tz::{Offset, TimeZone},
+};
+
+use super::{
+    RecurrenceRule,
+    parser::{Component, parse_civil_date_time},
+};
+
+/// The time zones an iCalendar file refers to.
+///
+/// `TZID`s naming an IANA time zone are resolved with the system database. Others fall back to
+/// the `VTIMEZONE` definition found in the file.
+pub(super) struct Zones {
+    defined: HashMap<String, DefinedZone>,
+}
+
+impl Zones {
+    pub fn new<'a>(components: impl Iterator<Item = &'a Component>) -> Self {
+        let defined = components
+            .filter_map(|component| {
+                let tzid = component.property("TZID")?.value.clone();
+                Some((tzid, DefinedZone::new(component)))
+            })
+            .collect();
+        Self { defined }
+    }
+
+    pub fn to_zoned(&self, tzid: &str, date_time: civil::DateTime) -> Option<jiff::Zoned> {
+        if let Some(time_zone) = iana_time_zone(tzid) {
+            return date_time.to_zoned(time_zone).ok();
+        }
+
+        let offset = self.defined.get(tzid)?.offset_at(date_time)?;
+        date_time.to_zoned(TimeZone::fixed(offset)).ok()
+    }
+}
+
+/// Find the IANA time zone a `TZID` refers to.
+///
+/// Some producers prefix the name, as in `/mozilla.org/20050126_1/Europe/Paris`, so the trailing
+/// segments are tried as well.
+pub(super) fn iana_time_zone(tzid: &str) -> Option<TimeZone> {
+    let tzid = tzid.trim_start_matches('/');
+    let segments: Vec<&str> = tzid.split('/').collect();
+    (0..segments.len()).find_map(|start| TimeZone::get(&segments[start..].join("/")).ok())
+}
+
+/// A time zone described by a `VTIMEZONE` component.
+struct DefinedZone {
+    observances: Vec<Observance>,
+}
+
+/// A `STANDARD` or `DAYLIGHT` sub-component.
+struct Observance {
+    start: civil::DateTime,
+    offset: Offset,
+    rule: Option<RecurrenceRule>,
+}
+
+impl DefinedZone {
+    fn new(component: &Component) -> Self {
+        let observances = component
+            .children
+            .iter()
+            .filter(|child| child.name == "STANDARD" || child.name == "DAYLIGHT")
+            .filter_map(|child| {
+                Some(Observance {
+                    start: parse_civil_date_time(&child.property("DTSTART")?.value)?,
+                    offset: parse_offset(&child.property("TZOFFSETTO")?.value)?,
+                    rule: child.property("RRULE").and_then(|property| {
+                        RecurrenceRule::parse(&property.value, &TimeZone::UTC)
+                    }),
+                })
+            })
+            .collect();
+        Self { observances }
+    }
+
+    /// The offset in effect at the given local time.
+    fn offset_at(&self, date_time: civil::DateTime) -> Option<Offset> {
+        self.observances
+            .iter()
+            .filter_map(|observance| {
+                observance
+                    .last_onset_before(date_time)
+                    .map(|onset| (onset, observance.offset))
+            })
+            .max_by_key(|(onset, _)| *onset)
+            .map(|(_, offset)| offset)
+            .or_else(|| {
+                // The time is before every observance, use the earliest one
+                self.observances
+                    .iter()
+                    .min_by_key(|observance| observance.start)
+                    .map(|observance| observance.offset)
+            })
+    }
+}
+
+impl Observance {
+    fn last_onset_before(&self, date_time: civil::DateTime) -> Option<civil::DateTime> {
+        if self.start > date_time {
+            return None;
+        }
+
+        let Some(rule) = &self.rule else {
+            return Some(self.start);
+        };
+
+        // Time zone rules are yearly, so the last onset is in this year or the previous one
+        [date_time.year(), date_time.year() - 1]
+            .into_iter()
+            .filter_map(|year| {
+                let month = rule.by_month.first().copied().unwrap_or(self.start.month());
+                let first_of_month = civil::Date::new(year, month, 1).ok()?;
+                let date = match rule.by_day.first() {
+                    Some((nth, weekday)) => first_of_month
+                        .nth_weekday_of_month(nth.unwrap_or(1), *weekday)
+                        .ok()?,
+                    None => civil::Date::new(year, month, self.start.day()).ok()?,
+                };
+                Some(date.to_datetime(self.start.time()))
+            })
+            .find(|onset| *onset <= date_time && *onset >= self.start)
+    }
+}
+
+/// Parse a `UTC-OFFSET` value such as `+0200` or `-033000`.
+pub(super) fn parse_offset(value: &str) -> Option<Offset> {
+    let value = value.trim();
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if !matches!(digits.len(), 4 | 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i32 = digits[0..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = digits.get(4..6).map_or(Some(0), |s| s.parse().ok())?;
+    Offset::from_seconds(sign * (hours * 3600 + minutes * 60 + seconds)).ok()
+}
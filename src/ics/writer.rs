@@ -0,0 +1,519 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use ccm::jiff::{
+    self, civil,
+    tz::{Offset, TimeZone},
+};
+
+use super::{
+    AlarmTrigger, RecurrenceRule, VAlarm, VCalendar, VEvent, color, parser::Component,
+    recurrence::Frequency,
+};
+
+const PRODUCT_ID: &str = "-//Titouan Real//Calendar Manager//EN";
+
+/// Lines longer than this many octets are folded, as required by RFC 5545.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// How many years of transitions are described for recurring events without an end.
+const UNBOUNDED_RECURRENCE_YEARS: i16 = 2;
+
+/// Serialize a calendar as an iCalendar file.
+///
+/// Events in a named time zone keep it, and a `VTIMEZONE` describing the zone around the events
+/// is included. Events in other zones are written in UTC.
+pub fn write(vcalendar: &VCalendar) -> String {
+    let mut writer = Writer::default();
+
+    writer.line("BEGIN", "VCALENDAR");
+    writer.line("VERSION", "2.0");
+    writer.line("PRODID", PRODUCT_ID);
+    writer.line("CALSCALE", "GREGORIAN");
+    if let Some(name) = &vcalendar.name {
+        writer.line("X-WR-CALNAME", &escape_text(name));
+    }
+    if let Some(color) = &vcalendar.color {
+        if color::parse_hex(color).is_some() {
+            writer.line("X-APPLE-CALENDAR-COLOR", color);
+        }
+        if let Some(name) = color::css_name(color) {
+            writer.line("COLOR", name);
+        }
+    }
+
+    for (tzid, (time_zone, years)) in named_time_zones(vcalendar) {
+        write_time_zone(&mut writer, &tzid, &time_zone, years);
+    }
+
+    let stamp = format_utc(jiff::Timestamp::now());
+    for event in &vcalendar.events {
+        write_event(&mut writer, event, &stamp);
+    }
+
+    writer.line("END", "VCALENDAR");
+    writer.output
+}
+
+/// Serialize components as they were parsed, e.g. after changing some of their properties.
+pub(super) fn write_components(components: &[Component]) -> String {
+    let mut writer = Writer::default();
+    for component in components {
+        write_component(&mut writer, component);
+    }
+    writer.output
+}
+
+#[derive(Default)]
+struct Writer {
+    output: String,
+}
+
+impl Writer {
+    fn line(&mut self, name: &str, value: &str) {
+        self.folded(&format!("{name}:{value}"));
+    }
+
+    fn date_time(&mut self, name: &str, date_time: &jiff::Zoned, all_day: bool) {
+        if all_day {
+            self.line(
+                &format!("{name};VALUE=DATE"),
+                &date_time.strftime("%Y%m%d").to_string(),
+            );
+        } else if let Some(tzid) = tzid(date_time.time_zone()) {
+            self.line(
+                &format!("{name};TZID={tzid}"),
+                &date_time.strftime("%Y%m%dT%H%M%S").to_string(),
+            );
+        } else {
+            self.line(name, &format_utc(date_time.timestamp()));
+        }
+    }
+
+    fn folded(&mut self, line: &str) {
+        let mut length = 0;
+        for c in line.chars() {
+            if length + c.len_utf8() > MAX_LINE_LENGTH {
+                self.output.push_str("\r\n ");
+                // The leading space counts towards the length of the continuation line
+                length = 1;
+            }
+            self.output.push(c);
+            length += c.len_utf8();
+        }
+        self.output.push_str("\r\n");
+    }
+}
+
+fn write_event(writer: &mut Writer, event: &VEvent, stamp: &str) {
+    writer.line("BEGIN", "VEVENT");
+    writer.line("UID", &escape_text(&event.uid));
+    writer.line("DTSTAMP", stamp);
+    writer.date_time("DTSTART", &event.start, event.all_day);
+    writer.date_time("DTEND", &event.end, event.all_day);
+    writer.line("SUMMARY", &escape_text(&event.summary));
+    if !event.description.is_empty() {
+        writer.line("DESCRIPTION", &escape_text(&event.description));
+    }
+    if !event.location.is_empty() {
+        writer.line("LOCATION", &escape_text(&event.location));
+    }
+    if let Some(name) = event.color.as_deref().and_then(color::css_name) {
+        writer.line("COLOR", name);
+    }
+    if event.transparent {
+        writer.line("TRANSP", "TRANSPARENT");
+    }
+    if let Some(rrule) = &event.rrule {
+        writer.line("RRULE", &rrule_value(rrule, event));
+    }
+    for exdate in &event.exdates {
+        writer.date_time("EXDATE", exdate, event.all_day);
+    }
+    if let Some(recurrence_id) = &event.recurrence_id {
+        writer.date_time("RECURRENCE-ID", recurrence_id, event.all_day);
+    }
+    for alarm in &event.alarms {
+        write_alarm(writer, alarm);
+    }
+    writer.line("END", "VEVENT");
+}
+
+fn write_component(writer: &mut Writer, component: &Component) {
+    writer.line("BEGIN", &component.name);
+    for property in &component.properties {
+        let mut name = property.name.clone();
+        for (param, value) in &property.params {
+            // The quotes were removed when parsing
+            if value.contains([':', ';', ',']) {
+                let _ = write!(name, ";{param}=\"{value}\"");
+            } else {
+                let _ = write!(name, ";{param}={value}");
+            }
+        }
+        writer.line(&name, &property.value);
+    }
+    for child in &component.children {
+        write_component(writer, child);
+    }
+    writer.line("END", &component.name);
+}
+
+fn write_alarm(writer: &mut Writer, alarm: &VAlarm) {
+    writer.line("BEGIN", "VALARM");
+    writer.line("ACTION", &escape_text(&alarm.action));
+    match alarm.trigger {
+        AlarmTrigger::Relative(offset) => writer.line("TRIGGER", &format_duration(offset)),
+        AlarmTrigger::Absolute(timestamp) => {
+            writer.line("TRIGGER;VALUE=DATE-TIME", &format_utc(timestamp))
+        }
+    }
+    writer.line("DESCRIPTION", &escape_text(&alarm.description));
+    writer.line("END", "VALARM");
+}
+
+/// Describe the time zone from the start of the first year to the end of the last year with
+/// its own `STANDARD` and `DAYLIGHT` observances, one per transition.
+fn write_time_zone(writer: &mut Writer, tzid: &str, time_zone: &TimeZone, years: (i16, i16)) {
+    let (first_year, last_year) = years;
+    let Ok(range_start) = civil::date(first_year, 1, 1).to_zoned(TimeZone::UTC) else {
+        return;
+    };
+    let Ok(range_end) = civil::date(last_year.saturating_add(1), 1, 1).to_zoned(TimeZone::UTC)
+    else {
+        return;
+    };
+
+    writer.line("BEGIN", "VTIMEZONE");
+    writer.line("TZID", tzid);
+
+    let initial_offset = time_zone.to_offset(range_start.timestamp());
+    write_observance(
+        writer,
+        range_start.timestamp(),
+        initial_offset,
+        initial_offset,
+        time_zone,
+    );
+
+    for transition in time_zone
+        .following(range_start.timestamp())
+        .take_while(|transition| transition.timestamp() < range_end.timestamp())
+    {
+        let before = transition
+            .timestamp()
+            .checked_sub(jiff::SignedDuration::from_nanos(1))
+            .unwrap_or(transition.timestamp());
+        write_observance(
+            writer,
+            transition.timestamp(),
+            time_zone.to_offset(before),
+            transition.offset(),
+            time_zone,
+        );
+    }
+
+    writer.line("END", "VTIMEZONE");
+}
+
+fn write_observance(
+    writer: &mut Writer,
+    onset: jiff::Timestamp,
+    offset_from: Offset,
+    offset_to: Offset,
+    time_zone: &TimeZone,
+) {
+    let info = time_zone.to_offset_info(onset);
+    let kind = if info.dst().is_dst() {
+        "DAYLIGHT"
+    } else {
+        "STANDARD"
+    };
+
+    writer.line("BEGIN", kind);
+    // The onset is expressed in the local time in effect before it
+    writer.line(
+        "DTSTART",
+        &offset_from
+            .to_datetime(onset)
+            .strftime("%Y%m%dT%H%M%S")
+            .to_string(),
+    );
+    writer.line("TZOFFSETFROM", &format_offset(offset_from));
+    writer.line("TZOFFSETTO", &format_offset(offset_to));
+    writer.line("TZNAME", &escape_text(info.abbreviation()));
+    writer.line("END", kind);
+}
+
+/// The IANA time zones the events start in, with the range of years they are used in.
+fn named_time_zones(vcalendar: &VCalendar) -> BTreeMap<String, (TimeZone, (i16, i16))> {
+    let mut zones: BTreeMap<String, (TimeZone, (i16, i16))> = BTreeMap::new();
+
+    for event in vcalendar.events.iter().filter(|event| !event.all_day) {
+        let Some(tzid) = tzid(event.start.time_zone()) else {
+            continue;
+        };
+
+        let first_year = event.start.year();
+        let last_year = match &event.rrule {
+            Some(rrule) => match rrule.until {
+                Some(until) => until.to_zoned(event.start.time_zone().clone()).year(),
+                None => first_year.saturating_add(UNBOUNDED_RECURRENCE_YEARS),
+            },
+            None => event.end.year(),
+        };
+
+        zones
+            .entry(tzid.to_owned())
+            .and_modify(|(_, (first, last))| {
+                *first = (*first).min(first_year);
+                *last = (*last).max(last_year);
+            })
+            .or_insert_with(|| (event.start.time_zone().clone(), (first_year, last_year)));
+    }
+
+    zones
+}
+
+/// The name to use in `TZID`, or `None` if the time should be written in UTC.
+pub(super) fn tzid(time_zone: &TimeZone) -> Option<&str> {
+    time_zone
+        .iana_name()
+        .filter(|name| !matches!(*name, "UTC" | "Etc/UTC"))
+}
+
+fn rrule_value(rrule: &RecurrenceRule, event: &VEvent) -> String {
+    let frequency = match rrule.frequency {
+        Frequency::Daily => "DAILY",
+        Frequency::Weekly => "WEEKLY",
+        Frequency::Monthly => "MONTHLY",
+        Frequency::Yearly => "YEARLY",
+    };
+    let mut value = format!("FREQ={frequency}");
+
+    if rrule.interval != 1 {
+        let _ = write!(value, ";INTERVAL={}", rrule.interval);
+    }
+    if let Some(count) = rrule.count {
+        let _ = write!(value, ";COUNT={count}");
+    }
+    if let Some(until) = rrule.until {
+        if event.all_day {
+            let date = until.to_zoned(event.start.time_zone().clone()).date();
+            let _ = write!(value, ";UNTIL={}", date.strftime("%Y%m%d"));
+        } else {
+            let _ = write!(value, ";UNTIL={}", format_utc(until));
+        }
+    }
+    if !rrule.by_day.is_empty() {
+        let days: Vec<String> = rrule
+            .by_day
+            .iter()
+            .map(|(nth, weekday)| {
+                let weekday = match weekday {
+                    civil::Weekday::Monday => "MO",
+                    civil::Weekday::Tuesday => "TU",
+                    civil::Weekday::Wednesday => "WE",
+                    civil::Weekday::Thursday => "TH",
+                    civil::Weekday::Friday => "FR",
+                    civil::Weekday::Saturday => "SA",
+                    civil::Weekday::Sunday => "SU",
+                };
+                match nth {
+                    Some(nth) => format!("{nth}{weekday}"),
+                    None => weekday.to_owned(),
+                }
+            })
+            .collect();
+        let _ = write!(value, ";BYDAY={}", days.join(","));
+    }
+    if !rrule.by_month_day.is_empty() {
+        let _ = write!(value, ";BYMONTHDAY={}", join(&rrule.by_month_day));
+    }
+    if !rrule.by_month.is_empty() {
+        let _ = write!(value, ";BYMONTH={}", join(&rrule.by_month));
+    }
+
+    value
+}
+
+fn join(values: &[i8]) -> String {
+    values
+        .iter()
+        .map(i8::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub(super) fn format_utc(timestamp: jiff::Timestamp) -> String {
+    timestamp.strftime("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_offset(offset: Offset) -> String {
+    let seconds = offset.seconds();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if seconds == 0 {
+        format!("{sign}{hours:02}{minutes:02}")
+    } else {
+        format!("{sign}{hours:02}{minutes:02}{seconds:02}")
+    }
+}
+
+/// Format a duration as in `-PT15M`, using days for whole days.
+fn format_duration(duration: jiff::SignedDuration) -> String {
+    let sign = if duration.is_negative() { "-" } else { "" };
+    let seconds = duration.as_secs().unsigned_abs();
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+
+    let mut value = format!("{sign}P");
+    if days > 0 {
+        let _ = write!(value, "{days}D");
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+        value.push('T');
+        if hours > 0 {
+            let _ = write!(value, "{hours}H");
+        }
+        if minutes > 0 {
+            let _ = write!(value, "{minutes}M");
+        }
+        if seconds > 0 || (hours == 0 && minutes == 0) {
+            let _ = write!(value, "{seconds}S");
+        }
+    }
+    value
+}
+
+pub(super) fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::{SignedDuration, Timestamp, Zoned, civil::Weekday};
+
+    use super::*;
+    use crate::ics::{parse, test_event};
+
+    fn zoned(value: &str) -> Zoned {
+        value.parse().expect("test date should be valid")
+    }
+
+    #[test]
+    fn import_of_export_preserves_events() {
+        let system = TimeZone::system();
+        let all_day_start = civil::date(2026, 12, 24).to_zoned(system.clone()).unwrap();
+        let all_day_end = civil::date(2026, 12, 26).to_zoned(system).unwrap();
+
+        let vcalendar = VCalendar {
+            name: Some("Work, mostly; sometimes not".to_owned()),
+            color: Some("#3584e4".to_owned()),
+            events: vec![
+                VEvent {
+                    summary: "Quarterly review".to_owned(),
+                    description: "Agenda:\n- numbers\n- plans, \\ and a description that is \
+                                  long enough to be folded over several lines in the file"
+                        .to_owned(),
+                    location: "Room 4; 2nd floor".to_owned(),
+                    color: Some("chocolate".to_owned()),
+                    alarms: vec![
+                        VAlarm {
+                            action: "DISPLAY".to_owned(),
+                            description: "Review in 15 minutes".to_owned(),
+                            trigger: AlarmTrigger::Relative(SignedDuration::from_mins(-15)),
+                        },
+                        VAlarm {
+                            action: "DISPLAY".to_owned(),
+                            description: "Review".to_owned(),
+                            trigger: AlarmTrigger::Absolute(
+                                Timestamp::from_second(1_800_000_000).unwrap(),
+                            ),
+                        },
+                    ],
+                    uid: "review@example.com".to_owned(),
+                    start: zoned("2026-03-29T10:00:00[Europe/Paris]"),
+                    end: zoned("2026-03-29T11:30:00[Europe/Paris]"),
+                    ..test_event()
+                },
+                VEvent {
+                    summary: "Standup ☕".to_owned(),
+                    rrule: Some(RecurrenceRule {
+                        frequency: Frequency::Weekly,
+                        interval: 2,
+                        count: None,
+                        until: Some(zoned("2027-06-30T23:00:00Z[UTC]").timestamp()),
+                        by_day: vec![(None, Weekday::Monday), (None, Weekday::Thursday)],
+                        by_month_day: Vec::new(),
+                        by_month: Vec::new(),
+                    }),
+                    exdates: vec![zoned("2026-11-05T09:00:00[America/New_York]")],
+                    uid: "standup".to_owned(),
+                    start: zoned("2026-10-26T09:00:00[America/New_York]"),
+                    end: zoned("2026-10-26T09:15:00[America/New_York]"),
+                    ..test_event()
+                },
+                VEvent {
+                    summary: "Standup, moved".to_owned(),
+                    recurrence_id: Some(zoned("2026-11-09T09:00:00[America/New_York]")),
+                    uid: "standup".to_owned(),
+                    start: zoned("2026-11-09T10:00:00[America/New_York]"),
+                    end: zoned("2026-11-09T10:15:00[America/New_York]"),
+                    ..test_event()
+                },
+                VEvent {
+                    summary: "Holidays".to_owned(),
+                    all_day: true,
+                    transparent: true,
+                    rrule: Some(RecurrenceRule {
+                        frequency: Frequency::Yearly,
+                        interval: 1,
+                        count: Some(3),
+                        until: None,
+                        by_day: Vec::new(),
+                        by_month_day: Vec::new(),
+                        by_month: Vec::new(),
+                    }),
+                    uid: "holidays".to_owned(),
+                    start: all_day_start,
+                    end: all_day_end,
+                    ..test_event()
+                },
+                VEvent {
+                    uid: "utc".to_owned(),
+                    start: zoned("2026-06-01T12:00:00Z[UTC]"),
+                    end: zoned("2026-06-01T12:00:00Z[UTC]"),
+                    ..test_event()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let exported = write(&vcalendar);
+        assert!(
+            exported
+                .lines()
+                .all(|line| line.len() <= MAX_LINE_LENGTH + 1)
+        );
+        assert!(exported.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Paris\r\n"));
+
+        let imported = parse(&exported).expect("exported file should parse");
+        assert_eq!(imported, vcalendar);
+    }
+}
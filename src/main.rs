@@ -1,6 +1,23 @@
 mod application;
+mod astronomy;
+mod birthdays;
+mod caldav;
+mod calendar_properties;
+mod calendar_systems;
+mod cli;
 mod config;
+mod dbus;
+mod event_locations;
+mod format;
+mod holidays;
+mod ics;
+mod mirror;
+mod saved_searches;
+mod search;
+mod subscriptions;
+mod time_zones;
 mod utils;
+mod vdir;
 mod widgets;
 
 use self::application::CalendarManagerApplication;
@@ -31,8 +48,13 @@ fn main() -> glib::ExitCode {
 
     let app = CalendarManagerApplication::new(
         "io.gitlab.TitouanReal.CalendarManager",
-        &gio::ApplicationFlags::empty(),
+        &(gio::ApplicationFlags::HANDLES_OPEN | gio::ApplicationFlags::HANDLES_COMMAND_LINE),
     );
 
-    app.run()
+    let exit_code = app.run();
+    // Commands finish after the main loop started, whose status GApplication does not return
+    match app.command_exit_code() {
+        Some(command_exit_code) if exit_code == glib::ExitCode::SUCCESS => command_exit_code,
+        _ => exit_code,
+    }
 }
@@ -0,0 +1,891 @@
+//! Two-way sync of local collections with calendars stored elsewhere, such as CalDAV servers
+//! or folders of iCalendar files.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use ccm::{Calendar, Collection, Event, Timeframe, jiff};
+use gettextrs::gettext;
+use gtk::{gdk, glib, prelude::*};
+use tracing::{debug, warn};
+
+mod source;
+mod state;
+
+use crate::{
+    CalendarManagerApplication,
+    ics::{self, ImportPlan, VCalendar, VEvent},
+};
+
+use self::state::{CalendarState, ResourceState};
+pub use self::{
+    source::{Source, SourceImpl},
+    state::SyncState,
+};
+
+/// A calendar found in a store.
+#[derive(Debug, Clone)]
+pub struct RemoteCalendar {
+    pub href: String,
+    pub name: String,
+    pub color: Option<String>,
+}
+
+/// An iCalendar resource downloaded from a store.
+#[derive(Debug, Clone)]
+pub struct RemoteResource {
+    pub href: String,
+    pub etag: Option<String>,
+    pub data: String,
+}
+
+/// The resources of a calendar that changed since the last sync.
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// The resources that were added or modified, with their new ETag.
+    pub changed: Vec<(String, String)>,
+    pub removed: Vec<String>,
+    /// The token to send at the next sync, if the store supports sync tokens.
+    pub sync_token: Option<String>,
+    /// Whether `changed` lists all resources, in which case the missing ones were removed.
+    pub complete: bool,
+}
+
+/// Why a sync failed.
+#[derive(Debug)]
+pub enum SyncError<E> {
+    Store(E),
+    /// The store lists no calendars anymore, although calendars were synced with it before.
+    ///
+    /// This is more likely an unavailable store, like an unmounted folder, than the removal of all
+    /// its calendars, so the local calendars are kept and nothing is synced.
+    NoCalendars,
+}
+
+impl<E: fmt::Display> fmt::Display for SyncError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Store(error) => write!(f, "{error}"),
+            Self::NoCalendars => write!(
+                f,
+                "{}",
+                gettext("No calendars were found, the local calendars were kept")
+            ),
+        }
+    }
+}
+
+/// What a store checks before replacing a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition<'a> {
+    /// The resource is new, it must not exist yet.
+    Absent,
+    /// The resource must not have changed since it had this ETag.
+    Matches(&'a str),
+    /// The ETag of the resource is not known, it is replaced whatever its version.
+    Any,
+}
+
+impl<'a> Precondition<'a> {
+    /// The precondition to replace a resource last seen with `etag`, which is empty if the store
+    /// did not tell it.
+    fn existing(etag: &'a str) -> Self {
+        if etag.is_empty() {
+            Self::Any
+        } else {
+            Self::Matches(etag)
+        }
+    }
+}
+
+/// Where the calendars of a collection are stored, as iCalendar resources identified by an
+/// href and versioned by an ETag.
+pub(crate) trait Store {
+    type Error: fmt::Display;
+
+    async fn calendars(&self) -> Result<Vec<RemoteCalendar>, Self::Error>;
+
+    /// Create a calendar for a local one, if the store supports it.
+    async fn create_calendar(
+        &self,
+        _name: &str,
+        _color: &str,
+    ) -> Result<Option<RemoteCalendar>, Self::Error> {
+        Ok(None)
+    }
+
+    /// List the resources that changed in the calendar at `href` since `sync_token`, which is
+    /// empty at the first sync.
+    async fn changes(&self, href: &str, sync_token: &str) -> Result<Changes, Self::Error>;
+
+    /// Download the resources at `hrefs` of the calendar at `href`.
+    async fn fetch(&self, href: &str, hrefs: &[String])
+    -> Result<Vec<RemoteResource>, Self::Error>;
+
+    /// Upload a resource if `precondition` holds, returning its new ETag if known.
+    async fn put(
+        &self,
+        href: &str,
+        precondition: Precondition<'_>,
+        data: String,
+    ) -> Result<Option<String>, Self::Error>;
+
+    /// Remove a resource, if it still has `etag` when it is known.
+    async fn delete(&self, href: &str, etag: Option<&str>) -> Result<(), Self::Error>;
+
+    /// The href of a new resource for the event `uid` in the calendar at `href`.
+    fn resource_href(&self, href: &str, uid: &str) -> String;
+
+    /// Whether `error` means that the resource changed in the store in the meantime.
+    fn is_conflict(error: &Self::Error) -> bool;
+}
+
+/// A change made to a local event since the last sync.
+enum LocalChange {
+    Modified(Event),
+    Deleted,
+}
+
+/// A local change to write to the store.
+enum Upload {
+    /// A new event, with its fingerprint.
+    Create(Box<VEvent>, String),
+    /// A modified event, with its fingerprint.
+    Put(Box<VEvent>, String),
+    Delete,
+}
+
+/// Sync the calendars of `collection` with the ones of `store`, in both directions.
+///
+/// Events modified on both sides since the last sync are conflicts: the stored version is kept
+/// and the local version is saved as a copy, to be uploaded at the next sync. Returns the number
+/// of conflicts.
+pub async fn sync<S: Store>(
+    store: &S,
+    collection: &Collection,
+    state: &mut SyncState,
+) -> Result<usize, SyncError<S::Error>> {
+    let mut remote_calendars = store.calendars().await.map_err(SyncError::Store)?;
+    if remote_calendars.is_empty() && !state.calendars.is_empty() {
+        return Err(SyncError::NoCalendars);
+    }
+    let local_calendars: HashMap<String, Calendar> = collection
+        .calendars()
+        .iter::<Calendar>()
+        .filter_map(Result::ok)
+        .map(|calendar| (calendar.uri(), calendar))
+        .collect();
+
+    // Calendars removed from the store are removed locally too
+    state.calendars.retain(|href, calendar_state| {
+        let exists = remote_calendars.iter().any(|remote| &remote.href == href);
+        if let Some(calendar) = local_calendars
+            .get(&calendar_state.calendar_uri)
+            .filter(|_| !exists)
+        {
+            CalendarManagerApplication::default().forget_calendar(calendar);
+            calendar.delete();
+        }
+        exists
+    });
+
+    // Calendars created locally are created in the store too
+    let tracked: HashSet<&String> = state
+        .calendars
+        .values()
+        .map(|calendar_state| &calendar_state.calendar_uri)
+        .collect();
+    let mut created = Vec::new();
+    for (uri, calendar) in &local_calendars {
+        if tracked.contains(uri) {
+            continue;
+        }
+        let color = ics::format_color(&calendar.color());
+        if let Some(remote) = store
+            .create_calendar(&calendar.name(), &color)
+            .await
+            .map_err(SyncError::Store)?
+        {
+            created.push((remote, uri.clone()));
+        }
+    }
+    for (remote, calendar_uri) in created {
+        state.calendars.insert(
+            remote.href.clone(),
+            CalendarState {
+                calendar_uri,
+                ..CalendarState::default()
+            },
+        );
+        remote_calendars.push(remote);
+    }
+
+    let mut conflicts = 0;
+    for remote in remote_calendars {
+        let calendar_state = state.calendars.entry(remote.href.clone()).or_default();
+        let calendar = match local_calendars.get(&calendar_state.calendar_uri) {
+            Some(calendar) => calendar.clone(),
+            None => {
+                let color = remote
+                    .color
+                    .as_deref()
+                    .and_then(|color| gdk::RGBA::parse(color).ok())
+                    .unwrap_or(gdk::RGBA::BLUE);
+                let calendar = collection.create_calendar(&remote.name, color);
+                *calendar_state = CalendarState {
+                    calendar_uri: calendar.uri(),
+                    ..CalendarState::default()
+                };
+                calendar
+            }
+        };
+
+        conflicts += sync_calendar(store, &remote.href, &calendar, calendar_state)
+            .await
+            .map_err(SyncError::Store)?;
+    }
+
+    Ok(conflicts)
+}
+
+async fn sync_calendar<S: Store>(
+    store: &S,
+    href: &str,
+    calendar: &Calendar,
+    state: &mut CalendarState,
+) -> Result<usize, S::Error> {
+    let mut changes = store.changes(href, &state.sync_token).await?;
+    let sync_token = changes.sync_token.take();
+    let (remote_changed, remote_removed) = remote_changes(changes, &state.resources);
+
+    let local_events: HashMap<String, Event> = calendar
+        .events()
+        .iter::<Event>()
+        .filter_map(Result::ok)
+        .map(|event| (event.uid(), event))
+        .collect();
+    let mut local_changes = local_changes(&state.resources, &local_events);
+    let known_uids: HashSet<&String> = state
+        .resources
+        .values()
+        .flat_map(|resource| &resource.uids)
+        .collect();
+    let mut created: Vec<Event> = local_events
+        .iter()
+        .filter(|(uid, _)| !known_uids.contains(uid))
+        .map(|(_, event)| event.clone())
+        .collect();
+
+    let mut conflicts = 0;
+
+    for resource_href in remote_removed {
+        let Some(resource) = state.resources.remove(&resource_href) else {
+            continue;
+        };
+        match local_changes.remove(&resource_href) {
+            // Keep the local changes, as a new resource
+            Some(LocalChange::Modified(event)) => created.push(event),
+            _ => {
+                for uid in &resource.uids {
+                    if let Some(event) = local_events.get(uid) {
+                        event.delete();
+                    }
+                }
+            }
+        }
+    }
+
+    let hrefs: Vec<String> = remote_changed.keys().cloned().collect();
+    for resource in store.fetch(href, &hrefs).await? {
+        let vcalendar = match ics::parse(&resource.data) {
+            Ok(vcalendar) => vcalendar,
+            Err(error) => {
+                warn!("Ignoring invalid resource {}: {error}", resource.href);
+                continue;
+            }
+        };
+
+        if let Some(LocalChange::Modified(event)) = local_changes.remove(&resource.href) {
+            debug!(
+                "Conflict on {}, keeping a copy of the local event",
+                resource.href
+            );
+            save_conflicted_copy(calendar, &event);
+            conflicts += 1;
+        }
+
+        let events = ics::expand_recurrences(&vcalendar);
+        let uids: Vec<String> = events.iter().map(|event| event.uid.clone()).collect();
+        ImportPlan::new(&vcalendar, calendar).apply().await;
+
+        if let Some(previous) = state.resources.get(&resource.href) {
+            for uid in previous.uids.iter().filter(|uid| !uids.contains(uid)) {
+                if let Some(event) = local_events.get(uid) {
+                    event.delete();
+                }
+            }
+        }
+
+        let fingerprint = match events.as_slice() {
+            [event] if vcalendar.events.len() == 1 && event.rrule.is_none() => {
+                vevent_fingerprint(event)
+            }
+            _ => String::new(),
+        };
+        let etag = resource
+            .etag
+            .or_else(|| remote_changed.get(&resource.href).cloned())
+            .unwrap_or_default();
+        state.resources.insert(
+            resource.href,
+            ResourceState {
+                etag,
+                uids,
+                fingerprint,
+                data: resource.data,
+            },
+        );
+    }
+
+    // Events that were just downloaded are not new, e.g. when the sync state was lost
+    let fetched_uids: HashSet<&String> = state
+        .resources
+        .values()
+        .flat_map(|resource| &resource.uids)
+        .collect();
+    created.retain(|event| !fetched_uids.contains(&event.uid()));
+
+    let mut uploads = Vec::new();
+    for (resource_href, change) in local_changes {
+        let upload = match change {
+            LocalChange::Modified(event) => match VEvent::from_event(&event, None) {
+                Some(vevent) => Upload::Put(Box::new(vevent), event_fingerprint(&event)),
+                None => continue,
+            },
+            LocalChange::Deleted => Upload::Delete,
+        };
+        uploads.push((resource_href, upload));
+    }
+    for event in created {
+        let Some(vevent) = VEvent::from_event(&event, None) else {
+            continue;
+        };
+        let resource_href = store.resource_href(href, &vevent.uid);
+        uploads.push((
+            resource_href,
+            Upload::Create(Box::new(vevent), event_fingerprint(&event)),
+        ));
+    }
+    conflicts += upload(store, href, &mut state.resources, uploads).await?;
+
+    state.sync_token = sync_token.unwrap_or_default();
+    Ok(conflicts)
+}
+
+/// The resources that changed in the store since the last sync, with their new ETag, and the
+/// ones that were removed.
+fn remote_changes(
+    changes: Changes,
+    resources: &HashMap<String, ResourceState>,
+) -> (HashMap<String, String>, HashSet<String>) {
+    let listed: HashSet<String> = changes
+        .changed
+        .iter()
+        .map(|(resource_href, _)| resource_href.clone())
+        .collect();
+    let changed = changes
+        .changed
+        .into_iter()
+        .filter(|(resource_href, etag)| {
+            resources
+                .get(resource_href)
+                .is_none_or(|resource| &resource.etag != etag)
+        })
+        .collect();
+    let mut removed: HashSet<String> = changes.removed.into_iter().collect();
+    if changes.complete {
+        removed.extend(
+            resources
+                .keys()
+                .filter(|resource_href| !listed.contains(*resource_href))
+                .cloned(),
+        );
+    }
+    (changed, removed)
+}
+
+/// The events modified or deleted locally since the last sync.
+///
+/// Only resources holding a single event are considered, the occurrences of recurring events
+/// cannot be uploaded.
+fn local_changes(
+    resources: &HashMap<String, ResourceState>,
+    local_events: &HashMap<String, Event>,
+) -> HashMap<String, LocalChange> {
+    resources
+        .iter()
+        .filter(|(_, resource)| !resource.fingerprint.is_empty())
+        .filter_map(|(href, resource)| {
+            let [uid] = resource.uids.as_slice() else {
+                return None;
+            };
+            let change = match local_events.get(uid) {
+                None => LocalChange::Deleted,
+                Some(event) if event_fingerprint(event) != resource.fingerprint => {
+                    LocalChange::Modified(event.clone())
+                }
+                Some(_) => return None,
+            };
+            Some((href.clone(), change))
+        })
+        .collect()
+}
+
+/// Write the local changes to the calendar at `calendar_href` of the store, returning the number
+/// of conflicts.
+///
+/// Modified events are applied to the object last fetched from the store, so that what local
+/// events cannot hold, like attendees and alarms, is kept. New events, and objects that cannot be
+/// patched, are written from scratch.
+async fn upload<S: Store>(
+    store: &S,
+    calendar_href: &str,
+    resources: &mut HashMap<String, ResourceState>,
+    uploads: Vec<(String, Upload)>,
+) -> Result<usize, S::Error> {
+    let mut conflicts = 0;
+
+    for (href, upload) in uploads {
+        let previous = resources
+            .get(&href)
+            .map(|resource| (resource.etag.clone(), resource.data.clone()));
+        let result = match upload {
+            Upload::Create(vevent, fingerprint) => {
+                let data = ics::write(&VCalendar {
+                    events: vec![(*vevent).clone()],
+                    ..VCalendar::default()
+                });
+                let mut result = store.put(&href, Precondition::Absent, data.clone()).await;
+                // The href is taken by another resource, e.g. one whose UID has the same file
+                // name: a random one is used, otherwise the event would be refused at every sync
+                let href = match &result {
+                    Err(error) if S::is_conflict(error) => {
+                        let href = store.resource_href(calendar_href, &glib::uuid_string_random());
+                        result = store.put(&href, Precondition::Absent, data.clone()).await;
+                        href
+                    }
+                    _ => href,
+                };
+                result.map(|etag| {
+                    resources.insert(
+                        href,
+                        ResourceState {
+                            etag: etag.unwrap_or_default(),
+                            uids: vec![vevent.uid],
+                            fingerprint,
+                            data,
+                        },
+                    );
+                })
+            }
+            Upload::Put(vevent, fingerprint) => {
+                let data = previous
+                    .as_ref()
+                    .and_then(|(_, data)| ics::patch_event(data, &vevent))
+                    .unwrap_or_else(|| {
+                        ics::write(&VCalendar {
+                            events: vec![(*vevent).clone()],
+                            ..VCalendar::default()
+                        })
+                    });
+                let precondition = match &previous {
+                    Some((etag, _)) => Precondition::existing(etag),
+                    None => Precondition::Absent,
+                };
+                store
+                    .put(&href, precondition, data.clone())
+                    .await
+                    .map(|etag| {
+                        resources.insert(
+                            href,
+                            ResourceState {
+                                etag: etag.unwrap_or_default(),
+                                uids: vec![vevent.uid],
+                                fingerprint,
+                                data,
+                            },
+                        );
+                    })
+            }
+            Upload::Delete => {
+                let Some((etag, _)) = previous else {
+                    continue;
+                };
+                let etag = Some(etag.as_str()).filter(|etag| !etag.is_empty());
+                store.delete(&href, etag).await.map(|()| {
+                    resources.remove(&href);
+                })
+            }
+        };
+        match result {
+            Ok(()) => (),
+            // Changed in the store in the meantime, the next sync handles it
+            Err(error) if S::is_conflict(&error) => conflicts += 1,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// Save the local version of a conflicting event as a new event.
+fn save_conflicted_copy(calendar: &Calendar, event: &Event) {
+    let Some(timeframe) = event.timeframe() else {
+        return;
+    };
+    // Translators: The name of a copy of an event modified both locally and remotely
+    let name = gettext("{} (Conflicted Copy)").replace("{}", &event.name());
+    calendar.import_event(
+        &glib::uuid_string_random(),
+        &name,
+        &event.description(),
+        timeframe,
+    );
+}
+
+fn event_fingerprint(event: &Event) -> String {
+    let timeframe = event.timeframe();
+    fingerprint(
+        &event.name(),
+        &event.description(),
+        timeframe
+            .as_ref()
+            .map(|timeframe| timeframe.start().timestamp()),
+        timeframe
+            .as_ref()
+            .map(|timeframe| timeframe.end().timestamp()),
+        timeframe.as_ref().is_some_and(Timeframe::all_day),
+    )
+}
+
+fn vevent_fingerprint(event: &VEvent) -> String {
+    fingerprint(
+        &event.summary,
+        &event.description,
+        Some(event.start.timestamp()),
+        Some(event.end.timestamp()),
+        event.all_day,
+    )
+}
+
+fn fingerprint(
+    name: &str,
+    description: &str,
+    start: Option<jiff::Timestamp>,
+    end: Option<jiff::Timestamp>,
+    all_day: bool,
+) -> String {
+    let format = |timestamp: Option<jiff::Timestamp>| {
+        timestamp
+            .map(|timestamp| timestamp.to_string())
+            .unwrap_or_default()
+    };
+    format!(
+        "{name}\u{1f}{description}\u{1f}{}\u{1f}{}\u{1f}{all_day}",
+        format(start),
+        format(end)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+    use crate::ics::{TEST_OBJECT, test_event};
+
+    #[derive(Debug)]
+    struct Conflict;
+
+    impl fmt::Display for Conflict {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "conflict")
+        }
+    }
+
+    /// A store of a single calendar keeping its resources in memory, by href, with their ETag.
+    #[derive(Default)]
+    struct MemoryStore {
+        resources: RefCell<HashMap<String, (String, String)>>,
+        version: Cell<u32>,
+    }
+
+    impl MemoryStore {
+        /// Add or replace a resource as another client would, returning its new ETag.
+        fn insert(&self, href: &str, data: &str) -> String {
+            self.version.set(self.version.get() + 1);
+            let etag = format!("\"{}\"", self.version.get());
+            self.resources
+                .borrow_mut()
+                .insert(href.to_owned(), (etag.clone(), data.to_owned()));
+            etag
+        }
+
+        fn data(&self, href: &str) -> Option<String> {
+            self.resources
+                .borrow()
+                .get(href)
+                .map(|(_, data)| data.clone())
+        }
+    }
+
+    impl Store for MemoryStore {
+        type Error = Conflict;
+
+        async fn calendars(&self) -> Result<Vec<RemoteCalendar>, Conflict> {
+            Ok(Vec::new())
+        }
+
+        async fn changes(&self, _href: &str, _sync_token: &str) -> Result<Changes, Conflict> {
+            Ok(Changes {
+                changed: self
+                    .resources
+                    .borrow()
+                    .iter()
+                    .map(|(href, (etag, _))| (href.clone(), etag.clone()))
+                    .collect(),
+                complete: true,
+                ..Changes::default()
+            })
+        }
+
+        async fn fetch(
+            &self,
+            _href: &str,
+            hrefs: &[String],
+        ) -> Result<Vec<RemoteResource>, Conflict> {
+            let resources = self.resources.borrow();
+            Ok(hrefs
+                .iter()
+                .filter_map(|href| {
+                    let (etag, data) = resources.get(href)?;
+                    Some(RemoteResource {
+                        href: href.clone(),
+                        etag: Some(etag.clone()),
+                        data: data.clone(),
+                    })
+                })
+                .collect())
+        }
+
+        async fn put(
+            &self,
+            href: &str,
+            precondition: Precondition<'_>,
+            data: String,
+        ) -> Result<Option<String>, Conflict> {
+            let current = self
+                .resources
+                .borrow()
+                .get(href)
+                .map(|(etag, _)| etag.clone());
+            let allowed = match precondition {
+                Precondition::Absent => current.is_none(),
+                Precondition::Matches(etag) => current.as_deref() == Some(etag),
+                Precondition::Any => true,
+            };
+            if !allowed {
+                return Err(Conflict);
+            }
+            Ok(Some(self.insert(href, &data)))
+        }
+
+        async fn delete(&self, href: &str, etag: Option<&str>) -> Result<(), Conflict> {
+            let mut resources = self.resources.borrow_mut();
+            let current = resources.get(href).map(|(current, _)| current.as_str());
+            if etag.is_some_and(|etag| current.is_some_and(|current| current != etag)) {
+                return Err(Conflict);
+            }
+            resources.remove(href);
+            Ok(())
+        }
+
+        fn resource_href(&self, href: &str, uid: &str) -> String {
+            format!("{href}/{uid}.ics")
+        }
+
+        fn is_conflict(_error: &Conflict) -> bool {
+            true
+        }
+    }
+
+    fn resource(etag: &str) -> ResourceState {
+        ResourceState {
+            etag: etag.to_owned(),
+            uids: vec![test_event().uid],
+            fingerprint: vevent_fingerprint(&test_event()),
+            data: TEST_OBJECT.to_owned(),
+        }
+    }
+
+    fn run<T>(future: impl Future<Output = T>) -> T {
+        glib::MainContext::default().block_on(future)
+    }
+
+    #[test]
+    fn remote_changes_are_found() {
+        let resources = HashMap::from([
+            ("unchanged".to_owned(), resource("1")),
+            ("modified".to_owned(), resource("1")),
+            ("removed".to_owned(), resource("1")),
+        ]);
+        let changes = || Changes {
+            changed: vec![
+                ("unchanged".to_owned(), "1".to_owned()),
+                ("modified".to_owned(), "2".to_owned()),
+                ("added".to_owned(), "1".to_owned()),
+            ],
+            ..Changes::default()
+        };
+
+        let (changed, removed) = remote_changes(changes(), &resources);
+        assert_eq!(
+            changed,
+            HashMap::from([
+                ("modified".to_owned(), "2".to_owned()),
+                ("added".to_owned(), "1".to_owned()),
+            ])
+        );
+        assert!(removed.is_empty());
+
+        // Resources missing from a complete listing were removed
+        let complete = Changes {
+            complete: true,
+            ..changes()
+        };
+        let (_, removed) = remote_changes(complete, &resources);
+        assert_eq!(removed, HashSet::from(["removed".to_owned()]));
+    }
+
+    #[test]
+    fn modified_events_are_patched() {
+        let store = MemoryStore::default();
+        let etag = store.insert("/calendar/review.ics", TEST_OBJECT);
+        let mut resources = HashMap::from([("/calendar/review.ics".to_owned(), resource(&etag))]);
+
+        let event = VEvent {
+            summary: "Yearly review".to_owned(),
+            ..test_event()
+        };
+        let fingerprint = vevent_fingerprint(&event);
+        let uploads = vec![(
+            "/calendar/review.ics".to_owned(),
+            Upload::Put(Box::new(event), fingerprint.clone()),
+        )];
+        assert_eq!(
+            run(upload(&store, "/calendar", &mut resources, uploads)).ok(),
+            Some(0)
+        );
+
+        let data = store
+            .data("/calendar/review.ics")
+            .expect("resource should be stored");
+        assert!(data.contains("SUMMARY;LANGUAGE=en:Yearly review\r\n"));
+        assert!(data.contains("LOCATION:Room 4\\; 2nd floor\r\n"));
+        assert!(
+            data.contains(
+                "ATTENDEE;CN=\"Doe, Jane\";PARTSTAT=ACCEPTED:mailto:jane@example.com\r\n"
+            )
+        );
+        assert!(data.contains("BEGIN:VALARM\r\n"));
+
+        let resource = &resources["/calendar/review.ics"];
+        assert_ne!(resource.etag, etag);
+        assert_eq!(resource.fingerprint, fingerprint);
+        assert_eq!(resource.data, data);
+    }
+
+    #[test]
+    fn stale_resources_are_conflicts() {
+        let store = MemoryStore::default();
+        let etag = store.insert("/calendar/review.ics", TEST_OBJECT);
+        let mut resources = HashMap::from([("/calendar/review.ics".to_owned(), resource(&etag))]);
+        // Modified by another client since the last sync
+        store.insert("/calendar/review.ics", "remote");
+
+        let uploads = vec![
+            (
+                "/calendar/review.ics".to_owned(),
+                Upload::Put(Box::new(test_event()), String::new()),
+            ),
+            ("/calendar/review.ics".to_owned(), Upload::Delete),
+        ];
+        assert_eq!(
+            run(upload(&store, "/calendar", &mut resources, uploads)).ok(),
+            Some(2)
+        );
+        assert_eq!(
+            store.data("/calendar/review.ics").as_deref(),
+            Some("remote")
+        );
+        assert_eq!(resources["/calendar/review.ics"].etag, etag);
+    }
+
+    #[test]
+    fn new_events_do_not_replace_resources() {
+        let store = MemoryStore::default();
+        store.insert("/calendar/taken.ics", "remote");
+        let mut resources = HashMap::new();
+
+        let uploads = vec![(
+            "/calendar/taken.ics".to_owned(),
+            Upload::Create(Box::new(test_event()), String::new()),
+        )];
+        assert_eq!(
+            run(upload(&store, "/calendar", &mut resources, uploads)).ok(),
+            Some(0)
+        );
+        assert_eq!(store.data("/calendar/taken.ics").as_deref(), Some("remote"));
+        assert!(!resources.contains_key("/calendar/taken.ics"));
+
+        // Written at another href, so that it is not refused again at the next sync
+        let [(href, resource)] = Vec::from_iter(&resources)[..] else {
+            panic!("the event should be uploaded once");
+        };
+        assert!(href.starts_with("/calendar/") && href.ends_with(".ics"));
+        assert_eq!(resource.uids, vec![test_event().uid]);
+        assert_eq!(
+            ics::parse(&resource.data).unwrap().events,
+            vec![test_event()]
+        );
+        assert_eq!(store.data(href).as_ref(), Some(&resource.data));
+    }
+
+    #[test]
+    fn unknown_etags_are_not_checked() {
+        let store = MemoryStore::default();
+        store.insert("/calendar/review.ics", TEST_OBJECT);
+        store.insert("/calendar/removed.ics", TEST_OBJECT);
+        let mut resources = HashMap::from([
+            ("/calendar/review.ics".to_owned(), resource("")),
+            ("/calendar/removed.ics".to_owned(), resource("")),
+        ]);
+
+        let uploads = vec![
+            (
+                "/calendar/review.ics".to_owned(),
+                Upload::Put(Box::new(test_event()), String::new()),
+            ),
+            ("/calendar/removed.ics".to_owned(), Upload::Delete),
+        ];
+        assert_eq!(
+            run(upload(&store, "/calendar", &mut resources, uploads)).ok(),
+            Some(0)
+        );
+        assert!(store.data("/calendar/review.ics").is_some());
+        assert_eq!(store.data("/calendar/removed.ics"), None);
+        assert!(!resources.contains_key("/calendar/removed.ics"));
+    }
+}
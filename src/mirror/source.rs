@@ -0,0 +1,140 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+};
+
+use ccm::jiff;
+use gtk::{
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use tracing::{debug, warn};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Source)]
+    pub struct Source {
+        /// A random identifier, used to find the sync state and the secrets of the source.
+        #[property(get, set, construct_only)]
+        id: RefCell<String>,
+        /// The URI of the collection holding the calendars of the source.
+        #[property(get, set, construct_only)]
+        collection_uri: RefCell<String>,
+        /// The Unix time of the last successful sync, or 0 if it never succeeded.
+        #[property(get, set)]
+        last_synced: Cell<i64>,
+        /// Why the last sync failed, if it did.
+        #[property(get, set, nullable)]
+        last_error: RefCell<Option<String>>,
+        /// The number of events modified on both sides at the last sync.
+        #[property(get, set)]
+        conflicts: Cell<u32>,
+        #[property(get, set)]
+        syncing: Cell<bool>,
+        pub timeout: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Source {
+        const NAME: &'static str = "MirrorSource";
+        const ABSTRACT: bool = true;
+        type Type = super::Source;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Source {
+        fn dispose(&self) {
+            if let Some(timeout) = self.timeout.take() {
+                timeout.remove();
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Somewhere calendars are stored and synced with a local collection.
+    pub struct Source(ObjectSubclass<imp::Source>);
+}
+
+pub trait SourceImpl: ObjectImpl {}
+
+unsafe impl<T: SourceImpl> IsSubclassable<T> for Source {}
+
+impl Source {
+    /// Run `sync` unless a sync is already running, and record its outcome.
+    ///
+    /// `sync` returns the number of conflicts.
+    pub async fn record_sync(&self, sync: impl Future<Output = Result<usize, String>>) {
+        if self.syncing() {
+            return;
+        }
+
+        self.set_syncing(true);
+        let result = sync.await;
+        self.set_syncing(false);
+
+        match result {
+            Ok(conflicts) => {
+                debug!("Synced {} with {conflicts} conflicts", self.id());
+                self.set_last_synced(jiff::Timestamp::now().as_second());
+                self.set_last_error(None::<String>);
+                self.set_conflicts(conflicts as u32);
+            }
+            Err(error) => {
+                warn!("Could not sync {}: {error}", self.id());
+                self.set_last_error(Some(error));
+            }
+        }
+    }
+
+    /// Call `callback` in `seconds`, replacing the previously scheduled call.
+    pub fn schedule(&self, seconds: u32, callback: impl FnOnce(&Self) + 'static) {
+        if let Some(timeout) = self.imp().timeout.take() {
+            timeout.remove();
+        }
+
+        let timeout = glib::timeout_add_seconds_local_once(
+            seconds,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.imp().timeout.take();
+                    callback(&obj);
+                }
+            ),
+        );
+        self.imp().timeout.replace(Some(timeout));
+    }
+
+    /// Cancel the scheduled call, if any.
+    pub fn unschedule(&self) {
+        if let Some(timeout) = self.imp().timeout.take() {
+            timeout.remove();
+        }
+    }
+
+    /// Save the sync status of the source in `dict`.
+    pub fn save_status(&self, dict: &glib::VariantDict) {
+        dict.insert("id", self.id());
+        dict.insert("collection-uri", self.collection_uri());
+        dict.insert("last-synced", self.last_synced());
+        if let Some(last_error) = self.last_error() {
+            dict.insert("last-error", last_error);
+        }
+    }
+
+    /// Restore the sync status saved with [`Source::save_status`].
+    pub fn restore_status(&self, dict: &glib::VariantDict) {
+        self.set_last_synced(
+            dict.lookup::<i64>("last-synced")
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+        );
+        self.set_last_error(dict.lookup::<String>("last-error").ok().flatten());
+    }
+}
@@ -0,0 +1,77 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use gtk::glib::{self, prelude::*};
+use tracing::warn;
+
+/// What was known of a resource on the server at the last sync.
+#[derive(Debug, Default, Clone, glib::Variant)]
+pub struct ResourceState {
+    pub etag: String,
+    /// The UIDs of the local events created from the resource.
+    pub uids: Vec<String>,
+    /// The content of the local event at the last sync, to detect local changes.
+    ///
+    /// Empty for recurring events, whose occurrences are stored as separate events and are only
+    /// downloaded.
+    pub fingerprint: String,
+    /// The iCalendar object as last fetched or uploaded, which local changes are applied to.
+    pub data: String,
+}
+
+#[derive(Debug, Default, Clone, glib::Variant)]
+pub struct CalendarState {
+    /// The URI of the local calendar mirroring the remote one.
+    pub calendar_uri: String,
+    pub sync_token: String,
+    /// The resources of the calendar, by href.
+    pub resources: HashMap<String, ResourceState>,
+}
+
+/// The sync state of a source, saved in the user data directory.
+#[derive(Debug, Default, Clone, glib::Variant)]
+pub struct SyncState {
+    /// The calendars of the source, by href.
+    pub calendars: HashMap<String, CalendarState>,
+}
+
+impl SyncState {
+    pub fn load(source_id: &str) -> Self {
+        let Ok(data) = std::fs::read(path(source_id)) else {
+            return Self::default();
+        };
+        glib::Variant::from_data::<Self, _>(data)
+            .get()
+            .unwrap_or_else(|| {
+                warn!("Ignoring invalid sync state of {source_id}");
+                Self::default()
+            })
+    }
+
+    pub fn save(&self, source_id: &str) {
+        let path = path(source_id);
+        let parent = path.parent().expect("state path should have a parent");
+        if let Err(error) = std::fs::create_dir_all(parent) {
+            warn!("Could not create {}: {error}", parent.display());
+            return;
+        }
+        if let Err(error) = glib::file_set_contents(&path, &self.to_variant().data_as_bytes()) {
+            warn!("Could not save the sync state of {source_id}: {error}");
+        }
+    }
+
+    /// Forget the sync state of a source that was removed.
+    pub fn delete(source_id: &str) {
+        match std::fs::remove_file(path(source_id)) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => warn!("Could not delete the sync state of {source_id}: {error}"),
+        }
+    }
+}
+
+fn path(source_id: &str) -> PathBuf {
+    glib::user_data_dir()
+        .join("calendar-manager")
+        .join("sync")
+        .join(format!("{source_id}.state"))
+}
@@ -0,0 +1,126 @@
+//! Searches saved as smart calendars, whose events are the live results of a query.
+
+use std::cell::OnceCell;
+
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use tracing::warn;
+
+mod smart_calendar;
+
+pub use self::smart_calendar::SmartCalendar;
+
+/// The colors given to new smart calendars, in turn.
+const COLORS: [&str; 6] = [
+    "#3584e4", "#2190a4", "#3a944a", "#c88800", "#e66100", "#9141ac",
+];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct SavedSearches {
+        pub settings: OnceCell<gio::Settings>,
+        pub model: OnceCell<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SavedSearches {
+        const NAME: &'static str = "SavedSearches";
+        type Type = super::SavedSearches;
+    }
+
+    impl ObjectImpl for SavedSearches {}
+}
+
+glib::wrapper! {
+    /// The smart calendars of the user, saved in the settings.
+    pub struct SavedSearches(ObjectSubclass<imp::SavedSearches>);
+}
+
+impl SavedSearches {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+        imp.settings
+            .set(settings.clone())
+            .expect("settings should only be set once");
+
+        let model = gio::ListStore::new::<SmartCalendar>();
+        for variant in settings.value("saved-searches").iter() {
+            match SmartCalendar::from_variant(&variant) {
+                Some(smart_calendar) => {
+                    obj.watch(&smart_calendar);
+                    model.append(&smart_calendar);
+                }
+                None => warn!("Ignoring invalid saved search: {variant}"),
+            }
+        }
+        imp.model.set(model).expect("model should only be set once");
+
+        obj
+    }
+
+    pub fn model(&self) -> gio::ListStore {
+        self.imp()
+            .model
+            .get()
+            .expect("model should be initialized")
+            .clone()
+    }
+
+    /// Save `query` as a smart calendar named `name`.
+    pub fn add(&self, name: &str, query: &str) -> SmartCalendar {
+        let model = self.model();
+        let color = COLORS[model.n_items() as usize % COLORS.len()];
+        let smart_calendar = SmartCalendar::new(name, query, color);
+        self.watch(&smart_calendar);
+        model.append(&smart_calendar);
+        self.save();
+        smart_calendar
+    }
+
+    pub fn remove(&self, smart_calendar: &SmartCalendar) {
+        let model = self.model();
+        if let Some(position) = model.find(smart_calendar) {
+            model.remove(position);
+        }
+        self.save();
+    }
+
+    /// Save the smart calendars when they change.
+    fn watch(&self, smart_calendar: &SmartCalendar) {
+        smart_calendar.connect_notify_local(
+            None,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_, _| obj.save()
+            ),
+        );
+    }
+
+    fn save(&self) {
+        let smart_calendars = self
+            .model()
+            .iter::<SmartCalendar>()
+            .filter_map(Result::ok)
+            .map(|smart_calendar| smart_calendar.to_variant())
+            .collect::<Vec<_>>();
+        let value =
+            glib::Variant::array_from_iter_with_type(glib::VariantTy::VARDICT, smart_calendars);
+
+        let settings = self
+            .imp()
+            .settings
+            .get()
+            .expect("settings should be initialized");
+        if let Err(error) = settings.set_value("saved-searches", &value) {
+            warn!("Could not save the saved searches: {error}");
+        }
+    }
+}
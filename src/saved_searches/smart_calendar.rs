@@ -0,0 +1,150 @@
+use std::cell::{Cell, OnceCell, RefCell};
+
+use ccm::{Calendar, Collection, Event};
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+
+use crate::{CalendarManagerApplication, search::Query};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::SmartCalendar)]
+    pub struct SmartCalendar {
+        #[property(get, set, construct_only)]
+        id: RefCell<String>,
+        #[property(get, set)]
+        name: RefCell<String>,
+        /// The search text, in the syntax of the search dialog.
+        #[property(get, set = Self::set_query)]
+        query: RefCell<String>,
+        /// The color, as `#rrggbb`.
+        #[property(get, set)]
+        color: RefCell<String>,
+        /// Whether the events are shown in the views.
+        #[property(get, set, default = true)]
+        visible: Cell<bool>,
+        pub parsed_query: RefCell<Query>,
+        pub filter: OnceCell<gtk::CustomFilter>,
+        pub events: OnceCell<gtk::FilterListModel>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SmartCalendar {
+        const NAME: &'static str = "SmartCalendar";
+        type Type = super::SmartCalendar;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for SmartCalendar {}
+
+    impl SmartCalendar {
+        fn set_query(&self, query: String) {
+            self.parsed_query.replace(Query::parse(&query));
+            self.query.replace(query);
+            if let Some(filter) = self.filter.get() {
+                filter.changed(gtk::FilterChange::Different);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A saved search shown like a calendar, whose events are the live results of its query.
+    pub struct SmartCalendar(ObjectSubclass<imp::SmartCalendar>);
+}
+
+impl SmartCalendar {
+    pub fn new(name: &str, query: &str, color: &str) -> Self {
+        Self::with_id(&glib::uuid_string_random(), name, query, color)
+    }
+
+    fn with_id(id: &str, name: &str, query: &str, color: &str) -> Self {
+        glib::Object::builder()
+            .property("id", id)
+            .property("name", name)
+            .property("query", query)
+            .property("color", color)
+            .property("visible", true)
+            .build()
+    }
+
+    /// The events of all calendars matching the query, updated when they change.
+    pub fn events(&self) -> gio::ListModel {
+        let imp = self.imp();
+        imp.events
+            .get_or_init(|| {
+                let filter = gtk::CustomFilter::new(glib::clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    #[upgrade_or]
+                    false,
+                    move |event| {
+                        event.downcast_ref::<Event>().is_some_and(|event| {
+                            obj.imp().parsed_query.borrow().matches_event(event)
+                        })
+                    }
+                ));
+                imp.filter
+                    .set(filter.clone())
+                    .expect("filter should only be set once");
+                gtk::FilterListModel::new(Some(all_events()), Some(filter))
+            })
+            .clone()
+            .upcast()
+    }
+
+    /// Restore a smart calendar saved with [`SmartCalendar::to_variant`].
+    pub fn from_variant(variant: &glib::Variant) -> Option<Self> {
+        let dict = glib::VariantDict::new(Some(variant));
+        let lookup_string = |key: &str| dict.lookup::<String>(key).ok().flatten();
+
+        let smart_calendar = Self::with_id(
+            &lookup_string("id")?,
+            &lookup_string("name")?,
+            &lookup_string("query")?,
+            &lookup_string("color")?,
+        );
+        smart_calendar.set_visible(
+            dict.lookup::<bool>("visible")
+                .ok()
+                .flatten()
+                .unwrap_or(true),
+        );
+        Some(smart_calendar)
+    }
+
+    pub fn to_variant(&self) -> glib::Variant {
+        let dict = glib::VariantDict::new(None);
+        dict.insert("id", self.id());
+        dict.insert("name", self.name());
+        dict.insert("query", self.query());
+        dict.insert("color", self.color());
+        dict.insert("visible", self.visible());
+        dict.end()
+    }
+}
+
+/// The events of the calendars of all collections, as a single model.
+fn all_events() -> gtk::FlattenListModel {
+    let collections = CalendarManagerApplication::default()
+        .manager()
+        .collections_model();
+    let calendars = gtk::FlattenListModel::new(Some(gtk::MapListModel::new(
+        Some(collections),
+        |collection| {
+            collection
+                .downcast_ref::<Collection>()
+                .expect("model should contain only Collection objects")
+                .calendars()
+                .upcast()
+        },
+    )));
+    gtk::FlattenListModel::new(Some(gtk::MapListModel::new(Some(calendars), |calendar| {
+        calendar
+            .downcast_ref::<Calendar>()
+            .expect("model should contain only Calendar objects")
+            .events()
+            .upcast()
+    })))
+}
@@ -0,0 +1,322 @@
+//! A full-text index of the events, to rank search results by relevance.
+//!
+//! Each calendar is a graph of an in-memory store with the Nepomuk ontology, where events are
+//! `nie:InformationElement`s identified by their URI, with their name as `nie:title`, their
+//! description as `nie:description` and their location as a `nie:keyword`, as the ontology has
+//! no full-text indexed property for places. Only the events that change are indexed again.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use ccm::{Calendar, Collection, Event, Manager};
+use gtk::{
+    gio,
+    glib::{self, translate::FromGlibPtrFull},
+    prelude::*,
+};
+use tracing::warn;
+
+use crate::{CalendarManagerApplication, spawn, utils::all_calendars};
+
+/// Surround the matches in the snippets of the index.
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+const SEARCH_QUERY: &str = r#"SELECT ?event fts:snippet(?event, "\u0001", "\u0002", "…", 12)
+WHERE { ?event fts:match ~text }
+ORDER BY DESC(fts:rank(?event))"#;
+
+/// An event found in the index.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub uri: String,
+    /// Pango markup of the text around the matches, with the matches in bold.
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Index {
+    connection: tsparql::SparqlConnection,
+    /// The URIs of the indexed events of each calendar by calendar URI, in the order of the
+    /// events of the calendar, to know which ones are removed when they change.
+    indexed: Rc<RefCell<HashMap<String, Vec<String>>>>,
+}
+
+impl Index {
+    pub fn new() -> Result<Self, glib::Error> {
+        // The bindings do not export the functions of the library
+        let ontology: gio::File = unsafe {
+            gio::File::from_glib_full(tsparql::ffi::tracker_sparql_get_ontology_nepomuk())
+        };
+        let connection = tsparql::SparqlConnection::new(
+            tsparql::SparqlConnectionFlags::NONE,
+            None::<&gio::File>,
+            Some(&ontology),
+            gio::Cancellable::NONE,
+        )?;
+        Ok(Self {
+            connection,
+            indexed: Rc::default(),
+        })
+    }
+
+    /// Index the events of all calendars of `manager`, and index them again when they change.
+    pub fn watch(&self, manager: &Manager) {
+        let collections = manager.collections_model();
+        for collection in collections.iter::<Collection>().filter_map(Result::ok) {
+            self.watch_collection(manager, &collection);
+        }
+
+        let index = self.clone();
+        let manager = manager.clone();
+        collections.connect_items_changed(move |collections, position, removed, added| {
+            for collection in (position..position + added)
+                .filter_map(|position| collections.item(position).and_downcast::<Collection>())
+            {
+                index.watch_collection(&manager, &collection);
+            }
+            if removed > 0 {
+                index.remove_deleted_calendars(&manager);
+            }
+        });
+    }
+
+    /// The events whose name, description or location match all `terms`, the most relevant
+    /// first.
+    pub async fn search(&self, terms: &[String]) -> Result<Vec<Hit>, glib::Error> {
+        let Some(statement) = self
+            .connection
+            .query_statement(SEARCH_QUERY, gio::Cancellable::NONE)?
+        else {
+            return Ok(Vec::new());
+        };
+        statement.bind_string("text", &match_expression(terms));
+
+        let cursor = statement.execute_future().await?;
+        let mut hits = Vec::new();
+        while cursor.next_future().await? {
+            let Some(uri) = cursor.string(0) else {
+                continue;
+            };
+            hits.push(Hit {
+                uri: uri.into(),
+                snippet: snippet_markup(&cursor.string(1).unwrap_or_default()),
+            });
+        }
+        Ok(hits)
+    }
+
+    fn watch_collection(&self, manager: &Manager, collection: &Collection) {
+        let calendars = collection.calendars();
+        for calendar in calendars.iter::<Calendar>().filter_map(Result::ok) {
+            self.watch_calendar(&calendar);
+        }
+
+        let index = self.clone();
+        let manager = manager.clone();
+        calendars.connect_items_changed(move |calendars, position, removed, added| {
+            for calendar in (position..position + added)
+                .filter_map(|position| calendars.item(position).and_downcast::<Calendar>())
+            {
+                index.watch_calendar(&calendar);
+            }
+            if removed > 0 {
+                index.remove_deleted_calendars(&manager);
+            }
+        });
+    }
+
+    /// Remove the graphs of the indexed calendars that are not in `manager` anymore.
+    fn remove_deleted_calendars(&self, manager: &Manager) {
+        let calendars: HashSet<String> = all_calendars(manager).iter().map(Calendar::uri).collect();
+        let deleted: Vec<String> = {
+            let mut indexed = self.indexed.borrow_mut();
+            let deleted: Vec<String> = indexed
+                .keys()
+                .filter(|uri| !calendars.contains(*uri))
+                .cloned()
+                .collect();
+            for uri in &deleted {
+                indexed.remove(uri);
+            }
+            deleted
+        };
+        if deleted.is_empty() {
+            return;
+        }
+
+        let batch = self.connection.create_batch();
+        for graph in &deleted {
+            batch.add_sparql(&format!("DROP SILENT GRAPH {}", iri(graph)));
+        }
+        spawn!(async move {
+            if let Err(error) = batch.execute_future().await {
+                warn!("Could not remove the events of deleted calendars: {error}");
+            }
+        });
+    }
+
+    fn watch_calendar(&self, calendar: &Calendar) {
+        let events = calendar.events();
+        // Replace the events indexed if the calendar was watched before
+        let indexed = self
+            .indexed
+            .borrow()
+            .get(&calendar.uri())
+            .map_or(0, Vec::len);
+        self.update_events(calendar, 0, indexed as u32, events.n_items());
+
+        let index = self.clone();
+        let weak_calendar = calendar.downgrade();
+        events.connect_items_changed(move |_, position, removed, added| {
+            if let Some(calendar) = weak_calendar.upgrade() {
+                index.update_events(&calendar, position, removed, added);
+            }
+        });
+    }
+
+    /// Index the `added` events of `calendar` from `position`, in place of the `removed` ones.
+    fn update_events(&self, calendar: &Calendar, position: u32, removed: u32, added: u32) {
+        let graph = calendar.uri();
+        let events = calendar.events();
+        let added_events: Vec<Event> = (position..position + added)
+            .filter_map(|position| events.item(position).and_downcast::<Event>())
+            .collect();
+
+        let removed_uris: Vec<String> = {
+            let mut indexed = self.indexed.borrow_mut();
+            let uris = indexed.entry(graph.clone()).or_default();
+            let start = (position as usize).min(uris.len());
+            let end = (start + removed as usize).min(uris.len());
+            uris.splice(start..end, added_events.iter().map(|event| event.uri()))
+                .collect()
+        };
+        if removed_uris.is_empty() && added_events.is_empty() {
+            return;
+        }
+
+        let batch = self.connection.create_batch();
+        // Changed events are removed first, so the properties they do not have anymore go away
+        let added_uris = added_events.iter().map(|event| event.uri());
+        for uri in removed_uris.into_iter().chain(added_uris) {
+            let (graph, uri) = (iri(&graph), iri(&uri));
+            batch.add_sparql(&format!(
+                "DELETE {{ GRAPH {graph} {{ {uri} ?property ?value }} }} \
+                 WHERE {{ GRAPH {graph} {{ {uri} ?property ?value }} }}"
+            ));
+        }
+        if !added_events.is_empty() {
+            let locations = CalendarManagerApplication::default()
+                .event_locations()
+                .calendar(calendar);
+            for event in &added_events {
+                let location = locations.get(&event.uid()).map(String::as_str);
+                batch.add_resource(Some(&graph), &resource(event, location));
+            }
+        }
+
+        spawn!(async move {
+            if let Err(error) = batch.execute_future().await {
+                warn!("Could not index the events of {graph}: {error}");
+            }
+        });
+    }
+}
+
+/// The indexed properties of `event`, which is at `location`.
+fn resource(event: &Event, location: Option<&str>) -> tsparql::Resource {
+    let resource = tsparql::Resource::new(Some(&event.uri()));
+    resource.set_uri("rdf:type", "nie:InformationElement");
+    resource.set_string("nie:title", &event.name());
+    let description = event.description();
+    if !description.is_empty() {
+        resource.set_string("nie:description", &description);
+    }
+    if let Some(location) = location {
+        resource.set_string("nie:keyword", location);
+    }
+    resource
+}
+
+/// `uri` as an IRI in a query, with the characters IRIs cannot contain percent-encoded.
+fn iri(uri: &str) -> String {
+    let mut iri = String::from("<");
+    for character in uri.chars() {
+        if character <= ' ' || "<>\"{}|^`\\".contains(character) {
+            iri.push_str(&format!("%{:02X}", character as u32));
+        } else {
+            iri.push(character);
+        }
+    }
+    iri.push('>');
+    iri
+}
+
+/// The full-text search expression matching all `terms`, each as a phrase.
+///
+/// The index only matches whole words, so the last word, which may still be typed, matches the
+/// words starting with it.
+fn match_expression(terms: &[String]) -> String {
+    let mut phrases: Vec<String> = terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if let Some(last) = phrases.last_mut() {
+        last.push('*');
+    }
+    phrases.join(" ")
+}
+
+fn snippet_markup(snippet: &str) -> String {
+    let mut markup = String::new();
+    // The parts alternate between text around the matches and the matches
+    for (index, part) in snippet.split([MATCH_START, MATCH_END]).enumerate() {
+        let part = glib::markup_escape_text(part);
+        if index % 2 == 1 {
+            markup.push_str(&format!("<b>{part}</b>"));
+        } else {
+            markup.push_str(&part);
+        }
+    }
+    markup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iris_are_escaped() {
+        assert_eq!(
+            iri("https://example.com/cal/work/a b.ics"),
+            "<https://example.com/cal/work/a%20b.ics>"
+        );
+        assert_eq!(
+            iri("urn:x> } ; DROP ALL ; #<"),
+            "<urn:x%3E%20%7D%20;%20DROP%20ALL%20;%20#%3C>"
+        );
+    }
+
+    #[test]
+    fn last_term_is_a_prefix() {
+        let terms = ["quarterly review".to_owned(), "bud".to_owned()];
+        assert_eq!(match_expression(&terms), r#""quarterly review" "bud"*"#);
+        assert_eq!(
+            match_expression(&["say \"hi\"".to_owned()]),
+            r#""say ""hi"""*"#
+        );
+        assert_eq!(match_expression(&[]), "");
+    }
+
+    #[test]
+    fn snippets_are_markup() {
+        assert_eq!(
+            snippet_markup("…the \u{1}budget\u{2} & \u{1}slides\u{2}…"),
+            "…the <b>budget</b> &amp; <b>slides</b>…"
+        );
+        assert_eq!(snippet_markup("<no match>"), "&lt;no match&gt;");
+    }
+}
@@ -0,0 +1,212 @@
+//! Searching events by text and filters, on plain data that can be searched away from the main
+//! thread.
+
+use std::ops::Range;
+
+use ccm::{
+    Event,
+    jiff::{Span, Timestamp, Zoned},
+};
+use gettextrs::gettext;
+use gtk::{gio, prelude::*};
+
+use crate::format;
+
+mod index;
+mod query;
+
+pub use self::{
+    index::{Hit, Index},
+    query::{Query, calendar_completion, calendar_key, parse_time},
+};
+
+/// What is searched in an event, copied from it on the main thread.
+#[derive(Debug, Clone)]
+pub struct EventData {
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub calendar_uri: String,
+    pub calendar_name: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub all_day: bool,
+}
+
+impl EventData {
+    /// Returns `None` for events without a timeframe, which are never found.
+    pub fn new(event: &Event) -> Option<Self> {
+        let timeframe = event.timeframe()?;
+        let calendar = event.calendar();
+        Some(Self {
+            uri: event.uri(),
+            name: event.name(),
+            description: event.description(),
+            calendar_uri: calendar.uri(),
+            calendar_name: calendar.name(),
+            start: timeframe.start().timestamp(),
+            end: timeframe.end().timestamp(),
+            all_day: timeframe.all_day(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum DateRange {
+    #[default]
+    Any,
+    /// Events that ended.
+    Past,
+    /// Events that did not end yet.
+    Upcoming,
+    /// Events happening at least partly between two times.
+    Between(Timestamp, Timestamp),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Kind {
+    #[default]
+    Any,
+    AllDay,
+    Timed,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filters {
+    pub calendar_uri: Option<String>,
+    pub range: DateRange,
+    pub kind: Kind,
+}
+
+impl Filters {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn matches(&self, event: &EventData, now: Timestamp) -> bool {
+        let calendar = self
+            .calendar_uri
+            .as_ref()
+            .is_none_or(|uri| &event.calendar_uri == uri);
+        let range = match self.range {
+            DateRange::Any => true,
+            DateRange::Past => event.end <= now,
+            DateRange::Upcoming => event.end > now,
+            DateRange::Between(from, to) => event.start < to && event.end > from,
+        };
+        let kind = match self.kind {
+            Kind::Any => true,
+            Kind::AllDay => event.all_day,
+            Kind::Timed => !event.all_day,
+        };
+        calendar && range && kind
+    }
+}
+
+/// The groups of search results, in the order they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    /// Events that ended.
+    Past,
+    /// Events that are happening or start later today.
+    Today,
+    ThisWeek,
+    NextWeek,
+    /// Events starting after next week, by year and month.
+    Month(i16, i8),
+}
+
+impl Section {
+    /// The section of an event from `start` to `end`, at the time `now`.
+    pub fn new(start: Timestamp, end: Timestamp, now: &Zoned) -> Self {
+        if end <= now.timestamp() {
+            return Self::Past;
+        }
+
+        let today = now.date();
+        let start = start.to_zoned(now.time_zone().clone()).date();
+        let next_monday =
+            today.saturating_add(Span::new().days(7 - today.weekday().to_monday_zero_offset()));
+        if start <= today {
+            Self::Today
+        } else if start < next_monday {
+            Self::ThisWeek
+        } else if start < next_monday.saturating_add(Span::new().days(7)) {
+            Self::NextWeek
+        } else {
+            Self::Month(start.year(), start.month())
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Self::Past => gettext("Past"),
+            Self::Today => gettext("Today"),
+            Self::ThisWeek => gettext("This Week"),
+            Self::NextWeek => gettext("Next Week"),
+            Self::Month(year, month) => format::month_year(*year, *month),
+        }
+    }
+}
+
+/// The indices of the events matching `query` and `filters`.
+///
+/// Returns `None` if `cancellable` was cancelled in the meantime.
+pub fn search(
+    events: &[EventData],
+    query: &Query,
+    filters: &Filters,
+    cancellable: &gio::Cancellable,
+) -> Option<Vec<usize>> {
+    let now = Timestamp::now();
+    let mut results = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        if index % 256 == 0 && cancellable.is_cancelled() {
+            return None;
+        }
+
+        if filters.matches(event, now) && query.matches(event) {
+            results.push(index);
+        }
+    }
+    Some(results)
+}
+
+/// The byte ranges of `text` where one of the lowercase `terms` appears, ignoring the case, in
+/// order and without overlaps.
+pub fn match_ranges(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (start, _) in text.char_indices() {
+        let Some(end) = terms
+            .iter()
+            .filter_map(|term| prefix_len(&text[start..], term))
+            .max()
+            .map(|len| start + len)
+        else {
+            continue;
+        };
+
+        match ranges.last_mut() {
+            Some(last) if last.end >= start => last.end = last.end.max(end),
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
+}
+
+/// The length of the prefix of `text` equal to the lowercase `term`, ignoring the case.
+fn prefix_len(text: &str, term: &str) -> Option<usize> {
+    let mut term = term.chars().peekable();
+    term.peek()?;
+    for (index, c) in text.char_indices() {
+        for lowercase in c.to_lowercase() {
+            if term.next() != Some(lowercase) {
+                return None;
+            }
+        }
+        if term.peek().is_none() {
+            return Some(index + c.len_utf8());
+        }
+    }
+    None
+}
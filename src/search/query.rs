@@ -0,0 +1,335 @@
+//! The search syntax, e.g. `calendar:Work after:2026-01-01 "quarterly review" -cancelled`.
+
+use ccm::{
+    Event,
+    jiff::{Timestamp, Zoned, civil, tz::TimeZone},
+};
+
+use super::EventData;
+use crate::CalendarManagerApplication;
+
+const CALENDAR: &str = "calendar";
+const AFTER: &str = "after";
+const BEFORE: &str = "before";
+
+/// A parsed search text.
+///
+/// Words and quoted phrases must all be found in the name or the description of an event, unless
+/// they are prefixed with `-`. Invalid or negated dates are searched as words.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    /// The lowercase words and phrases to find.
+    pub terms: Vec<String>,
+    /// The lowercase words and phrases that must not be found.
+    pub excluded_terms: Vec<String>,
+    /// The lowercase names of the calendars to search in, `calendar:`.
+    pub calendars: Vec<String>,
+    /// The lowercase names of the calendars not to search in, `-calendar:`.
+    pub excluded_calendars: Vec<String>,
+    /// The events starting from this time, `after:`.
+    pub after: Option<Timestamp>,
+    /// The events starting before this time, `before:`, which includes the whole day of a date.
+    pub before: Option<Timestamp>,
+}
+
+struct Token {
+    negated: bool,
+    key: Option<&'static str>,
+    value: String,
+}
+
+impl Query {
+    /// Parse `text`, with the dates and times without a time zone in the display time zone.
+    pub fn parse(text: &str) -> Self {
+        let time_zone = CalendarManagerApplication::default().time_zones().display();
+        Self::parse_in(text, &time_zone)
+    }
+
+    fn parse_in(text: &str, time_zone: &TimeZone) -> Self {
+        let mut query = Self::default();
+        for token in tokens(text) {
+            match (token.key, token.negated) {
+                (Some(CALENDAR), false) => query.calendars.push(token.value.to_lowercase()),
+                (Some(CALENDAR), true) => query.excluded_calendars.push(token.value.to_lowercase()),
+                (Some(key), false) if query.set_date(key, &token.value, time_zone) => {}
+                (Some(key), negated) => {
+                    let term = format!("{key}:{}", token.value.to_lowercase());
+                    query.push_term(term, negated);
+                }
+                (None, negated) => query.push_term(token.value.to_lowercase(), negated),
+            }
+        }
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The query without the words and phrases to find, e.g. once they were found in the index.
+    pub fn without_terms(&self) -> Self {
+        Self {
+            terms: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    pub fn matches_event(&self, event: &Event) -> bool {
+        EventData::new(event).is_some_and(|event| self.matches(&event))
+    }
+
+    pub(super) fn matches(&self, event: &EventData) -> bool {
+        let name = event.name.to_lowercase();
+        let description = event.description.to_lowercase();
+        let contains = |term: &String| name.contains(term) || description.contains(term);
+        let calendar_name = event.calendar_name.to_lowercase();
+
+        self.terms.iter().all(contains)
+            && !self.excluded_terms.iter().any(contains)
+            && (self.calendars.is_empty() || self.calendars.contains(&calendar_name))
+            && !self.excluded_calendars.contains(&calendar_name)
+            && self.after.is_none_or(|after| event.start >= after)
+            && self.before.is_none_or(|before| event.start < before)
+    }
+
+    fn push_term(&mut self, term: String, negated: bool) {
+        if negated {
+            self.excluded_terms.push(term);
+        } else {
+            self.terms.push(term);
+        }
+    }
+
+    /// Returns whether `value` is a valid time for `key`.
+    fn set_date(&mut self, key: &str, value: &str, time_zone: &TimeZone) -> bool {
+        let Some((time, date_only)) = parse_time_in(value, time_zone) else {
+            return false;
+        };
+        match key {
+            AFTER => self.after = Some(time.timestamp()),
+            BEFORE if date_only => match time.tomorrow() {
+                Ok(end) => self.before = Some(end.timestamp()),
+                Err(_) => return false,
+            },
+            BEFORE => self.before = Some(time.timestamp()),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// If `text` ends with an unfinished `calendar:` key, the position where the key starts and the
+/// lowercase name typed so far.
+pub fn calendar_completion(text: &str) -> Option<(usize, String)> {
+    let start = text.rfind("calendar:")?;
+    let before = &text[..start];
+    if !before
+        .strip_suffix('-')
+        .unwrap_or(before)
+        .chars()
+        .next_back()
+        .is_none_or(char::is_whitespace)
+    {
+        return None;
+    }
+
+    let value = &text[start + CALENDAR.len() + 1..];
+    let name = match value.strip_prefix('"') {
+        Some(name) if !name.contains('"') => name,
+        None if !value.contains(char::is_whitespace) => value,
+        _ => return None,
+    };
+    Some((start, name.to_lowercase()))
+}
+
+/// `calendar:name`, with the name quoted if needed.
+pub fn calendar_key(name: &str) -> String {
+    if name.contains(char::is_whitespace) {
+        format!("{CALENDAR}:\"{name}\"")
+    } else {
+        format!("{CALENDAR}:{name}")
+    }
+}
+
+fn tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return tokens;
+        }
+
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut key = None;
+        let mut value = String::new();
+        let mut quoted = false;
+        while let Some(c) = chars.next_if(|c| quoted || !c.is_whitespace()) {
+            match c {
+                '"' => quoted = !quoted,
+                ':' if !quoted && key.is_none() => {
+                    key = [CALENDAR, AFTER, BEFORE]
+                        .into_iter()
+                        .find(|key| *key == value);
+                    if key.is_some() {
+                        value.clear();
+                    } else {
+                        value.push(c);
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+
+        if !value.is_empty() {
+            tokens.push(Token {
+                negated,
+                key,
+                value,
+            });
+        }
+    }
+}
+
+/// Parse a date, a date and time in the display time zone, or a date and time with an offset or a
+/// time zone. Returns whether only a date was given.
+pub fn parse_time(value: &str) -> Option<(Zoned, bool)> {
+    let time_zone = CalendarManagerApplication::default().time_zones().display();
+    parse_time_in(value, &time_zone)
+}
+
+/// Like [`parse_time`], with `time_zone` instead of the display time zone.
+fn parse_time_in(value: &str, time_zone: &TimeZone) -> Option<(Zoned, bool)> {
+    if let Ok(zoned) = value.parse::<Zoned>() {
+        return Some((zoned, false));
+    }
+    if let Ok(timestamp) = value.parse::<Timestamp>() {
+        return Some((timestamp.to_zoned(time_zone.clone()), false));
+    }
+    let date_time = value.parse::<civil::DateTime>().ok()?;
+    let date_only = !value.contains(['T', 't', ' ']);
+    let zoned = date_time.to_zoned(time_zone.clone()).ok()?;
+    Some((zoned, date_only))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris() -> TimeZone {
+        TimeZone::get("Europe/Paris").unwrap()
+    }
+
+    fn timestamp(text: &str) -> Timestamp {
+        text.parse().unwrap()
+    }
+
+    fn event(name: &str, calendar_name: &str, start: &str) -> EventData {
+        let start = timestamp(start);
+        EventData {
+            uri: String::new(),
+            name: name.to_owned(),
+            description: "Slides and budget".to_owned(),
+            calendar_uri: String::new(),
+            calendar_name: calendar_name.to_owned(),
+            start,
+            end: start,
+            all_day: false,
+        }
+    }
+
+    #[test]
+    fn terms_and_calendars_are_parsed() {
+        let query = Query::parse_in(
+            r#"calendar:Work -calendar:"Public holidays" "Quarterly Review"  -cancelled Budget"#,
+            &paris(),
+        );
+        assert_eq!(
+            query,
+            Query {
+                terms: vec!["quarterly review".to_owned(), "budget".to_owned()],
+                excluded_terms: vec!["cancelled".to_owned()],
+                calendars: vec!["work".to_owned()],
+                excluded_calendars: vec!["public holidays".to_owned()],
+                after: None,
+                before: None,
+            }
+        );
+
+        assert!(Query::parse_in("  ", &paris()).is_empty());
+        assert!(query.without_terms().terms.is_empty());
+        assert_eq!(query.without_terms().calendars, query.calendars);
+    }
+
+    #[test]
+    fn dates_are_parsed() {
+        let query = Query::parse_in("after:2026-01-01 before:2026-01-31", &paris());
+        assert_eq!(query.after, Some(timestamp("2025-12-31T23:00Z")));
+        // The whole day of a date is included
+        assert_eq!(query.before, Some(timestamp("2026-01-31T23:00Z")));
+
+        let query = Query::parse_in("before:2026-01-31T12:00", &paris());
+        assert_eq!(query.before, Some(timestamp("2026-01-31T11:00Z")));
+        let query = Query::parse_in("after:2026-07-01T08:00[Asia/Tokyo]", &paris());
+        assert_eq!(query.after, Some(timestamp("2026-06-30T23:00Z")));
+        let query = Query::parse_in("after:2026-07-01T08:00Z", &paris());
+        assert_eq!(query.after, Some(timestamp("2026-07-01T08:00Z")));
+    }
+
+    #[test]
+    fn invalid_keys_are_terms() {
+        let query = Query::parse_in(
+            "after:tomorrow -before:2026-01-01 url:https://example.com",
+            &paris(),
+        );
+        assert_eq!(
+            query.terms,
+            ["after:tomorrow", "url:https://example.com"].map(String::from)
+        );
+        assert_eq!(
+            query.excluded_terms,
+            ["before:2026-01-01"].map(String::from)
+        );
+        assert_eq!((query.after, query.before), (None, None));
+    }
+
+    #[test]
+    fn events_are_matched() {
+        let review = event("Quarterly review", "Work", "2026-01-15T09:00Z");
+        let matches = |text: &str| Query::parse_in(text, &paris()).matches(&review);
+
+        assert!(matches(""));
+        assert!(matches("REVIEW budget"));
+        assert!(matches("\"quarterly review\""));
+        assert!(!matches("\"review quarterly\""));
+        assert!(!matches("review -budget"));
+        assert!(matches("calendar:work"));
+        assert!(!matches("calendar:home"));
+        assert!(!matches("-calendar:Work"));
+        assert!(matches("after:2026-01-15 before:2026-01-15"));
+        assert!(!matches("after:2026-01-16"));
+        assert!(!matches("before:2026-01-14"));
+    }
+
+    #[test]
+    fn calendar_keys_are_completed() {
+        assert_eq!(
+            calendar_completion("review calendar:Wo"),
+            Some((7, "wo".to_owned()))
+        );
+        assert_eq!(
+            calendar_completion("-calendar:\"Public ho"),
+            Some((1, "public ho".to_owned()))
+        );
+        assert_eq!(calendar_completion("calendar:"), Some((0, String::new())));
+        assert_eq!(calendar_completion("calendar:Work review"), None);
+        assert_eq!(calendar_completion("calendar:\"Public holidays\""), None);
+        assert_eq!(calendar_completion("mycalendar:Work"), None);
+
+        assert_eq!(calendar_key("Work"), "calendar:Work");
+        assert_eq!(
+            calendar_key("Public holidays"),
+            "calendar:\"Public holidays\""
+        );
+    }
+}
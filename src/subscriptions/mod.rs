@@ -0,0 +1,400 @@
+//! Remote iCalendar feeds mirrored into read-only calendars.
+
+use std::cell::OnceCell;
+
+use ccm::{Calendar, Resource, jiff};
+use gettextrs::gettext;
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use soup::prelude::*;
+use tracing::{debug, warn};
+
+mod subscription;
+
+use crate::{
+    CalendarManagerApplication,
+    ics::{self, ImportPlan},
+    spawn,
+};
+
+pub use self::subscription::Subscription;
+
+/// The refresh intervals offered to the user, in minutes.
+///
+/// They are shown in this order in the refresh interval rows.
+pub const REFRESH_INTERVALS: [u32; 4] = [15, 60, 6 * 60, 24 * 60];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Subscriptions {
+        pub settings: OnceCell<gio::Settings>,
+        pub session: soup::Session,
+        pub model: OnceCell<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Subscriptions {
+        const NAME: &'static str = "Subscriptions";
+        type Type = super::Subscriptions;
+    }
+
+    impl ObjectImpl for Subscriptions {}
+}
+
+glib::wrapper! {
+    /// The feeds the user subscribed to, saved in the settings and refreshed periodically.
+    pub struct Subscriptions(ObjectSubclass<imp::Subscriptions>);
+}
+
+impl Subscriptions {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+        imp.settings
+            .set(settings.clone())
+            .expect("settings should only be set once");
+
+        let model = gio::ListStore::new::<Subscription>();
+        for variant in settings.value("subscriptions").iter() {
+            match Subscription::from_variant(&variant) {
+                Some(subscription) => {
+                    obj.watch(&subscription);
+                    model.append(&subscription);
+                }
+                None => warn!("Ignoring invalid subscription: {variant}"),
+            }
+        }
+        imp.model.set(model).expect("model should only be set once");
+
+        obj
+    }
+
+    pub fn model(&self) -> gio::ListStore {
+        self.imp()
+            .model
+            .get()
+            .expect("model should be initialized")
+            .clone()
+    }
+
+    /// The subscription backing `calendar`, if it is a subscribed calendar.
+    pub fn find(&self, calendar: &Calendar) -> Option<Subscription> {
+        let calendar_uri = calendar.uri();
+        self.model()
+            .iter::<Subscription>()
+            .filter_map(Result::ok)
+            .find(|subscription| subscription.calendar_uri() == calendar_uri)
+    }
+
+    /// Mirror the feed at `url` into `calendar` from now on.
+    pub fn subscribe(&self, calendar: &Calendar, url: &str, refresh_interval: u32) -> Subscription {
+        let subscription = Subscription::new(&calendar.uri(), url, refresh_interval);
+        self.watch(&subscription);
+        self.model().append(&subscription);
+        self.save();
+        self.refresh(&subscription);
+        subscription
+    }
+
+    /// Stop refreshing `calendar`. The calendar and its events are left untouched.
+    pub fn unsubscribe(&self, calendar: &Calendar) {
+        let Some(subscription) = self.find(calendar) else {
+            return;
+        };
+        if let Some(timeout) = subscription.imp().timeout.take() {
+            timeout.remove();
+        }
+
+        let model = self.model();
+        if let Some(position) = model.find(&subscription) {
+            model.remove(position);
+        }
+        self.save();
+    }
+
+    /// Refresh all subscriptions, e.g. when the application starts.
+    pub fn refresh_all(&self) {
+        for subscription in self.model().iter::<Subscription>().filter_map(Result::ok) {
+            self.refresh(&subscription);
+        }
+    }
+
+    /// Fetch the feed of `subscription` now, then schedule the next refresh.
+    pub fn refresh(&self, subscription: &Subscription) {
+        spawn!(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[strong]
+            subscription,
+            async move {
+                obj.sync(&subscription).await;
+                obj.schedule(&subscription);
+            }
+        ));
+    }
+
+    /// Save the subscriptions and refresh them when their settings change.
+    fn watch(&self, subscription: &Subscription) {
+        subscription.connect_refresh_interval_notify(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |subscription| {
+                obj.save();
+                obj.schedule(subscription);
+            }
+        ));
+    }
+
+    fn schedule(&self, subscription: &Subscription) {
+        if let Some(timeout) = subscription.imp().timeout.take() {
+            timeout.remove();
+        }
+
+        let timeout = glib::timeout_add_seconds_local_once(
+            subscription.refresh_interval() * 60,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[weak]
+                subscription,
+                move || {
+                    subscription.imp().timeout.take();
+                    obj.refresh(&subscription);
+                }
+            ),
+        );
+        subscription.imp().timeout.replace(Some(timeout));
+    }
+
+    async fn sync(&self, subscription: &Subscription) {
+        if subscription.syncing() {
+            return;
+        }
+
+        subscription.set_syncing(true);
+        let result = self.fetch(subscription).await;
+        subscription.set_syncing(false);
+
+        match result {
+            Ok(()) => {
+                subscription.set_last_synced(jiff::Timestamp::now().as_second());
+                subscription.set_last_error(None::<String>);
+            }
+            Err(error) => {
+                warn!("Could not refresh {}: {error}", subscription.url());
+                subscription.set_last_error(Some(error));
+            }
+        }
+        self.save();
+    }
+
+    /// Download the feed and mirror it into its calendar.
+    ///
+    /// The validators of the last response are sent along, so unchanged feeds are not
+    /// downloaded again.
+    async fn fetch(&self, subscription: &Subscription) -> Result<(), String> {
+        let manager = CalendarManagerApplication::default().manager();
+        let Some(Resource::Calendar(calendar)) =
+            manager.find_resource(&subscription.calendar_uri())
+        else {
+            return Err(gettext("The calendar does not exist anymore"));
+        };
+
+        let validators = Validators {
+            etag: subscription.etag(),
+            last_modified: subscription.last_modified(),
+        };
+        let download = download(&self.imp().session, &subscription.http_url(), &validators).await?;
+        let Download::Modified { body, validators } = download else {
+            debug!("{} is not modified", subscription.url());
+            return Ok(());
+        };
+
+        let vcalendar =
+            ics::parse(&String::from_utf8_lossy(&body)).map_err(|error| error.to_string())?;
+        let plan = ImportPlan::mirror(&vcalendar, &calendar);
+        debug!("Refreshing {}: {:?}", subscription.url(), plan.summary());
+        plan.apply().await;
+
+        subscription.set_etag(validators.etag);
+        subscription.set_last_modified(validators.last_modified);
+
+        Ok(())
+    }
+
+    fn save(&self) {
+        let subscriptions = self
+            .model()
+            .iter::<Subscription>()
+            .filter_map(Result::ok)
+            .map(|subscription| subscription.to_variant())
+            .collect::<Vec<_>>();
+        let value =
+            glib::Variant::array_from_iter_with_type(glib::VariantTy::VARDICT, subscriptions);
+
+        let settings = self
+            .imp()
+            .settings
+            .get()
+            .expect("settings should be initialized");
+        if let Err(error) = settings.set_value("subscriptions", &value) {
+            warn!("Could not save subscriptions: {error}");
+        }
+    }
+}
+
+/// The cache validators of a feed response.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// The outcome of a conditional feed download.
+#[derive(Debug)]
+enum Download {
+    /// The feed did not change since the response the validators come from.
+    NotModified,
+    /// The feed was downloaded, with the validators of the new response.
+    Modified {
+        body: glib::Bytes,
+        validators: Validators,
+    },
+}
+
+/// Download `url`, unless it did not change since the response `validators` come from.
+async fn download(
+    session: &soup::Session,
+    url: &str,
+    validators: &Validators,
+) -> Result<Download, String> {
+    let message = soup::Message::new("GET", url).map_err(|error| error.to_string())?;
+    if let Some(headers) = message.request_headers() {
+        if let Some(etag) = &validators.etag {
+            headers.append("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            headers.append("If-Modified-Since", last_modified);
+        }
+    }
+
+    let body = session
+        .send_and_read_future(&message, glib::Priority::DEFAULT)
+        .await
+        .map_err(|error| error.to_string())?;
+
+    match message.status() {
+        soup::Status::NotModified => Ok(Download::NotModified),
+        soup::Status::Ok => {
+            let validators = message
+                .response_headers()
+                .map(|headers| Validators {
+                    etag: headers.one("ETag").map(String::from),
+                    last_modified: headers.one("Last-Modified").map(String::from),
+                })
+                .unwrap_or_default();
+            Ok(Download::Modified { body, validators })
+        }
+        _ => Err(format!(
+            "{} {}",
+            message.status_code(),
+            message.reason_phrase().unwrap_or_default()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n";
+    const ETAG: &str = "\"v1\"";
+    const LAST_MODIFIED: &str = "Mon, 19 Oct 2026 08:00:00 GMT";
+
+    /// Serve `FEED` at `/feed` on a local port, honoring `If-None-Match`.
+    ///
+    /// Returns the server, the address of the feed and the number of requests served.
+    fn serve_feed() -> (soup::Server, String, Rc<Cell<u32>>) {
+        let server = glib::Object::new::<soup::Server>();
+        let requests = Rc::new(Cell::new(0));
+        server.add_handler(
+            Some("/feed"),
+            clone!(
+                #[strong]
+                requests,
+                move |_, message, _, _| {
+                    requests.set(requests.get() + 1);
+                    let matches = message
+                        .request_headers()
+                        .and_then(|headers| headers.one("If-None-Match"))
+                        .is_some_and(|etag| etag == ETAG);
+                    if matches {
+                        message.set_status(304, None);
+                        return;
+                    }
+                    message.set_status(200, None);
+                    if let Some(headers) = message.response_headers() {
+                        headers.replace("ETag", ETAG);
+                        headers.replace("Last-Modified", LAST_MODIFIED);
+                    }
+                    message.set_response(
+                        Some("text/calendar"),
+                        soup::MemoryUse::Copy,
+                        FEED.as_bytes(),
+                    );
+                }
+            ),
+        );
+        server
+            .listen_local(0, soup::ServerListenOptions::IPV4_ONLY)
+            .expect("the server should listen on a local port");
+        let base = server
+            .uris()
+            .into_iter()
+            .next()
+            .expect("the server should have an address");
+        (server, format!("{}feed", base.to_str()), requests)
+    }
+
+    #[test]
+    fn download_is_conditional() {
+        let (_server, url, requests) = serve_feed();
+        let session = soup::Session::new();
+
+        glib::MainContext::default().block_on(async {
+            let Ok(Download::Modified { body, validators }) =
+                download(&session, &url, &Validators::default()).await
+            else {
+                panic!("the first download should return the feed");
+            };
+            assert_eq!(&body[..], FEED.as_bytes());
+            assert_eq!(validators.etag.as_deref(), Some(ETAG));
+            assert_eq!(validators.last_modified.as_deref(), Some(LAST_MODIFIED));
+
+            let unchanged = download(&session, &url, &validators).await;
+            assert!(matches!(unchanged, Ok(Download::NotModified)));
+
+            let missing = url.replace("/feed", "/missing");
+            let error = download(&session, &missing, &Validators::default()).await;
+            assert!(error.is_err_and(|error| error.starts_with("404")));
+        });
+
+        assert_eq!(requests.get(), 2);
+    }
+
+    #[test]
+    fn webcal_is_fetched_over_https() {
+        let subscription = Subscription::new("calendar", "webcal://example.com/feed.ics", 60);
+        assert_eq!(subscription.http_url(), "https://example.com/feed.ics");
+        let subscription = Subscription::new("calendar", "http://example.com/feed.ics", 60);
+        assert_eq!(subscription.http_url(), "http://example.com/feed.ics");
+    }
+}
@@ -0,0 +1,118 @@
+use std::cell::{Cell, RefCell};
+
+use gtk::{glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Subscription)]
+    pub struct Subscription {
+        /// The URI of the calendar the feed is mirrored into.
+        #[property(get, set, construct_only)]
+        calendar_uri: RefCell<String>,
+        /// The address of the feed, with an `http`, `https` or `webcal` scheme.
+        #[property(get, set, construct_only)]
+        url: RefCell<String>,
+        /// The number of minutes between two refreshes.
+        #[property(get, set, minimum = 1, default = 60)]
+        refresh_interval: Cell<u32>,
+        /// The `ETag` of the last response, sent back in `If-None-Match`.
+        #[property(get, set, nullable)]
+        etag: RefCell<Option<String>>,
+        /// The `Last-Modified` of the last response, sent back in `If-Modified-Since`.
+        #[property(get, set, nullable)]
+        last_modified: RefCell<Option<String>>,
+        /// The Unix time of the last successful refresh, or 0 if it never succeeded.
+        #[property(get, set)]
+        last_synced: Cell<i64>,
+        /// Why the last refresh failed, if it did.
+        #[property(get, set, nullable)]
+        last_error: RefCell<Option<String>>,
+        #[property(get, set)]
+        syncing: Cell<bool>,
+        pub timeout: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Subscription {
+        const NAME: &'static str = "Subscription";
+        type Type = super::Subscription;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Subscription {
+        fn dispose(&self) {
+            if let Some(timeout) = self.timeout.take() {
+                timeout.remove();
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A remote iCalendar feed mirrored into a read-only calendar.
+    pub struct Subscription(ObjectSubclass<imp::Subscription>);
+}
+
+impl Subscription {
+    pub fn new(calendar_uri: &str, url: &str, refresh_interval: u32) -> Self {
+        glib::Object::builder()
+            .property("calendar-uri", calendar_uri)
+            .property("url", url)
+            .property("refresh-interval", refresh_interval)
+            .build()
+    }
+
+    /// The address to fetch, with `webcal` replaced by `https`.
+    pub fn http_url(&self) -> String {
+        let url = self.url();
+        match url.strip_prefix("webcal://") {
+            Some(rest) => format!("https://{rest}"),
+            None => url,
+        }
+    }
+
+    /// Restore a subscription saved with [`Subscription::to_variant`].
+    pub fn from_variant(variant: &glib::Variant) -> Option<Self> {
+        let dict = glib::VariantDict::new(Some(variant));
+        let lookup_string = |key: &str| dict.lookup::<String>(key).ok().flatten();
+
+        let subscription = Self::new(
+            &lookup_string("calendar-uri")?,
+            &lookup_string("url")?,
+            dict.lookup::<u32>("refresh-interval")
+                .ok()
+                .flatten()
+                .unwrap_or(60),
+        );
+        subscription.set_etag(lookup_string("etag"));
+        subscription.set_last_modified(lookup_string("last-modified"));
+        subscription.set_last_synced(
+            dict.lookup::<i64>("last-synced")
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+        );
+        subscription.set_last_error(lookup_string("last-error"));
+        Some(subscription)
+    }
+
+    pub fn to_variant(&self) -> glib::Variant {
+        let dict = glib::VariantDict::new(None);
+        dict.insert("calendar-uri", self.calendar_uri());
+        dict.insert("url", self.url());
+        dict.insert("refresh-interval", self.refresh_interval());
+        if let Some(etag) = self.etag() {
+            dict.insert("etag", etag);
+        }
+        if let Some(last_modified) = self.last_modified() {
+            dict.insert("last-modified", last_modified);
+        }
+        dict.insert("last-synced", self.last_synced());
+        if let Some(last_error) = self.last_error() {
+            dict.insert("last-error", last_error);
+        }
+        dict.end()
+    }
+}
@@ -0,0 +1,245 @@
+//! The time zone times are shown in, and the time zones events are planned in.
+//!
+//! Events only store instants, so the IANA time zone of each event is kept by event URI in a data
+//! file, loaded once on startup. All-day events are days rather than instants: they go from
+//! midnight to midnight in the system time zone and are the same days in every time zone.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use ccm::{
+    Calendar, Event, Timeframe,
+    jiff::{
+        Timestamp, Zoned, civil,
+        tz::{self, TimeZone},
+    },
+};
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use tracing::warn;
+
+use crate::utils;
+
+/// The data file holding the time zones of the events.
+const EVENT_TIME_ZONES_FILE: &str = "event-time-zones";
+
+/// Time zone names that are not places, except `UTC`.
+const HIDDEN_PREFIXES: [&str; 4] = ["Etc/", "posix/", "right/", "SystemV/"];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::TimeZones)]
+    pub struct TimeZones {
+        /// The IANA name of the time zone times are shown in, or an empty string for the system
+        /// time zone.
+        #[property(get, set)]
+        display_name: RefCell<String>,
+        /// The IANA names of up to two other time zones shown next to the display one.
+        #[property(get, set)]
+        secondary_names: RefCell<Vec<String>>,
+        /// The IANA names of the time zones of the events, by event URI.
+        pub event_zones: RefCell<HashMap<String, String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TimeZones {
+        const NAME: &'static str = "TimeZones";
+        type Type = super::TimeZones;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TimeZones {}
+}
+
+glib::wrapper! {
+    pub struct TimeZones(ObjectSubclass<imp::TimeZones>);
+}
+
+impl TimeZones {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        settings
+            .bind("display-time-zone", &obj, "display-name")
+            .build();
+        settings
+            .bind("secondary-time-zones", &obj, "secondary-names")
+            .build();
+        obj.imp()
+            .event_zones
+            .replace(utils::load_data(EVENT_TIME_ZONES_FILE));
+        obj
+    }
+
+    /// The time zone times are shown in.
+    pub fn display(&self) -> TimeZone {
+        let name = self.display_name();
+        if name.is_empty() {
+            return TimeZone::system();
+        }
+        TimeZone::get(&name).unwrap_or_else(|error| {
+            warn!("Showing times in the system time zone instead of {name}: {error}");
+            TimeZone::system()
+        })
+    }
+
+    /// The other time zones shown next to the display one, with their IANA names.
+    pub fn secondary(&self) -> Vec<(String, TimeZone)> {
+        self.secondary_names()
+            .into_iter()
+            .filter_map(|name| {
+                TimeZone::get(&name)
+                    .inspect_err(|error| warn!("Not showing the time zone {name}: {error}"))
+                    .ok()
+                    .map(|time_zone| (name, time_zone))
+            })
+            .collect()
+    }
+
+    /// The current time in the display time zone.
+    pub fn now(&self) -> Zoned {
+        Zoned::now().with_time_zone(self.display())
+    }
+
+    pub fn to_display(&self, timestamp: Timestamp) -> Zoned {
+        timestamp.to_zoned(self.display())
+    }
+
+    /// The time zone to show `timeframe` in, which is the system time zone for all-day events.
+    pub fn for_timeframe(&self, timeframe: &Timeframe) -> TimeZone {
+        if timeframe.all_day() {
+            TimeZone::system()
+        } else {
+            self.display()
+        }
+    }
+
+    /// The IANA name of the time zone `event` was planned in, if it is known.
+    pub fn event_name(&self, event: &Event) -> Option<String> {
+        self.imp().event_zones.borrow().get(&event.uri()).cloned()
+    }
+
+    /// The time zone `event` was planned in, or the display time zone if it is not known.
+    pub fn event(&self, event: &Event) -> TimeZone {
+        self.event_name(event)
+            .and_then(|name| TimeZone::get(&name).ok())
+            .unwrap_or_else(|| self.display())
+    }
+
+    /// Remember that the event at `uri` is planned in the time zone named `name`, or forget its
+    /// time zone.
+    pub fn set_event(&self, uri: &str, name: Option<&str>) {
+        self.set_events([(uri.to_owned(), name.map(String::from))]);
+    }
+
+    /// Like [`TimeZones::set_event`] for several events at once, by URI.
+    pub fn set_events(&self, events: impl IntoIterator<Item = (String, Option<String>)>) {
+        let mut zones = self.imp().event_zones.borrow_mut();
+        for (uri, name) in events {
+            match name {
+                Some(name) => zones.insert(uri, name),
+                None => zones.remove(&uri),
+            };
+        }
+        utils::save_data(EVENT_TIME_ZONES_FILE, &*zones);
+    }
+
+    /// Forget the time zones of the events of a deleted calendar.
+    pub fn forget(&self, calendar: &Calendar) {
+        let mut zones = self.imp().event_zones.borrow_mut();
+        let count = zones.len();
+        for event in calendar.events().iter::<Event>().filter_map(Result::ok) {
+            zones.remove(&event.uri());
+        }
+        if zones.len() != count {
+            utils::save_data(EVENT_TIME_ZONES_FILE, &*zones);
+        }
+    }
+}
+
+/// The IANA names of the time zones that can be chosen, sorted.
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = tz::db()
+        .available()
+        .map(|name| name.to_string())
+        .filter(|name| {
+            name == "UTC"
+                || (name.contains('/')
+                    && !HIDDEN_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix)))
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// The IANA name of `time_zone`, or `UTC` if it has none.
+pub fn name(time_zone: &TimeZone) -> String {
+    time_zone.iana_name().unwrap_or("UTC").to_owned()
+}
+
+/// When `date` starts and ends in `time_zone`.
+///
+/// Days are not always 24 hours long: the days of daylight saving time transitions are 23 or 25
+/// hours long, and some days do not start at midnight.
+pub fn day_bounds(date: civil::Date, time_zone: &TimeZone) -> Option<(Timestamp, Timestamp)> {
+    let start = date.to_zoned(time_zone.clone()).ok()?.start_of_day().ok()?;
+    let end = start.tomorrow().ok()?.start_of_day().ok()?;
+    Some((start.timestamp(), end.timestamp()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ccm::jiff::{SignedDuration, civil::date};
+
+    use super::*;
+
+    fn day_length(date: civil::Date, time_zone: &str) -> SignedDuration {
+        let time_zone = TimeZone::get(time_zone).unwrap();
+        let (start, end) = day_bounds(date, &time_zone).expect("day should have bounds");
+        end.duration_since(start)
+    }
+
+    #[test]
+    fn days_follow_daylight_saving_time() {
+        assert_eq!(
+            day_length(date(2026, 10, 19), "Europe/Paris"),
+            SignedDuration::from_hours(24)
+        );
+        assert_eq!(
+            day_length(date(2026, 3, 29), "Europe/Paris"),
+            SignedDuration::from_hours(23)
+        );
+        assert_eq!(
+            day_length(date(2026, 10, 25), "Europe/Paris"),
+            SignedDuration::from_hours(25)
+        );
+        assert_eq!(
+            day_length(date(2026, 3, 8), "America/New_York"),
+            SignedDuration::from_hours(23)
+        );
+    }
+
+    #[test]
+    fn days_can_start_after_midnight() {
+        // Clocks went from 00:00 to 01:00 in São Paulo
+        let time_zone = TimeZone::get("America/Sao_Paulo").unwrap();
+        let (start, end) = day_bounds(date(2018, 11, 4), &time_zone).unwrap();
+        assert_eq!(
+            start.to_zoned(time_zone).datetime(),
+            date(2018, 11, 4).at(1, 0, 0, 0)
+        );
+        assert_eq!(end.duration_since(start), SignedDuration::from_hours(23));
+    }
+
+    #[test]
+    fn names_are_places() {
+        let names = names();
+        assert!(names.iter().any(|name| name == "Europe/Paris"));
+        assert!(names.iter().any(|name| name == "UTC"));
+        assert!(!names.iter().any(|name| name.starts_with("Etc/")));
+        assert!(names.is_sorted());
+
+        assert_eq!(name(&TimeZone::get("Asia/Tokyo").unwrap()), "Asia/Tokyo");
+    }
+}
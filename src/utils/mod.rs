@@ -1,7 +1,24 @@
-use gtk::{gdk, graphene, gsk, prelude::*};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use adw::prelude::*;
+use ccm::{Calendar, Collection, Event, Manager, jiff};
+use gettextrs::gettext;
+use gtk::{gdk, gio, glib, graphene, gsk};
+use tracing::{debug, error, warn};
+
+use crate::ics::{self, VCalendar};
 
 mod macros;
 
+/// How long to wait for a new event to be added to its calendar.
+const NEW_EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often to look for a new event in its calendar.
+const NEW_EVENT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 // TODO: Is the size really useful?
 pub fn get_circle_paintable_from_color(color: &gdk::RGBA, size: f32) -> gdk::Paintable {
     let snapshot = gtk::Snapshot::new();
@@ -49,3 +66,144 @@ pub fn get_horizontal_bar_paintable_from_color(
     let graphene_size = graphene::Size::new(width, height);
     snapshot.to_paintable(Some(&graphene_size)).unwrap()
 }
+
+/// The calendars of all collections.
+pub fn all_calendars(manager: &Manager) -> Vec<Calendar> {
+    manager
+        .collections_model()
+        .iter::<Collection>()
+        .filter_map(Result::ok)
+        .flat_map(|collection| {
+            collection
+                .calendars()
+                .iter::<Calendar>()
+                .filter_map(Result::ok)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The events of `calendars` happening at least partly between `from` and `to`, in
+/// chronological order.
+pub fn events_between(
+    calendars: &[Calendar],
+    from: jiff::Timestamp,
+    to: jiff::Timestamp,
+) -> Vec<Event> {
+    let mut events: Vec<Event> = calendars
+        .iter()
+        .flat_map(|calendar| calendar.events().iter::<Event>().filter_map(Result::ok))
+        .filter(|event| {
+            event.timeframe().is_some_and(|timeframe| {
+                timeframe.start().timestamp() < to && timeframe.end().timestamp() > from
+            })
+        })
+        .collect();
+    sort_chronologically(&mut events);
+    events
+}
+
+/// The event `uid` of `calendar`, waiting for it to be added, e.g. after
+/// [`Calendar::import_event`], which adds it to the events of the calendar asynchronously.
+///
+/// Returns `None` if the event is still missing after a few seconds.
+pub async fn wait_for_event(calendar: &Calendar, uid: &str) -> Option<Event> {
+    let deadline = Instant::now() + NEW_EVENT_TIMEOUT;
+    loop {
+        let event = calendar
+            .events()
+            .iter::<Event>()
+            .filter_map(Result::ok)
+            .find(|event| event.uid() == uid);
+        if event.is_some() || Instant::now() >= deadline {
+            return event;
+        }
+        glib::timeout_future(NEW_EVENT_POLL_INTERVAL).await;
+    }
+}
+
+/// The path of the data file `name`, in the user data directory.
+fn data_path(name: &str) -> PathBuf {
+    glib::user_data_dir().join("calendar-manager").join(name)
+}
+
+/// The value saved in the data file `name` with [`save_data`], or the default one if there is
+/// none.
+pub fn load_data<T: FromVariant + StaticVariantType + Default>(name: &str) -> T {
+    let Ok(data) = std::fs::read(data_path(name)) else {
+        return T::default();
+    };
+    glib::Variant::from_data::<T, _>(data)
+        .get()
+        .unwrap_or_else(|| {
+            warn!("Ignoring the invalid data file {name}");
+            T::default()
+        })
+}
+
+/// Save `value` in the data file `name`, replacing it atomically.
+pub fn save_data(name: &str, value: &impl ToVariant) {
+    let path = data_path(name);
+    let parent = path.parent().expect("data path should have a parent");
+    if let Err(error) = std::fs::create_dir_all(parent) {
+        warn!("Could not create {}: {error}", parent.display());
+        return;
+    }
+    if let Err(error) = glib::file_set_contents(&path, &value.to_variant().data_as_bytes()) {
+        warn!("Could not save the data file {name}: {error}");
+    }
+}
+
+pub fn sort_chronologically(events: &mut [Event]) {
+    events.sort_by_key(|event| {
+        event
+            .timeframe()
+            .map(|timeframe| timeframe.start().timestamp())
+    });
+}
+
+/// Ask the user where to save `vcalendar`, then write it there as an iCalendar file.
+///
+/// Errors are reported to the user with an alert.
+pub async fn save_ics_file(vcalendar: &VCalendar, parent: &impl IsA<gtk::Widget>) {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some(&gettext("iCalendar Files")));
+    filter.add_mime_type("text/calendar");
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+
+    let name = vcalendar.name.as_deref().unwrap_or("calendar");
+    let file_dialog = gtk::FileDialog::builder()
+        .title(gettext("Export Events"))
+        .initial_name(format!("{name}.ics"))
+        .filters(&filters)
+        .build();
+
+    let window = parent.root().and_downcast::<gtk::Window>();
+    let file = match file_dialog.save_future(window.as_ref()).await {
+        Ok(file) => file,
+        Err(error) => {
+            debug!("No file to export to: {error}");
+            return;
+        }
+    };
+
+    let contents = ics::write(vcalendar);
+    if let Err((_, error)) = file
+        .replace_contents_future(
+            contents.into_bytes(),
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+        )
+        .await
+    {
+        error!("Could not export to {}: {error}", file.uri());
+        let alert = adw::AlertDialog::new(
+            Some(&gettext("Could Not Export Events")),
+            Some(&error.to_string()),
+        );
+        alert.add_response("close", &gettext("_Close"));
+        alert.present(Some(parent));
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+
+use gtk::{gio, glib, prelude::*};
+
+use crate::mirror::{Changes, Precondition, RemoteCalendar, RemoteResource, Store};
+
+const ATTRIBUTES: &str = "standard::name,standard::type,standard::is-hidden,etag::value";
+
+/// A folder in the vdir format used by vdirsyncer and khal: each calendar is a subdirectory
+/// holding one `.ics` file per event, with optional `displayname` and `color` files.
+///
+/// Hrefs are the paths of the calendars and of the files, and ETags are the ones of GIO, which
+/// change with the modification time.
+pub struct Directory {
+    path: PathBuf,
+}
+
+impl Directory {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_owned(),
+        }
+    }
+}
+
+impl Store for Directory {
+    type Error = glib::Error;
+
+    async fn calendars(&self) -> Result<Vec<RemoteCalendar>, glib::Error> {
+        let mut calendars = Vec::new();
+        for info in children(&gio::File::for_path(&self.path)).await? {
+            if info.file_type() != gio::FileType::Directory || info.is_hidden() {
+                continue;
+            }
+            let path = self.path.join(info.name());
+            let name = read_metadata(&path, "displayname")
+                .await
+                .unwrap_or_else(|| info.name().to_string_lossy().into_owned());
+            calendars.push(RemoteCalendar {
+                color: read_metadata(&path, "color").await,
+                href: path.to_string_lossy().into_owned(),
+                name,
+            });
+        }
+        Ok(calendars)
+    }
+
+    async fn create_calendar(
+        &self,
+        name: &str,
+        color: &str,
+    ) -> Result<Option<RemoteCalendar>, glib::Error> {
+        // Add a suffix until the name is free, without checking first to not race with others
+        let mut suffix = 1;
+        let (dir, path) = loop {
+            let path = match suffix {
+                1 => self.path.join(file_name(name)),
+                _ => self.path.join(format!("{}-{suffix}", file_name(name))),
+            };
+            let dir = gio::File::for_path(&path);
+            match dir.make_directory_future(glib::Priority::DEFAULT).await {
+                Ok(()) => break (dir, path),
+                Err(error) if error.matches(gio::IOErrorEnum::Exists) => suffix += 1,
+                Err(error) => return Err(error),
+            }
+        };
+        for (metadata, value) in [("displayname", name), ("color", color)] {
+            dir.child(metadata)
+                .replace_contents_future(
+                    value.as_bytes().to_vec(),
+                    None,
+                    false,
+                    gio::FileCreateFlags::NONE,
+                )
+                .await
+                .map_err(|(_, error)| error)?;
+        }
+
+        Ok(Some(RemoteCalendar {
+            href: path.to_string_lossy().into_owned(),
+            name: name.to_owned(),
+            color: Some(color.to_owned()),
+        }))
+    }
+
+    /// There are no sync tokens, all the files are listed every time.
+    async fn changes(&self, href: &str, _sync_token: &str) -> Result<Changes, glib::Error> {
+        let dir = Path::new(href);
+        let changed = children(&gio::File::for_path(dir))
+            .await?
+            .into_iter()
+            .filter(|info| {
+                info.file_type() == gio::FileType::Regular
+                    && !info.is_hidden()
+                    && info
+                        .name()
+                        .extension()
+                        .is_some_and(|extension| extension == "ics")
+            })
+            .map(|info| {
+                let etag = info.attribute_string("etag::value").unwrap_or_default();
+                (
+                    dir.join(info.name()).to_string_lossy().into_owned(),
+                    etag.into(),
+                )
+            })
+            .collect();
+
+        Ok(Changes {
+            changed,
+            complete: true,
+            ..Changes::default()
+        })
+    }
+
+    async fn fetch(
+        &self,
+        _href: &str,
+        hrefs: &[String],
+    ) -> Result<Vec<RemoteResource>, glib::Error> {
+        let mut resources = Vec::new();
+        for href in hrefs {
+            let (contents, etag) = match gio::File::for_path(href).load_contents_future().await {
+                Ok(result) => result,
+                // Removed since it was listed, the next sync handles it
+                Err(error) if error.matches(gio::IOErrorEnum::NotFound) => continue,
+                Err(error) => return Err(error),
+            };
+            resources.push(RemoteResource {
+                href: href.clone(),
+                etag: etag.map(Into::into),
+                data: String::from_utf8_lossy(&contents).into_owned(),
+            });
+        }
+        Ok(resources)
+    }
+
+    async fn put(
+        &self,
+        href: &str,
+        precondition: Precondition<'_>,
+        data: String,
+    ) -> Result<Option<String>, glib::Error> {
+        let file = gio::File::for_path(href);
+        let etag = match precondition {
+            Precondition::Matches(etag) => Some(etag),
+            Precondition::Any => None,
+            Precondition::Absent => {
+                let stream = file
+                    .create_future(gio::FileCreateFlags::NONE, glib::Priority::DEFAULT)
+                    .await?;
+                stream
+                    .write_all_future(data.into_bytes(), glib::Priority::DEFAULT)
+                    .await
+                    .map_err(|(_, error)| error)?;
+                stream.close_future(glib::Priority::DEFAULT).await?;
+                return Ok(stream.etag().map(Into::into));
+            }
+        };
+
+        // GIO checks the ETag and replaces the file atomically
+        let (_, new_etag) = file
+            .replace_contents_future(data.into_bytes(), etag, false, gio::FileCreateFlags::NONE)
+            .await
+            .map_err(|(_, error)| error)?;
+        Ok(Some(new_etag.into()))
+    }
+
+    async fn delete(&self, href: &str, etag: Option<&str>) -> Result<(), glib::Error> {
+        let file = gio::File::for_path(href);
+        let result = async {
+            let info = file
+                .query_info_future(
+                    "etag::value",
+                    gio::FileQueryInfoFlags::NONE,
+                    glib::Priority::DEFAULT,
+                )
+                .await?;
+            if etag
+                .is_some_and(|etag| info.attribute_string("etag::value").as_deref() != Some(etag))
+            {
+                return Err(glib::Error::new(
+                    gio::IOErrorEnum::WrongEtag,
+                    "The file was modified",
+                ));
+            }
+            file.delete_future(glib::Priority::DEFAULT).await
+        };
+
+        match result.await {
+            Err(error) if error.matches(gio::IOErrorEnum::NotFound) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn resource_href(&self, href: &str, uid: &str) -> String {
+        Path::new(href)
+            .join(format!("{}.ics", file_name(uid)))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn is_conflict(error: &glib::Error) -> bool {
+        error.matches(gio::IOErrorEnum::WrongEtag) || error.matches(gio::IOErrorEnum::Exists)
+    }
+}
+
+async fn children(dir: &gio::File) -> Result<Vec<gio::FileInfo>, glib::Error> {
+    let enumerator = dir
+        .enumerate_children_future(
+            ATTRIBUTES,
+            gio::FileQueryInfoFlags::NONE,
+            glib::Priority::DEFAULT,
+        )
+        .await?;
+
+    let mut children = Vec::new();
+    loop {
+        let infos = enumerator
+            .next_files_future(64, glib::Priority::DEFAULT)
+            .await?;
+        if infos.is_empty() {
+            return Ok(children);
+        }
+        children.extend(infos);
+    }
+}
+
+/// The content of the metadata file `name` of the calendar at `path`, if it is set.
+async fn read_metadata(path: &Path, name: &str) -> Option<String> {
+    let (contents, _) = gio::File::for_path(path.join(name))
+        .load_contents_future()
+        .await
+        .ok()?;
+    let value = String::from_utf8_lossy(&contents).trim().to_owned();
+    (!value.is_empty()).then_some(value)
+}
+
+/// `name` with the characters that are not safe in file names replaced.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.@".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,183 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use ccm::{Calendar, Collection};
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use tracing::warn;
+
+use crate::mirror::{Source, SourceImpl};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Folder)]
+    pub struct Folder {
+        #[property(get, set, construct_only)]
+        path: RefCell<PathBuf>,
+        pub monitors: RefCell<Vec<gio::FileMonitor>>,
+        /// The handlers watching the models of the local collection, with their model.
+        pub local_handlers: RefCell<Vec<(glib::Object, glib::SignalHandlerId)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Folder {
+        const NAME: &'static str = "VdirFolder";
+        type Type = super::Folder;
+        type ParentType = Source;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Folder {
+        fn dispose(&self) {
+            self.obj().unwatch();
+        }
+    }
+    impl SourceImpl for Folder {}
+}
+
+glib::wrapper! {
+    /// A folder of vdir calendars synced with a local collection.
+    pub struct Folder(ObjectSubclass<imp::Folder>)
+        @extends Source;
+}
+
+impl Folder {
+    pub fn new(id: &str, path: &Path, collection_uri: &str) -> Self {
+        glib::Object::builder()
+            .property("id", id)
+            .property("path", path)
+            .property("collection-uri", collection_uri)
+            .build()
+    }
+
+    /// Restore a folder saved with [`Folder::to_variant`].
+    pub fn from_variant(variant: &glib::Variant) -> Option<Self> {
+        let dict = glib::VariantDict::new(Some(variant));
+        let lookup_string = |key: &str| dict.lookup::<String>(key).ok().flatten();
+
+        let folder = Self::new(
+            &lookup_string("id")?,
+            Path::new(&lookup_string("path")?),
+            &lookup_string("collection-uri")?,
+        );
+        folder.upcast_ref::<Source>().restore_status(&dict);
+        Some(folder)
+    }
+
+    pub fn to_variant(&self) -> glib::Variant {
+        let dict = glib::VariantDict::new(None);
+        self.upcast_ref::<Source>().save_status(&dict);
+        dict.insert("path", self.path().to_string_lossy().into_owned());
+        dict.end()
+    }
+
+    /// Stop watching the folder and its collection for changes.
+    pub fn unwatch(&self) {
+        for monitor in self.imp().monitors.take() {
+            monitor.cancel();
+        }
+        self.unwatch_collection();
+    }
+
+    /// Call `on_change` when calendars or events are added, modified or removed in `collection`,
+    /// replacing the previous handlers.
+    ///
+    /// Changes made while the folder is syncing come from the sync itself and are ignored.
+    pub fn watch_collection(&self, collection: &Collection, on_change: impl Fn(&Self) + 'static) {
+        self.unwatch_collection();
+
+        let weak = self.downgrade();
+        let on_change = Rc::new(move || {
+            let Some(obj) = weak.upgrade() else {
+                return;
+            };
+            if !obj.upcast_ref::<Source>().syncing() {
+                on_change(&obj);
+            }
+        });
+
+        let calendars = collection.calendars();
+        let mut handlers = Vec::new();
+        for calendar in calendars.iter::<Calendar>().filter_map(Result::ok) {
+            let events = calendar.events();
+            let on_change = on_change.clone();
+            let handler = events.connect_items_changed(move |_, _, _, _| on_change());
+            handlers.push((events.upcast(), handler));
+        }
+        // New calendars are watched after the sync they trigger
+        let handler = calendars.connect_items_changed(move |_, _, _, _| on_change());
+        handlers.push((calendars.upcast(), handler));
+
+        self.imp().local_handlers.replace(handlers);
+    }
+
+    fn unwatch_collection(&self) {
+        for (model, handler) in self.imp().local_handlers.take() {
+            model.disconnect(handler);
+        }
+    }
+
+    /// Call `on_change` when files are changed in the folder or in one of its calendars,
+    /// replacing the previous monitors.
+    pub async fn watch(&self, on_change: impl Fn(&Self) + Clone + 'static) {
+        let root = gio::File::for_path(self.path());
+        let mut dirs = vec![root.clone()];
+        match root
+            .enumerate_children_future(
+                "standard::name,standard::type,standard::is-hidden",
+                gio::FileQueryInfoFlags::NONE,
+                glib::Priority::DEFAULT,
+            )
+            .await
+        {
+            Ok(enumerator) => {
+                while let Ok(infos) = enumerator
+                    .next_files_future(64, glib::Priority::DEFAULT)
+                    .await
+                {
+                    if infos.is_empty() {
+                        break;
+                    }
+                    dirs.extend(
+                        infos
+                            .into_iter()
+                            .filter(|info| {
+                                info.file_type() == gio::FileType::Directory && !info.is_hidden()
+                            })
+                            .map(|info| root.child(info.name())),
+                    );
+                }
+            }
+            Err(error) => warn!("Could not list {}: {error}", self.path().display()),
+        }
+
+        let monitors = dirs
+            .into_iter()
+            .filter_map(|dir| {
+                let monitor = dir
+                    .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+                    .inspect_err(|error| warn!("Could not watch {}: {error}", dir.uri()))
+                    .ok()?;
+                let on_change = on_change.clone();
+                monitor.connect_changed(glib::clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move |_, _, _, event| {
+                        if event != gio::FileMonitorEvent::AttributeChanged {
+                            on_change(&obj);
+                        }
+                    }
+                ));
+                Some(monitor)
+            })
+            .collect();
+
+        for monitor in self.imp().monitors.replace(monitors) {
+            monitor.cancel();
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Folders of calendars in the vdir format, synced both ways with local collections.
+
+use std::{cell::OnceCell, path::Path};
+
+use ccm::Collection;
+use gettextrs::gettext;
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use tracing::warn;
+
+mod directory;
+mod folder;
+
+use crate::{
+    CalendarManagerApplication,
+    mirror::{self, Source, SyncState},
+    spawn,
+};
+
+use self::directory::Directory;
+pub use self::folder::Folder;
+
+/// The number of minutes between two syncs of a folder, in case some changes were missed.
+const SYNC_INTERVAL: u32 = 5;
+
+/// The number of seconds to wait after a change in a folder before syncing it, so that a batch
+/// of changes is synced at once.
+const CHANGE_DELAY: u32 = 2;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Folders {
+        pub settings: OnceCell<gio::Settings>,
+        pub model: OnceCell<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Folders {
+        const NAME: &'static str = "VdirFolders";
+        type Type = super::Folders;
+    }
+
+    impl ObjectImpl for Folders {}
+}
+
+glib::wrapper! {
+    /// The vdir folders of the user, saved in the settings and synced when they change.
+    pub struct Folders(ObjectSubclass<imp::Folders>);
+}
+
+impl Folders {
+    pub fn new(settings: &gio::Settings) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+        imp.settings
+            .set(settings.clone())
+            .expect("settings should only be set once");
+
+        let model = gio::ListStore::new::<Folder>();
+        for variant in settings.value("vdir-folders").iter() {
+            match Folder::from_variant(&variant) {
+                Some(folder) => model.append(&folder),
+                None => warn!("Ignoring invalid vdir folder: {variant}"),
+            }
+        }
+        imp.model.set(model).expect("model should only be set once");
+
+        obj
+    }
+
+    pub fn model(&self) -> gio::ListStore {
+        self.imp()
+            .model
+            .get()
+            .expect("model should be initialized")
+            .clone()
+    }
+
+    /// The folder synced with `collection`, if any.
+    pub fn find(&self, collection: &Collection) -> Option<Folder> {
+        let collection_uri = collection.uri();
+        self.model()
+            .iter::<Folder>()
+            .filter_map(Result::ok)
+            .find(|folder| folder.upcast_ref::<Source>().collection_uri() == collection_uri)
+    }
+
+    /// Create a collection for the folder at `path` and sync it.
+    pub fn add(&self, path: &Path) -> Folder {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned());
+
+        let manager = CalendarManagerApplication::default().manager();
+        let collection = manager.create_collection(&name);
+        let folder = Folder::new(&glib::uuid_string_random(), path, &collection.uri());
+
+        self.model().append(&folder);
+        self.save();
+        self.sync(&folder);
+        folder
+    }
+
+    /// Stop syncing `folder` and forget it, leaving its files untouched.
+    pub fn remove(&self, folder: &Folder) {
+        let source = folder.upcast_ref::<Source>();
+        source.unschedule();
+        folder.unwatch();
+
+        let model = self.model();
+        if let Some(position) = model.find(folder) {
+            model.remove(position);
+        }
+        self.save();
+        SyncState::delete(&source.id());
+    }
+
+    /// Sync all folders, e.g. when the application starts.
+    pub fn sync_all(&self) {
+        for folder in self.model().iter::<Folder>().filter_map(Result::ok) {
+            self.sync(&folder);
+        }
+    }
+
+    /// Sync `folder` now, then watch it for changes and schedule the next sync.
+    pub fn sync(&self, folder: &Folder) {
+        spawn!(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[strong]
+            folder,
+            async move {
+                let source = folder.upcast_ref::<Source>();
+                source.record_sync(sync_folder(&folder)).await;
+                // The folder may have been removed during the sync
+                if obj.model().find(&folder).is_none() {
+                    return;
+                }
+                obj.save();
+
+                folder
+                    .watch(clone!(
+                        #[weak]
+                        obj,
+                        move |folder| obj.schedule_sync(folder, CHANGE_DELAY)
+                    ))
+                    .await;
+                // Local changes are written to the folder soon after they are made
+                let manager = CalendarManagerApplication::default().manager();
+                if let Some(ccm::Resource::Collection(collection)) =
+                    manager.find_resource(&source.collection_uri())
+                {
+                    folder.watch_collection(
+                        &collection,
+                        clone!(
+                            #[weak]
+                            obj,
+                            move |folder| obj.schedule_sync(folder, CHANGE_DELAY)
+                        ),
+                    );
+                }
+                obj.schedule_sync(&folder, SYNC_INTERVAL * 60);
+            }
+        ));
+    }
+
+    fn schedule_sync(&self, folder: &Folder, seconds: u32) {
+        folder.upcast_ref::<Source>().schedule(
+            seconds,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[weak]
+                folder,
+                move |_| obj.sync(&folder)
+            ),
+        );
+    }
+
+    fn save(&self) {
+        let folders = self
+            .model()
+            .iter::<Folder>()
+            .filter_map(Result::ok)
+            .map(|folder| folder.to_variant())
+            .collect::<Vec<_>>();
+        let value = glib::Variant::array_from_iter_with_type(glib::VariantTy::VARDICT, folders);
+
+        let settings = self
+            .imp()
+            .settings
+            .get()
+            .expect("settings should be initialized");
+        if let Err(error) = settings.set_value("vdir-folders", &value) {
+            warn!("Could not save vdir folders: {error}");
+        }
+    }
+}
+
+async fn sync_folder(folder: &Folder) -> Result<usize, String> {
+    let source = folder.upcast_ref::<Source>();
+    let manager = CalendarManagerApplication::default().manager();
+    let Some(ccm::Resource::Collection(collection)) =
+        manager.find_resource(&source.collection_uri())
+    else {
+        return Err(gettext("The collection does not exist anymore"));
+    };
+
+    let mut state = SyncState::load(&source.id());
+    let result = mirror::sync(&Directory::new(&folder.path()), &collection, &mut state).await;
+    // Whatever was done before an error is kept
+    state.save(&source.id());
+    result.map_err(|error| error.to_string())
+}
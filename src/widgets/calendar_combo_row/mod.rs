@@ -1,6 +1,7 @@
 use std::cell::OnceCell;
 
 use adw::{prelude::*, subclass::prelude::*};
+use ccm::Calendar;
 use gtk::{
     FlattenListModel,
     glib::{self, clone},
@@ -18,6 +19,9 @@ use self::{
     calendar_combo_row_list_item::CalendarComboRowListItem,
 };
 
+/// The number of calendars from which they can be searched by name.
+const SEARCH_THRESHOLD: u32 = 8;
+
 mod imp {
     use super::*;
 
@@ -56,8 +60,11 @@ mod imp {
                 FlattenListModel::new(Some(model))
             });
 
-            self.obj()
-                .set_model(Some(self.flattened_collections_model()));
+            let obj = self.obj();
+            let model = self.flattened_collections_model();
+            obj.set_model(Some(model));
+            obj.set_enable_search(model.n_items() >= SEARCH_THRESHOLD);
+            obj.select_preferred_calendar();
         }
     }
 
@@ -69,7 +76,7 @@ mod imp {
 
     #[gtk::template_callbacks]
     impl CalendarComboRow {
-        fn flattened_collections_model(&self) -> &FlattenListModel {
+        pub(super) fn flattened_collections_model(&self) -> &FlattenListModel {
             self.flattened_collections_model
                 .get()
                 .expect("flattened_collections_model should be initialized")
@@ -116,6 +123,14 @@ mod imp {
                 .expect("item should be a Calendar");
             let selected = self.obj().selected_item() == item.item();
             let calendar_combo_row_list_item = CalendarComboRowListItem::new(&calendar, selected);
+
+            // Read-only calendars are shown, but cannot be chosen
+            let writable = !CalendarManagerApplication::default()
+                .calendar_properties()
+                .is_read_only(&calendar);
+            item.set_selectable(writable);
+            item.set_activatable(writable);
+            calendar_combo_row_list_item.set_sensitive(writable);
             item.set_child(Some(&calendar_combo_row_list_item));
 
             // TODO: Is it necessary to disconnect the signal?
@@ -139,6 +154,43 @@ impl CalendarComboRow {
     pub fn new() -> Self {
         glib::Object::builder().build()
     }
+
+    /// Select the calendar events were last created in or imported to, or else the default
+    /// calendar, or else the first writable calendar.
+    fn select_preferred_calendar(&self) {
+        let calendar_properties = CalendarManagerApplication::default().calendar_properties();
+        let calendars: Vec<Calendar> = self
+            .imp()
+            .flattened_collections_model()
+            .iter::<Calendar>()
+            .filter_map(Result::ok)
+            .filter(|calendar| !calendar_properties.is_read_only(calendar))
+            .collect();
+        let calendar = calendars
+            .iter()
+            .find(|calendar| calendar_properties.is_last_used(calendar))
+            .or_else(|| {
+                calendars
+                    .iter()
+                    .find(|calendar| calendar_properties.is_default(calendar))
+            })
+            .or(calendars.first());
+        if let Some(calendar) = calendar {
+            self.select_calendar(calendar);
+        }
+    }
+
+    /// Select `calendar`, if it is one of the choices.
+    pub fn select_calendar(&self, calendar: &Calendar) {
+        let position = self
+            .imp()
+            .flattened_collections_model()
+            .iter::<Calendar>()
+            .position(|item| item.is_ok_and(|item| &item == calendar));
+        if let Some(position) = position {
+            self.set_selected(position as u32);
+        }
+    }
 }
 
 impl Default for CalendarComboRow {
@@ -0,0 +1,105 @@
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::glib::{self, clone};
+
+use crate::{CalendarManagerApplication, spawn};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/caldav_account_dialog.ui")]
+    pub struct CalDavAccountDialog {
+        #[template_child]
+        error_banner: TemplateChild<adw::Banner>,
+        #[template_child]
+        server_url: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        username: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        password: TemplateChild<adw::PasswordEntryRow>,
+        #[template_child]
+        add_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CalDavAccountDialog {
+        const NAME: &'static str = "CalDavAccountDialog";
+        type Type = super::CalDavAccountDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for CalDavAccountDialog {}
+    impl WidgetImpl for CalDavAccountDialog {}
+    impl AdwDialogImpl for CalDavAccountDialog {}
+
+    #[gtk::template_callbacks]
+    impl CalDavAccountDialog {
+        #[template_callback]
+        fn update_add_sensitivity(&self) {
+            let complete = [
+                self.server_url.text(),
+                self.username.text(),
+                self.password.text(),
+            ]
+            .iter()
+            .all(|text| !text.trim().is_empty());
+            self.add_button.set_sensitive(complete);
+        }
+
+        /// Connect to the server, and add the account if it worked.
+        #[template_callback]
+        fn add_account(&self) {
+            self.add_button.set_sensitive(false);
+            self.error_banner.set_revealed(false);
+
+            let server_url = self.server_url.text().trim().to_string();
+            let username = self.username.text().trim().to_string();
+            let password = self.password.text().to_string();
+
+            spawn!(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    let accounts = CalendarManagerApplication::default().caldav_accounts();
+                    match accounts.add(&server_url, &username, &password).await {
+                        Ok(_) => {
+                            imp.obj().close();
+                        }
+                        Err(error) => {
+                            imp.error_banner.set_title(&error);
+                            imp.error_banner.set_revealed(true);
+                            imp.update_add_sensitivity();
+                        }
+                    }
+                }
+            ));
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A dialog to add a CalDAV account.
+    pub struct CalDavAccountDialog(ObjectSubclass<imp::CalDavAccountDialog>)
+        @extends gtk::Widget, adw::Dialog;
+}
+
+impl CalDavAccountDialog {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+}
+
+impl Default for CalDavAccountDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,24 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::Calendar;
+use ccm::{
+    Calendar, Event,
+    jiff::{SignedDuration, civil},
+};
+use gettextrs::{gettext, ngettext};
 use gtk::glib::{self, clone};
 
+use crate::{
+    CalendarManagerApplication,
+    calendar_properties::{DURATIONS, Properties, REMINDERS},
+    format,
+    ics::VCalendar,
+    spawn,
+    subscriptions::{REFRESH_INTERVALS, Subscription},
+    utils::save_ics_file,
+    widgets::CreateEventDialog,
+};
+
 mod imp {
     use super::*;
 
@@ -13,10 +28,33 @@ mod imp {
     pub struct CalendarDetailsPage {
         #[property(get, set, construct_only)]
         pub calendar: RefCell<Option<Calendar>>,
+        /// The subscription the events of the calendar come from, if any.
+        #[property(get, nullable)]
+        pub subscription: RefCell<Option<Subscription>>,
         #[template_child]
         pub name_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
         pub calendar_color_button: TemplateChild<gtk::ColorDialogButton>,
+        #[template_child]
+        pub description_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub events_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub date_range_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub default_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub default_duration_choice: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub show_as_choice: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub reminders_row: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub locked_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub refresh_interval_choice: TemplateChild<adw::ComboRow>,
+        /// Whether the rows show the properties of the calendar, so their changes are saved.
+        pub loaded: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -43,6 +81,26 @@ mod imp {
             let calendar = self.obj().calendar().unwrap();
 
             self.name_entry.set_text(&calendar.name());
+            self.load_properties(&calendar);
+            self.update_summary();
+            calendar.events().connect_items_changed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _, _, _| imp.update_summary()
+            ));
+
+            let subscription = CalendarManagerApplication::default()
+                .subscriptions()
+                .find(&calendar);
+            if let Some(subscription) = &subscription {
+                let position = REFRESH_INTERVALS
+                    .iter()
+                    .position(|interval| *interval >= subscription.refresh_interval())
+                    .unwrap_or(REFRESH_INTERVALS.len() - 1);
+                self.refresh_interval_choice.set_selected(position as u32);
+            }
+            self.subscription.replace(subscription);
+            self.obj().notify_subscription();
 
             calendar.connect_name_notify(clone!(
                 #[weak(rename_to = imp)]
@@ -71,6 +129,135 @@ mod imp {
     impl WidgetImpl for CalendarDetailsPage {}
     impl NavigationPageImpl for CalendarDetailsPage {}
 
+    impl CalendarDetailsPage {
+        /// Show the properties of `calendar` in the rows.
+        fn load_properties(&self, calendar: &Calendar) {
+            let calendar_properties = CalendarManagerApplication::default().calendar_properties();
+            let properties = calendar_properties.get(calendar);
+
+            self.description_entry.set_text(&properties.description);
+            self.default_row
+                .set_active(calendar_properties.is_default(calendar));
+            self.locked_row.set_active(properties.locked);
+            self.show_as_choice
+                .set_selected(if properties.busy { 0 } else { 1 });
+
+            let durations: Vec<String> = DURATIONS
+                .iter()
+                .map(|minutes| format::duration(*minutes))
+                .collect();
+            self.default_duration_choice
+                .set_model(Some(&gtk::StringList::new(
+                    &durations.iter().map(String::as_str).collect::<Vec<_>>(),
+                )));
+            let position = DURATIONS
+                .iter()
+                .position(|duration| *duration >= properties.default_duration)
+                .unwrap_or(DURATIONS.len() - 1);
+            self.default_duration_choice.set_selected(position as u32);
+
+            for minutes in REMINDERS {
+                let check = gtk::CheckButton::builder()
+                    .active(properties.reminders.contains(&minutes))
+                    .valign(gtk::Align::Center)
+                    .build();
+                check.connect_toggled(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |check| {
+                        let active = check.is_active();
+                        imp.update_properties(|properties| {
+                            properties.reminders.retain(|reminder| *reminder != minutes);
+                            if active {
+                                properties.reminders.push(minutes);
+                                properties.reminders.sort_unstable();
+                            }
+                        });
+                        imp.update_reminders_subtitle();
+                    }
+                ));
+                let row = adw::ActionRow::builder()
+                    .title(format::reminder(minutes))
+                    .activatable_widget(&check)
+                    .build();
+                row.add_prefix(&check);
+                self.reminders_row.add_row(&row);
+            }
+            self.update_reminders_subtitle();
+
+            self.loaded.set(true);
+        }
+
+        /// Change the properties of the calendar with `f`, and save them.
+        fn update_properties(&self, f: impl FnOnce(&mut Properties)) {
+            if !self.loaded.get() {
+                return;
+            }
+            let calendar = self.obj().calendar().unwrap();
+            let calendar_properties = CalendarManagerApplication::default().calendar_properties();
+            let mut properties = calendar_properties.get(&calendar);
+            f(&mut properties);
+            calendar_properties.set(&calendar, properties);
+        }
+
+        fn update_reminders_subtitle(&self) {
+            let calendar = self.obj().calendar().unwrap();
+            let reminders = CalendarManagerApplication::default()
+                .calendar_properties()
+                .get(&calendar)
+                .reminders;
+            let subtitle = if reminders.is_empty() {
+                gettext("None")
+            } else {
+                reminders
+                    .iter()
+                    .map(|minutes| format::reminder(*minutes))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            self.reminders_row.set_subtitle(&subtitle);
+        }
+
+        /// Show how many events the calendar has, and from when to when.
+        fn update_summary(&self) {
+            let calendar = self.obj().calendar().unwrap();
+            let events = calendar.events();
+            let n_events = events.n_items();
+            self.events_row.set_subtitle(
+                &ngettext("{} event", "{} events", n_events).replace("{}", &n_events.to_string()),
+            );
+
+            let time_zones = CalendarManagerApplication::default().time_zones();
+            let timeframes = events
+                .iter::<Event>()
+                .filter_map(Result::ok)
+                .filter_map(|event| event.timeframe());
+            let mut range: Option<(civil::Date, civil::Date)> = None;
+            for timeframe in timeframes {
+                let time_zone = time_zones.for_timeframe(&timeframe);
+                let start = timeframe.start().timestamp();
+                // The end is excluded, like the next day of all-day events
+                let end = timeframe
+                    .end()
+                    .timestamp()
+                    .checked_sub(SignedDuration::from_nanos(1))
+                    .map_or(start, |end| end.max(start));
+                let start = start.to_zoned(time_zone.clone());
+                let end = end.to_zoned(time_zone);
+                let (first, last) = range.unwrap_or((start.date(), end.date()));
+                range = Some((first.min(start.date()), last.max(end.date())));
+            }
+            self.date_range_row.set_visible(range.is_some());
+            if let Some((first, last)) = range {
+                self.date_range_row.set_subtitle(&if first == last {
+                    format::date(first)
+                } else {
+                    format!("{} – {}", format::date(first), format::date(last))
+                });
+            }
+        }
+    }
+
     #[gtk::template_callbacks]
     impl CalendarDetailsPage {
         #[template_callback]
@@ -87,9 +274,102 @@ mod imp {
             calendar.update(None, Some(color));
         }
 
+        #[template_callback]
+        fn update_description(&self) {
+            let description = self.description_entry.text().trim().to_owned();
+            self.update_properties(|properties| properties.description = description);
+        }
+
+        #[template_callback]
+        fn update_default(&self) {
+            if !self.loaded.get() {
+                return;
+            }
+            let calendar = self.obj().calendar().unwrap();
+            CalendarManagerApplication::default()
+                .calendar_properties()
+                .set_default(&calendar, self.default_row.is_active());
+        }
+
+        #[template_callback]
+        fn create_event(&self) {
+            let calendar = self.obj().calendar().unwrap();
+            let dialog = CreateEventDialog::for_calendar(&calendar);
+            dialog.present(Some(&*self.obj()));
+        }
+
+        #[template_callback]
+        fn update_default_duration(&self) {
+            let selected = self.default_duration_choice.selected() as usize;
+            if let Some(duration) = DURATIONS.get(selected) {
+                self.update_properties(|properties| properties.default_duration = *duration);
+            }
+        }
+
+        #[template_callback]
+        fn update_show_as(&self) {
+            let busy = self.show_as_choice.selected() == 0;
+            self.update_properties(|properties| properties.busy = busy);
+        }
+
+        #[template_callback]
+        fn update_locked(&self) {
+            let locked = self.locked_row.is_active();
+            self.update_properties(|properties| properties.locked = locked);
+        }
+
+        #[template_callback]
+        fn export_calendar(&self) {
+            let calendar = self.obj().calendar().unwrap();
+            let vcalendar = VCalendar::from_calendar(&calendar);
+            spawn!(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    save_ics_file(&vcalendar, &*imp.obj()).await;
+                }
+            ));
+        }
+
+        #[template_callback]
+        fn update_refresh_interval(&self) {
+            let Some(subscription) = self.obj().subscription() else {
+                return;
+            };
+            let selected = self.refresh_interval_choice.selected() as usize;
+            if let Some(interval) = REFRESH_INTERVALS.get(selected) {
+                subscription.set_refresh_interval(*interval);
+            }
+        }
+
+        #[template_callback]
+        fn refresh_subscription(&self) {
+            if let Some(subscription) = self.obj().subscription() {
+                CalendarManagerApplication::default()
+                    .subscriptions()
+                    .refresh(&subscription);
+            }
+        }
+
+        #[template_callback(function)]
+        fn has_subscription(subscription: Option<Subscription>) -> bool {
+            subscription.is_some()
+        }
+
+        #[template_callback(function)]
+        fn has_error(error: Option<String>) -> bool {
+            error.is_some()
+        }
+
+        #[template_callback(function)]
+        fn format_last_synced(last_synced: i64) -> String {
+            format::sync_time(last_synced)
+        }
+
         #[template_callback]
         fn delete_calendar(&self) {
             let calendar = self.obj().calendar().unwrap();
+            CalendarManagerApplication::default().forget_calendar(&calendar);
             calendar.delete();
         }
     }
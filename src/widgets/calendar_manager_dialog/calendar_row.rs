@@ -7,7 +7,7 @@ use gtk::{
     glib,
 };
 
-use crate::utils::get_circle_paintable_from_color;
+use crate::{utils::get_circle_paintable_from_color, widgets::ImportDialog};
 
 mod imp {
     use super::*;
@@ -40,6 +40,9 @@ mod imp {
     impl ObjectImpl for CalendarRow {
         fn constructed(&self) {
             self.parent_constructed();
+
+            let obj = self.obj();
+            ImportDialog::install_drop_target(&*obj, obj.calendar().as_ref());
         }
     }
     impl WidgetImpl for CalendarRow {}
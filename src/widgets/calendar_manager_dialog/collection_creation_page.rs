@@ -0,0 +1,76 @@
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::glib;
+
+use crate::CalendarManagerApplication;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/collection_creation_page.ui")]
+    pub struct CollectionCreationPage {
+        #[template_child]
+        pub name: TemplateChild<adw::EntryRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CollectionCreationPage {
+        const NAME: &'static str = "CollectionCreationPage";
+        type Type = super::CollectionCreationPage;
+        type ParentType = adw::NavigationPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for CollectionCreationPage {}
+    impl WidgetImpl for CollectionCreationPage {}
+    impl NavigationPageImpl for CollectionCreationPage {}
+
+    #[gtk::template_callbacks]
+    impl CollectionCreationPage {
+        #[template_callback]
+        fn create_local_collection(&self) {
+            let name = self.name.text();
+            let name = name.trim();
+            if name.is_empty() {
+                return;
+            }
+            CalendarManagerApplication::default()
+                .manager()
+                .create_collection(name);
+            let _ = self
+                .obj()
+                .activate_action("calendar-manager.close-subpage", None);
+        }
+
+        #[template_callback(function)]
+        fn has_name(text: String) -> bool {
+            !text.trim().is_empty()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A page to choose where a new collection keeps its calendars, and to create it.
+    pub struct CollectionCreationPage(ObjectSubclass<imp::CollectionCreationPage>)
+    @extends gtk::Widget, adw::NavigationPage;
+}
+
+impl CollectionCreationPage {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl Default for CollectionCreationPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,17 @@
 use std::cell::RefCell;
 
 use adw::prelude::*;
-use ccm::Collection;
-use gtk::{glib, subclass::prelude::*};
+use ccm::{Calendar, Collection};
+use gettextrs::{gettext, ngettext};
+use gtk::{
+    glib::{self, clone},
+    subclass::prelude::*,
+};
+
+use crate::{
+    CalendarManagerApplication, caldav::Account, format, ics::VCalendar, mirror::Source, spawn,
+    utils::save_ics_file, vdir::Folder,
+};
 
 use super::{calendar_creation_dialog::CalendarCreationDialog, calendar_row::CalendarRow};
 
@@ -15,6 +24,9 @@ mod imp {
     pub struct CollectionRow {
         #[property(get, set, construct_only)]
         pub collection: RefCell<Option<Collection>>,
+        /// The CalDAV account or vdir folder the collection is synced with, if any.
+        #[property(get, nullable)]
+        pub source: RefCell<Option<Source>>,
         // #[template_child]
         // pub preferences_group: TemplateChild<adw::PreferencesGroup>,
         #[template_child]
@@ -32,6 +44,14 @@ mod imp {
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
             klass.bind_template_callbacks();
+
+            klass.install_action("collection.rename", None, |obj, _, _| {
+                obj.imp().rename_collection();
+            });
+
+            klass.install_action("collection.delete", None, |obj, _, _| {
+                obj.imp().delete_collection();
+            });
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -53,11 +73,126 @@ mod imp {
                 .bind_model(Some(&collection.calendars()), |calendar| {
                     CalendarRow::new(calendar.downcast_ref().unwrap()).upcast()
                 });
+
+            let application = CalendarManagerApplication::default();
+            let source = application
+                .caldav_accounts()
+                .find(&collection)
+                .map(Cast::upcast)
+                .or_else(|| {
+                    application
+                        .vdir_folders()
+                        .find(&collection)
+                        .map(Cast::upcast)
+                });
+            self.source.replace(source);
+            self.obj().notify_source();
         }
     }
     impl WidgetImpl for CollectionRow {}
     impl ListBoxRowImpl for CollectionRow {}
 
+    impl CollectionRow {
+        /// Rename the collection, after asking for its new name.
+        fn rename_collection(&self) {
+            let collection = self
+                .obj()
+                .collection()
+                .expect("collection should be initialized");
+            let entry = gtk::Entry::builder()
+                .text(collection.name())
+                .activates_default(true)
+                .build();
+            let alert = adw::AlertDialog::builder()
+                .heading(gettext("Rename Collection"))
+                .extra_child(&entry)
+                .default_response("rename")
+                .close_response("cancel")
+                .build();
+            alert.add_response("cancel", &gettext("_Cancel"));
+            alert.add_response("rename", &gettext("_Rename"));
+            alert.set_response_appearance("rename", adw::ResponseAppearance::Suggested);
+
+            alert.connect_response(Some("rename"), move |_, _| {
+                let name = entry.text();
+                let name = name.trim();
+                if !name.is_empty() && name != collection.name() {
+                    collection.update(Some(name));
+                }
+            });
+            alert.present(Some(&*self.obj()));
+        }
+
+        /// Delete the collection with its calendars and events, after asking for confirmation.
+        ///
+        /// The account or folder it is synced with is forgotten, but what it holds is kept.
+        fn delete_collection(&self) {
+            let collection = self
+                .obj()
+                .collection()
+                .expect("collection should be initialized");
+            let calendars: Vec<Calendar> = collection
+                .calendars()
+                .iter::<Calendar>()
+                .filter_map(Result::ok)
+                .collect();
+            let n_calendars = calendars.len() as u32;
+            let n_events: u32 = calendars
+                .iter()
+                .map(|calendar| calendar.events().n_items())
+                .sum();
+
+            // Translators: How many calendars and events a collection being deleted holds
+            let mut body = gettext("Its {calendars} and {events} will be deleted.")
+                .replace(
+                    "{calendars}",
+                    &ngettext("{} calendar", "{} calendars", n_calendars)
+                        .replace("{}", &n_calendars.to_string()),
+                )
+                .replace(
+                    "{events}",
+                    &ngettext("{} event", "{} events", n_events)
+                        .replace("{}", &n_events.to_string()),
+                );
+            let source = self.obj().source();
+            if let Some(source) = &source {
+                let kept = if source.is::<Account>() {
+                    gettext("The calendars on the server are kept.")
+                } else {
+                    gettext("The files of the folder are kept.")
+                };
+                body = format!("{body} {kept}");
+            }
+
+            let alert = adw::AlertDialog::builder()
+                // Translators: The heading of the confirmation to delete a collection
+                .heading(gettext("Delete {}?").replace("{}", &collection.name()))
+                .body(body)
+                .default_response("cancel")
+                .close_response("cancel")
+                .build();
+            alert.add_response("cancel", &gettext("_Cancel"));
+            alert.add_response("delete", &gettext("_Delete"));
+            alert.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+            alert.connect_response(Some("delete"), move |_, _| {
+                let application = CalendarManagerApplication::default();
+                for calendar in &calendars {
+                    application.forget_calendar(calendar);
+                }
+                if let Some(source) = &source {
+                    if let Some(account) = source.downcast_ref::<Account>() {
+                        application.caldav_accounts().remove(account);
+                    } else if let Some(folder) = source.downcast_ref::<Folder>() {
+                        application.vdir_folders().remove(folder);
+                    }
+                }
+                collection.delete();
+            });
+            alert.present(Some(&*self.obj()));
+        }
+    }
+
     #[gtk::template_callbacks]
     impl CollectionRow {
         #[template_callback]
@@ -71,6 +206,55 @@ mod imp {
             dialog.present(Some(&*self.obj()));
         }
 
+        #[template_callback]
+        fn export_collection(&self) {
+            let collection = self
+                .obj()
+                .collection()
+                .expect("collection should be initialized");
+            let vcalendar = VCalendar::from_collection(&collection);
+            spawn!(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    save_ics_file(&vcalendar, &*imp.obj()).await;
+                }
+            ));
+        }
+
+        #[template_callback]
+        fn sync_source(&self) {
+            let Some(source) = self.obj().source() else {
+                return;
+            };
+            let application = CalendarManagerApplication::default();
+            if let Some(account) = source.downcast_ref::<Account>() {
+                application.caldav_accounts().sync(account);
+            } else if let Some(folder) = source.downcast_ref::<Folder>() {
+                application.vdir_folders().sync(folder);
+            }
+        }
+
+        #[template_callback(function)]
+        fn has_source(source: Option<Source>) -> bool {
+            source.is_some()
+        }
+
+        #[template_callback(function)]
+        fn sync_status(last_synced: i64, last_error: Option<String>, conflicts: u32) -> String {
+            if let Some(error) = last_error {
+                return gettext("Sync failed: {}").replace("{}", &error);
+            }
+            let status = gettext("Last synced: {}").replace("{}", &format::sync_time(last_synced));
+            match conflicts {
+                0 => status,
+                _ => format!(
+                    "{status} · {}",
+                    gettext("Conflicting events were kept as copies")
+                ),
+            }
+        }
+
         #[template_callback]
         fn list_is_empty(&self, n_items: u32) -> bool {
             n_items == 0
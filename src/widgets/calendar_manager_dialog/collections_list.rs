@@ -2,6 +2,8 @@ use adw::prelude::*;
 use ccm::Collection;
 use gtk::{gio::ListModel, glib, subclass::prelude::*};
 
+use crate::{CalendarManagerApplication, holidays::HolidayCalendar, widgets::SmartCalendarRow};
+
 use super::collection_row::CollectionRow;
 
 mod imp {
@@ -12,6 +14,14 @@ mod imp {
     pub struct CollectionsList {
         #[template_child]
         pub collections_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub saved_searches_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub saved_searches_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub holidays_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub birthdays_row: TemplateChild<adw::SwitchRow>,
     }
 
     #[glib::object_subclass]
@@ -32,6 +42,46 @@ mod imp {
     impl ObjectImpl for CollectionsList {
         fn constructed(&self) {
             self.parent_constructed();
+
+            let saved_searches = CalendarManagerApplication::default()
+                .saved_searches()
+                .model();
+            self.saved_searches_list
+                .bind_model(Some(&saved_searches), |smart_calendar| {
+                    SmartCalendarRow::new(
+                        smart_calendar
+                            .downcast_ref()
+                            .expect("Model should contain only SmartCalendar objects"),
+                        true,
+                    )
+                    .upcast()
+                });
+            saved_searches
+                .bind_property("n-items", &*self.saved_searches_box, "visible")
+                .transform_to(|_, n_items: u32| Some(n_items > 0))
+                .sync_create()
+                .build();
+
+            let holidays = CalendarManagerApplication::default().holidays().model();
+            self.holidays_list.bind_model(Some(&holidays), |calendar| {
+                let calendar = calendar
+                    .downcast_ref::<HolidayCalendar>()
+                    .expect("Model should contain only HolidayCalendar objects");
+                let row = adw::SwitchRow::builder().title(calendar.name()).build();
+                calendar
+                    .bind_property("visible", &row, "active")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+                row.upcast()
+            });
+
+            CalendarManagerApplication::default()
+                .birthdays()
+                .bind_property("visible", &*self.birthdays_row, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
         }
     }
     impl WidgetImpl for CollectionsList {}
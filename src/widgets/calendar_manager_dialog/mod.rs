@@ -1,17 +1,25 @@
 use adw::subclass::prelude::*;
 use ccm::Resource;
+use gettextrs::gettext;
 use gtk::{glib, prelude::*};
-use tracing::error;
+use tracing::{debug, error};
 
+mod caldav_account_dialog;
 mod calendar_creation_dialog;
 mod calendar_details_page;
 mod calendar_row;
+mod collection_creation_page;
 mod collection_row;
 mod collections_list;
+mod subscription_dialog;
 
-use crate::CalendarManagerApplication;
+use crate::{CalendarManagerApplication, spawn};
 
-use self::{calendar_details_page::CalendarDetailsPage, collections_list::CollectionsList};
+use self::{
+    caldav_account_dialog::CalDavAccountDialog, calendar_details_page::CalendarDetailsPage,
+    collection_creation_page::CollectionCreationPage, collections_list::CollectionsList,
+    subscription_dialog::SubscriptionDialog,
+};
 
 mod imp {
     use super::*;
@@ -66,6 +74,45 @@ mod imp {
             klass.install_action("calendar-manager.close-subpage", None, |obj, _, _| {
                 obj.imp().navigation_view.pop();
             });
+
+            klass.install_action("calendar-manager.new-collection", None, |obj, _, _| {
+                obj.imp()
+                    .navigation_view
+                    .push(&CollectionCreationPage::new());
+            });
+
+            klass.install_action("calendar-manager.subscribe", None, |obj, _, _| {
+                SubscriptionDialog::new().present(Some(obj));
+            });
+
+            klass.install_action("calendar-manager.add-caldav-account", None, |obj, _, _| {
+                CalDavAccountDialog::new().present(Some(obj));
+            });
+
+            klass.install_action("calendar-manager.add-vdir-folder", None, |obj, _, _| {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(gettext("Add Folder"))
+                    .build();
+                let window = obj.root().and_downcast::<gtk::Window>();
+
+                spawn!(async move {
+                    let folder = match file_dialog.select_folder_future(window.as_ref()).await {
+                        Ok(folder) => folder,
+                        Err(error) => {
+                            debug!("No folder to add: {error}");
+                            return;
+                        }
+                    };
+                    match folder.path() {
+                        Some(path) => {
+                            CalendarManagerApplication::default()
+                                .vdir_folders()
+                                .add(&path);
+                        }
+                        None => error!("{} is not a local folder", folder.uri()),
+                    }
+                });
+            });
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -0,0 +1,123 @@
+use adw::{prelude::*, subclass::prelude::*};
+use ccm::Collection;
+use gtk::glib;
+
+use crate::{CalendarManagerApplication, subscriptions::REFRESH_INTERVALS};
+
+/// The URI schemes of the feeds that can be subscribed to.
+const SUPPORTED_SCHEMES: [&str; 3] = ["http", "https", "webcal"];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/subscription_dialog.ui")]
+    pub struct SubscriptionDialog {
+        #[template_child]
+        url: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        name: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        color: TemplateChild<gtk::ColorDialogButton>,
+        #[template_child]
+        collection_choice: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        refresh_interval_choice: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        subscribe_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SubscriptionDialog {
+        const NAME: &'static str = "SubscriptionDialog";
+        type Type = super::SubscriptionDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SubscriptionDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let manager = CalendarManagerApplication::default().manager();
+            self.collection_choice
+                .set_model(Some(&manager.collections_model()));
+        }
+    }
+    impl WidgetImpl for SubscriptionDialog {}
+    impl AdwDialogImpl for SubscriptionDialog {}
+
+    #[gtk::template_callbacks]
+    impl SubscriptionDialog {
+        fn parsed_url(&self) -> Option<glib::Uri> {
+            let url = glib::Uri::parse(self.url.text().trim(), glib::UriFlags::NONE).ok()?;
+            SUPPORTED_SCHEMES
+                .contains(&url.scheme().as_str())
+                .then_some(url)
+        }
+
+        #[template_callback]
+        fn update_subscribe_sensitivity(&self) {
+            let can_subscribe =
+                self.parsed_url().is_some() && self.collection_choice.selected_item().is_some();
+            self.subscribe_button.set_sensitive(can_subscribe);
+        }
+
+        #[template_callback]
+        fn subscribe(&self) {
+            let Some(url) = self.parsed_url() else {
+                return;
+            };
+            let Some(collection) = self
+                .collection_choice
+                .selected_item()
+                .and_downcast::<Collection>()
+            else {
+                return;
+            };
+
+            let name = self.name.text();
+            let name = match name.trim() {
+                "" => url
+                    .host()
+                    .map(String::from)
+                    .unwrap_or_else(|| url.to_string()),
+                name => name.to_string(),
+            };
+            let refresh_interval = REFRESH_INTERVALS
+                [self.refresh_interval_choice.selected() as usize % REFRESH_INTERVALS.len()];
+
+            let calendar = collection.create_calendar(&name, self.color.rgba());
+            CalendarManagerApplication::default()
+                .subscriptions()
+                .subscribe(&calendar, &url.to_string(), refresh_interval);
+            self.obj().close();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A dialog to subscribe to a remote iCalendar feed.
+    pub struct SubscriptionDialog(ObjectSubclass<imp::SubscriptionDialog>)
+        @extends gtk::Widget, adw::Dialog;
+}
+
+impl SubscriptionDialog {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+}
+
+impl Default for SubscriptionDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
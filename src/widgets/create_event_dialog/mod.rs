@@ -1,10 +1,20 @@
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::Calendar;
+use ccm::{
+    Calendar, Timeframe,
+    jiff::{RoundMode, SignedDuration, Timestamp, Unit, Zoned, ZonedRound, civil, tz::TimeZone},
+};
+use gettextrs::gettext;
 use gtk::glib;
+use tracing::warn;
 
-mod calendar_combo_row;
+use crate::{
+    CalendarManagerApplication, spawn, time_zones,
+    utils::wait_for_event,
+    widgets::{CalendarComboRow, TimeZoneComboRow},
+};
 
-use self::calendar_combo_row::CalendarComboRow;
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
 
 mod imp {
     use super::*;
@@ -15,9 +25,19 @@ mod imp {
         #[template_child]
         name: TemplateChild<adw::EntryRow>,
         #[template_child]
+        all_day: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        start: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        end: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        time_zone: TemplateChild<TimeZoneComboRow>,
+        #[template_child]
         calendar_choice: TemplateChild<CalendarComboRow>,
         #[template_child]
         description: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        create: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -37,23 +57,184 @@ mod imp {
     }
 
     // TODO: Call adw_entry_row_grab_focus_without_selecting on the name entry row
-    impl ObjectImpl for CreateEventDialog {}
+    impl ObjectImpl for CreateEventDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let time_zones = CalendarManagerApplication::default().time_zones();
+            self.time_zone
+                .set_time_zone(time_zones::name(&time_zones.display()));
+            self.set_default_times();
+            self.update_create_sensitivity();
+        }
+    }
     impl WidgetImpl for CreateEventDialog {}
     impl AdwDialogImpl for CreateEventDialog {}
 
     #[gtk::template_callbacks]
     impl CreateEventDialog {
+        /// Prevent creating events in read-only calendars, and events whose times are invalid.
+        #[template_callback]
+        fn update_create_sensitivity(&self) {
+            let read_only = self
+                .calendar_choice
+                .selected_item()
+                .and_downcast::<Calendar>()
+                .is_some_and(|calendar| {
+                    CalendarManagerApplication::default()
+                        .calendar_properties()
+                        .is_read_only(&calendar)
+                });
+            self.calendar_choice.set_subtitle(&if read_only {
+                gettext("This calendar is read-only")
+            } else {
+                String::new()
+            });
+
+            let start = self.parse_entry(&self.start);
+            let end = self.parse_entry(&self.end);
+            for (entry, valid) in [(&self.start, start.is_some()), (&self.end, end.is_some())] {
+                if valid {
+                    entry.remove_css_class("error");
+                } else {
+                    entry.add_css_class("error");
+                }
+            }
+
+            self.create
+                .set_sensitive(!read_only && self.timeframe().is_some());
+        }
+
+        /// Last for the default duration of the newly chosen calendar.
+        #[template_callback]
+        fn calendar_changed(&self) {
+            if !self.all_day.is_active() {
+                let time_zone = TimeZone::get(&self.time_zone.time_zone())
+                    .unwrap_or_else(|_| TimeZone::system());
+                let start = self
+                    .start
+                    .text()
+                    .trim()
+                    .parse::<civil::DateTime>()
+                    .ok()
+                    .and_then(|date_time| date_time.to_zoned(time_zone).ok());
+                if let Some(start) = start {
+                    let end = start.saturating_add(self.default_duration());
+                    self.end
+                        .set_text(&end.strftime(DATE_TIME_FORMAT).to_string());
+                }
+            }
+            self.update_create_sensitivity();
+        }
+
+        #[template_callback]
+        fn all_day_toggled(&self) {
+            if self.all_day.is_active() {
+                // Keep the days of the times
+                for entry in [&self.start, &self.end] {
+                    if let Ok(date_time) = entry.text().parse::<civil::DateTime>() {
+                        entry.set_text(&date_time.date().strftime(DATE_FORMAT).to_string());
+                    }
+                }
+            } else {
+                self.set_default_times();
+            }
+        }
+
         #[template_callback]
         fn create_event(&self) {
+            let Some(timeframe) = self.timeframe() else {
+                return;
+            };
             let calendar: Calendar = self
                 .calendar_choice
                 .selected_item()
                 .expect("There should be a selected item")
                 .downcast()
                 .expect("Selected item should be a Calendar");
-            calendar.create_event(&self.name.text(), &self.description.text());
+
+            CalendarManagerApplication::default()
+                .calendar_properties()
+                .set_last_used(&calendar);
+
+            let uid = glib::uuid_string_random();
+            calendar.import_event(&uid, &self.name.text(), &self.description.text(), timeframe);
+            if !self.all_day.is_active() {
+                let time_zone = self.time_zone.time_zone();
+                spawn!(async move {
+                    match wait_for_event(&calendar, &uid).await {
+                        Some(event) => CalendarManagerApplication::default()
+                            .time_zones()
+                            .set_event(&event.uri(), Some(&time_zone)),
+                        None => warn!("Could not find the new event {uid} to set its time zone"),
+                    }
+                });
+            }
             self.obj().close();
         }
+
+        /// Start at the next hour in the chosen time zone, for the default duration of the chosen
+        /// calendar, or today if the event lasts all day.
+        fn set_default_times(&self) {
+            let time_zone =
+                TimeZone::get(&self.time_zone.time_zone()).unwrap_or_else(|_| TimeZone::system());
+            let now = Zoned::now().with_time_zone(time_zone);
+            let start = now
+                .round(ZonedRound::new().smallest(Unit::Hour).mode(RoundMode::Ceil))
+                .unwrap_or(now);
+            let end = start.saturating_add(self.default_duration());
+
+            let format = if self.all_day.is_active() {
+                DATE_FORMAT
+            } else {
+                DATE_TIME_FORMAT
+            };
+            self.start.set_text(&start.strftime(format).to_string());
+            self.end.set_text(&end.strftime(format).to_string());
+        }
+
+        /// The default duration of the events of the chosen calendar.
+        fn default_duration(&self) -> SignedDuration {
+            self.calendar_choice
+                .selected_item()
+                .and_downcast::<Calendar>()
+                .map(|calendar| {
+                    CalendarManagerApplication::default()
+                        .calendar_properties()
+                        .get(&calendar)
+                        .default_duration()
+                })
+                .unwrap_or(SignedDuration::from_hours(1))
+        }
+
+        /// The time or the day of `entry`, as the start of the time or day.
+        fn parse_entry(&self, entry: &adw::EntryRow) -> Option<Timestamp> {
+            let text = entry.text();
+            if self.all_day.is_active() {
+                let date = text.trim().parse::<civil::Date>().ok()?;
+                time_zones::day_bounds(date, &TimeZone::system()).map(|(start, _)| start)
+            } else {
+                let time_zone = TimeZone::get(&self.time_zone.time_zone()).ok()?;
+                let date_time = text.trim().parse::<civil::DateTime>().ok()?;
+                Some(date_time.to_zoned(time_zone).ok()?.timestamp())
+            }
+        }
+
+        /// When the event happens, if its times are valid.
+        ///
+        /// The end of all-day events is the last day they include, and they are stored until the
+        /// start of the next day.
+        fn timeframe(&self) -> Option<Timeframe> {
+            let all_day = self.all_day.is_active();
+            let start = self.parse_entry(&self.start)?;
+            let end = if all_day {
+                let date = self.end.text().trim().parse::<civil::Date>().ok()?;
+                time_zones::day_bounds(date, &TimeZone::system())?.1
+            } else {
+                self.parse_entry(&self.end)?
+            };
+            (end > start).then(|| Timeframe::new(start, end, all_day))
+        }
     }
 }
 
@@ -66,6 +247,13 @@ impl CreateEventDialog {
     pub fn new() -> Self {
         glib::Object::new()
     }
+
+    /// Create a dialog for an event in `calendar`.
+    pub fn for_calendar(calendar: &Calendar) -> Self {
+        let obj = Self::new();
+        obj.imp().calendar_choice.select_calendar(calendar);
+        obj
+    }
 }
 
 impl Default for CreateEventDialog {
@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+
+use adw::{prelude::*, subclass::prelude::*};
+use ccm::{Event, Timeframe};
+use gettextrs::gettext;
+use gtk::{
+    gdk::{Paintable, RGBA},
+    glib,
+};
+
+use crate::{
+    CalendarManagerApplication, format, time_zones, utils::get_circle_paintable_from_color,
+    widgets::TimeZoneComboRow,
+};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/event_details_dialog.ui")]
+    #[properties(wrapper_type = super::EventDetailsDialog)]
+    pub struct EventDetailsDialog {
+        #[property(get, set, construct_only)]
+        pub event: RefCell<Option<Event>>,
+        #[template_child]
+        other_date_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        event_time_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        time_zone_row: TemplateChild<TimeZoneComboRow>,
+        #[template_child]
+        secondary_times: TemplateChild<adw::PreferencesGroup>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EventDetailsDialog {
+        const NAME: &'static str = "EventDetailsDialog";
+        type Type = super::EventDetailsDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            Timeframe::ensure_type();
+
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for EventDetailsDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let Some(event) = self.obj().event() else {
+                return;
+            };
+            self.update_other_date();
+
+            // All-day events are the same days in every time zone
+            if event
+                .timeframe()
+                .is_none_or(|timeframe| timeframe.all_day())
+            {
+                self.time_zone_row.set_visible(false);
+                return;
+            }
+
+            let application = CalendarManagerApplication::default();
+            let time_zones = application.time_zones();
+            self.time_zone_row
+                .set_time_zone(time_zones::name(&time_zones.event(&event)));
+            self.time_zone_row.set_sensitive(
+                !application
+                    .calendar_properties()
+                    .is_read_only(&event.calendar()),
+            );
+            self.update_event_time();
+
+            let Some(timeframe) = event.timeframe() else {
+                return;
+            };
+            for (name, time_zone) in time_zones.secondary() {
+                let row = adw::ActionRow::builder()
+                    .title(name.replace('_', " "))
+                    .subtitle(format!(
+                        "{} {}",
+                        format::timeframe_in(&timeframe, &time_zone),
+                        timeframe
+                            .start()
+                            .timestamp()
+                            .to_zoned(time_zone)
+                            .strftime("%Z")
+                    ))
+                    .css_classes(["property"])
+                    .build();
+                self.secondary_times.add(&row);
+                self.secondary_times.set_visible(true);
+            }
+        }
+    }
+    impl WidgetImpl for EventDetailsDialog {}
+    impl AdwDialogImpl for EventDetailsDialog {}
+
+    #[gtk::template_callbacks]
+    impl EventDetailsDialog {
+        #[template_callback]
+        fn time_zone_changed(&self) {
+            let Some(event) = self.obj().event() else {
+                return;
+            };
+            let application = CalendarManagerApplication::default();
+            // The time zone is part of the event, which cannot be changed in read-only calendars
+            if application
+                .calendar_properties()
+                .is_read_only(&event.calendar())
+            {
+                return;
+            }
+            let name = self.time_zone_row.time_zone();
+            let time_zones = application.time_zones();
+            if name != time_zones::name(&time_zones.event(&event)) {
+                time_zones.set_event(&event.uri(), Some(&name));
+                self.update_event_time();
+            }
+        }
+
+        /// Show the days of the event in the calendar system shown next to the Gregorian one.
+        fn update_other_date(&self) {
+            let Some(timeframe) = self.obj().event().and_then(|event| event.timeframe()) else {
+                return;
+            };
+            let application = CalendarManagerApplication::default();
+            let time_zone = application.time_zones().for_timeframe(&timeframe);
+            let start = timeframe.start().timestamp().to_zoned(time_zone.clone());
+            let mut end = timeframe.end().timestamp().to_zoned(time_zone);
+            // The end of all-day events is the start of the next day
+            if timeframe.all_day() {
+                end = end.yesterday().unwrap_or(end);
+            }
+
+            let calendar_system = application.calendar_system();
+            let Some(start_date) = calendar_system.date(start.date()) else {
+                return;
+            };
+            let subtitle = match calendar_system.date(end.date()) {
+                Some(end_date) if end_date != start_date => {
+                    // Translators: The first and last days of an event in another calendar system
+                    gettext("{start} – {end}")
+                        .replace("{start}", &start_date.long())
+                        .replace("{end}", &end_date.long())
+                }
+                _ => start_date.long(),
+            };
+            self.other_date_row.set_subtitle(&subtitle);
+            self.other_date_row.set_visible(true);
+        }
+
+        /// Show when the event happens in its own time zone, if it is not the display one.
+        fn update_event_time(&self) {
+            let Some(event) = self.obj().event() else {
+                return;
+            };
+            let Some(timeframe) = event.timeframe() else {
+                return;
+            };
+            let time_zones = CalendarManagerApplication::default().time_zones();
+            let time_zone = time_zones.event(&event);
+            let shown = time_zone != time_zones.display();
+            self.event_time_row.set_visible(shown);
+            if shown {
+                self.event_time_row.set_subtitle(&format!(
+                    "{} ({})",
+                    format::timeframe_in(&timeframe, &time_zone),
+                    time_zones::name(&time_zone).replace('_', " ")
+                ));
+            }
+        }
+
+        #[template_callback(function)]
+        fn format_time(timeframe: Option<Timeframe>) -> String {
+            timeframe
+                .map(|timeframe| format::timeframe(&timeframe))
+                .unwrap_or_default()
+        }
+
+        #[template_callback(function)]
+        fn get_color_image(color: RGBA) -> Paintable {
+            get_circle_paintable_from_color(&color, 16.)
+        }
+
+        #[template_callback(function)]
+        fn has_text(text: Option<String>) -> bool {
+            text.is_some_and(|text| !text.is_empty())
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The details of an event, e.g. when opening it from the search results.
+    pub struct EventDetailsDialog(ObjectSubclass<imp::EventDetailsDialog>)
+        @extends gtk::Widget, adw::Dialog;
+}
+
+impl EventDetailsDialog {
+    pub fn new(event: &Event) -> Self {
+        glib::Object::builder().property("event", event).build()
+    }
+}
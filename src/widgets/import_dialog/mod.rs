@@ -0,0 +1,304 @@
+use std::cell::{OnceCell, RefCell};
+
+use adw::{prelude::*, subclass::prelude::*};
+use ccm::Calendar;
+use gettextrs::{gettext, ngettext};
+use gtk::{gdk, gio, glib};
+use tracing::error;
+
+use crate::{
+    CalendarManagerApplication,
+    ics::{self, ImportPlan, VCalendar},
+    spawn,
+    widgets::CalendarComboRow,
+};
+
+/// The MIME type of iCalendar data, e.g. when dragged from a mail client.
+const ICALENDAR_MIME_TYPE: &str = "text/calendar";
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/import_dialog.ui")]
+    #[properties(wrapper_type = super::ImportDialog)]
+    pub struct ImportDialog {
+        /// The name of the imported file, shown to the user.
+        #[property(get, set, construct_only)]
+        source_name: RefCell<String>,
+        #[template_child]
+        pub calendar_choice: TemplateChild<CalendarComboRow>,
+        #[template_child]
+        added_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        updated_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        skipped_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        dropped_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        import_button: TemplateChild<gtk::Button>,
+        pub vcalendar: OnceCell<VCalendar>,
+        plan: RefCell<Option<ImportPlan>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ImportDialog {
+        const NAME: &'static str = "ImportDialog";
+        type Type = super::ImportDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for ImportDialog {}
+    impl WidgetImpl for ImportDialog {}
+    impl AdwDialogImpl for ImportDialog {}
+
+    #[gtk::template_callbacks]
+    impl ImportDialog {
+        /// Compare the file with the selected calendar and show what importing would do.
+        #[template_callback]
+        pub fn update_summary(&self) {
+            let Some(vcalendar) = self.vcalendar.get() else {
+                return;
+            };
+            let Some(calendar) = self
+                .calendar_choice
+                .selected_item()
+                .and_downcast::<Calendar>()
+            else {
+                self.plan.replace(None);
+                self.import_button.set_sensitive(false);
+                return;
+            };
+
+            let calendar_properties = CalendarManagerApplication::default().calendar_properties();
+            if calendar_properties.is_read_only(&calendar) {
+                self.calendar_choice
+                    .set_subtitle(&gettext("This calendar is read-only"));
+                self.plan.replace(None);
+                self.import_button.set_sensitive(false);
+                return;
+            }
+            self.calendar_choice.set_subtitle("");
+
+            let plan = ImportPlan::new(vcalendar, &calendar);
+            let summary = plan.summary();
+            self.added_label.set_label(&summary.added.to_string());
+            self.updated_label.set_label(&summary.updated.to_string());
+            self.skipped_label.set_label(&summary.skipped.to_string());
+
+            // Tell the user about what is lost, as ccm has nowhere to store it or it cannot be
+            // expanded into single events
+            let mut dropped = Vec::new();
+            if summary.dropped_alarms > 0 {
+                dropped.push(
+                    ngettext(
+                        "The reminders of {} event",
+                        "The reminders of {} events",
+                        summary.dropped_alarms as u32,
+                    )
+                    .replace("{}", &summary.dropped_alarms.to_string()),
+                );
+            }
+            if summary.unsupported_recurrences > 0 {
+                dropped.push(
+                    ngettext(
+                        "{} recurring event with an unsupported repetition",
+                        "{} recurring events with an unsupported repetition",
+                        summary.unsupported_recurrences as u32,
+                    )
+                    .replace("{}", &summary.unsupported_recurrences.to_string()),
+                );
+            }
+            self.dropped_row.set_visible(!dropped.is_empty());
+            self.dropped_row.set_subtitle(&dropped.join("\n"));
+
+            self.import_button
+                .set_sensitive(summary.added + summary.updated > 0);
+            self.plan.replace(Some(plan));
+        }
+
+        #[template_callback]
+        fn import_events(&self) {
+            if let Some(plan) = self.plan.take() {
+                spawn!(plan.apply());
+            }
+            if let Some(calendar) = self
+                .calendar_choice
+                .selected_item()
+                .and_downcast::<Calendar>()
+            {
+                CalendarManagerApplication::default()
+                    .calendar_properties()
+                    .set_last_used(&calendar);
+            }
+            self.obj().close();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ImportDialog(ObjectSubclass<imp::ImportDialog>)
+        @extends gtk::Widget, adw::Dialog;
+}
+
+impl ImportDialog {
+    pub fn new(vcalendar: VCalendar, source_name: &str) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("source-name", source_name)
+            .build();
+        obj.imp()
+            .vcalendar
+            .set(vcalendar)
+            .expect("vcalendar should only be set once");
+        obj.imp().update_summary();
+        obj
+    }
+
+    /// Read an iCalendar file and present the import preview for it over `parent`.
+    ///
+    /// When `calendar` is set, it is preselected as the destination of the events. Errors are
+    /// reported to the user with an alert.
+    pub async fn present_for_file(
+        file: &gio::File,
+        calendar: Option<&Calendar>,
+        parent: &impl IsA<gtk::Widget>,
+    ) {
+        let source_name = file
+            .basename()
+            .map(|name| name.display().to_string())
+            .unwrap_or_else(|| file.uri().to_string());
+
+        let contents = file
+            .load_contents_future()
+            .await
+            .map(|(contents, _)| contents.to_vec())
+            .map_err(|error| error.to_string());
+        Self::present_for_contents(contents, &source_name, calendar, parent);
+    }
+
+    /// Accept iCalendar files and data dropped on `widget`, and present the import preview for
+    /// them.
+    ///
+    /// The widget is highlighted while something that can be imported is dragged over it.
+    pub fn install_drop_target(widget: &impl IsA<gtk::Widget>, calendar: Option<&Calendar>) {
+        let formats = gdk::ContentFormatsBuilder::new()
+            .add_type(gdk::FileList::static_type())
+            .add_mime_type(ICALENDAR_MIME_TYPE)
+            .build();
+        let drop_target = gtk::DropTargetAsync::new(Some(formats), gdk::DragAction::COPY);
+
+        let calendar = calendar.cloned();
+        drop_target.connect_drop(move |drop_target, drop, _, _| {
+            let Some(parent) = drop_target.widget() else {
+                return false;
+            };
+            let drop = drop.clone();
+            let calendar = calendar.clone();
+            spawn!(async move {
+                Self::import_drop(&drop, calendar.as_ref(), &parent).await;
+            });
+            true
+        });
+
+        widget.add_css_class("import-drop-target");
+        widget.add_controller(drop_target);
+    }
+
+    async fn import_drop(drop: &gdk::Drop, calendar: Option<&Calendar>, parent: &gtk::Widget) {
+        if drop.formats().contain_type(gdk::FileList::static_type()) {
+            let files = match drop
+                .read_value_future(gdk::FileList::static_type(), glib::Priority::DEFAULT)
+                .await
+                .map_err(|error| error.to_string())
+                .and_then(|value| {
+                    value
+                        .get::<gdk::FileList>()
+                        .map_err(|error| error.to_string())
+                }) {
+                Ok(files) => files.files(),
+                Err(error) => {
+                    error!("Could not read dropped files: {error}");
+                    drop.finish(gdk::DragAction::empty());
+                    return;
+                }
+            };
+            drop.finish(gdk::DragAction::COPY);
+
+            for file in files {
+                Self::present_for_file(&file, calendar, parent).await;
+            }
+            return;
+        }
+
+        let contents = match drop
+            .read_future(&[ICALENDAR_MIME_TYPE], glib::Priority::DEFAULT)
+            .await
+        {
+            Ok((stream, _)) => read_stream(&stream).await,
+            Err(error) => Err(error),
+        };
+        match &contents {
+            Ok(_) => drop.finish(gdk::DragAction::COPY),
+            Err(_) => drop.finish(gdk::DragAction::empty()),
+        }
+
+        Self::present_for_contents(
+            contents.map_err(|error| error.to_string()),
+            &gettext("Dropped Events"),
+            calendar,
+            parent,
+        );
+    }
+
+    fn present_for_contents(
+        contents: Result<Vec<u8>, String>,
+        source_name: &str,
+        calendar: Option<&Calendar>,
+        parent: &impl IsA<gtk::Widget>,
+    ) {
+        let vcalendar = contents.and_then(|contents| {
+            ics::parse(&String::from_utf8_lossy(&contents)).map_err(|error| error.to_string())
+        });
+
+        match vcalendar {
+            Ok(vcalendar) => {
+                let dialog = ImportDialog::new(vcalendar, source_name);
+                if let Some(calendar) = calendar {
+                    dialog.imp().calendar_choice.select_calendar(calendar);
+                }
+                dialog.present(Some(parent));
+            }
+            Err(error) => {
+                error!("Could not import {source_name}: {error}");
+                let alert =
+                    adw::AlertDialog::new(Some(&gettext("Could Not Import File")), Some(&error));
+                alert.add_response("close", &gettext("_Close"));
+                alert.present(Some(parent));
+            }
+        }
+    }
+}
+
+/// Read `stream` to the end.
+async fn read_stream(stream: &gio::InputStream) -> Result<Vec<u8>, glib::Error> {
+    let output = gio::MemoryOutputStream::new_resizable();
+    output
+        .splice_future(
+            stream,
+            gio::OutputStreamSpliceFlags::CLOSE_SOURCE | gio::OutputStreamSpliceFlags::CLOSE_TARGET,
+            glib::Priority::DEFAULT,
+        )
+        .await?;
+    Ok(output.steal_as_bytes().to_vec())
+}
@@ -1,10 +1,19 @@
+mod calendar_combo_row;
 mod calendar_manager_dialog;
 mod create_event_dialog;
+mod event_details_dialog;
+mod import_dialog;
+mod preferences_dialog;
 mod search_dialog;
+mod smart_calendar_row;
+mod time_zone_combo_row;
 mod views;
 mod window;
 
 pub use self::{
-    calendar_manager_dialog::CalendarManagerDialog, create_event_dialog::CreateEventDialog,
-    search_dialog::SearchDialog, window::CalendarManagerWindow,
+    calendar_combo_row::CalendarComboRow, calendar_manager_dialog::CalendarManagerDialog,
+    create_event_dialog::CreateEventDialog, event_details_dialog::EventDetailsDialog,
+    import_dialog::ImportDialog, preferences_dialog::PreferencesDialog,
+    search_dialog::SearchDialog, smart_calendar_row::SmartCalendarRow,
+    time_zone_combo_row::TimeZoneComboRow, window::CalendarManagerWindow,
 };
@@ -0,0 +1,164 @@
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::glib::{self, clone};
+
+use crate::{
+    CalendarManagerApplication, calendar_systems::CalendarSystem, time_zones,
+    widgets::TimeZoneComboRow,
+};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/preferences_dialog.ui")]
+    pub struct PreferencesDialog {
+        #[template_child]
+        calendar_system: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        moon_phases: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        daylight: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        latitude: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        longitude: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        display_time_zone: TemplateChild<TimeZoneComboRow>,
+        #[template_child]
+        second_time_zone: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        second_time_zone_choice: TemplateChild<TimeZoneComboRow>,
+        #[template_child]
+        third_time_zone: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        third_time_zone_choice: TemplateChild<TimeZoneComboRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PreferencesDialog {
+        const NAME: &'static str = "PreferencesDialog";
+        type Type = super::PreferencesDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PreferencesDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            // The choices of the row are in the order of `CalendarSystem::ALL`
+            let application = CalendarManagerApplication::default();
+            application
+                .bind_property("calendar-system", &*self.calendar_system, "selected")
+                .transform_to(|_, calendar_system: CalendarSystem| {
+                    CalendarSystem::ALL
+                        .iter()
+                        .position(|choice| *choice == calendar_system)
+                        .map(|position| position as u32)
+                })
+                .transform_from(|_, selected: u32| {
+                    CalendarSystem::ALL.get(selected as usize).copied()
+                })
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            let astronomy = application.astronomy();
+            astronomy
+                .bind_property("show-moon-phases", &*self.moon_phases, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            astronomy
+                .bind_property("show-daylight", &*self.daylight, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            astronomy
+                .bind_property("latitude", &*self.latitude, "value")
+                .bidirectional()
+                .sync_create()
+                .build();
+            astronomy
+                .bind_property("longitude", &*self.longitude, "value")
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            let time_zones = application.time_zones();
+            time_zones
+                .bind_property("display-name", &*self.display_time_zone, "time-zone")
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            let display_name = time_zones::name(&time_zones.display());
+            let mut secondary_names = time_zones.secondary_names().into_iter();
+            for (row, choice) in self.secondary_rows() {
+                let name = secondary_names.next();
+                row.set_enable_expansion(name.is_some());
+                choice.set_time_zone(name.unwrap_or_else(|| display_name.clone()));
+
+                // Connected once the rows show the settings, to not save them half loaded
+                row.connect_enable_expansion_notify(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.save_secondary_time_zones()
+                ));
+                choice.connect_time_zone_notify(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.save_secondary_time_zones()
+                ));
+            }
+        }
+    }
+    impl WidgetImpl for PreferencesDialog {}
+    impl AdwDialogImpl for PreferencesDialog {}
+    impl PreferencesDialogImpl for PreferencesDialog {}
+
+    impl PreferencesDialog {
+        fn secondary_rows(&self) -> [(&adw::ExpanderRow, &TimeZoneComboRow); 2] {
+            [
+                (&self.second_time_zone, &self.second_time_zone_choice),
+                (&self.third_time_zone, &self.third_time_zone_choice),
+            ]
+        }
+
+        fn save_secondary_time_zones(&self) {
+            let names: Vec<String> = self
+                .secondary_rows()
+                .into_iter()
+                .filter(|(row, _)| row.enables_expansion())
+                .map(|(_, choice)| choice.time_zone())
+                .collect();
+            CalendarManagerApplication::default()
+                .time_zones()
+                .set_secondary_names(names);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PreferencesDialog(ObjectSubclass<imp::PreferencesDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::PreferencesDialog;
+}
+
+impl PreferencesDialog {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl Default for PreferencesDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,15 @@
 use std::cell::RefCell;
 
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::{Event, Timeframe};
+use ccm::{Event, Timeframe, jiff::Zoned};
 use gtk::{
     gdk::{Paintable, RGBA},
     glib,
 };
 
-use crate::utils::get_horizontal_bar_paintable_from_color;
+use crate::{
+    CalendarManagerApplication, format, search, utils::get_horizontal_bar_paintable_from_color,
+};
 
 mod imp {
     use super::*;
@@ -18,6 +20,14 @@ mod imp {
     pub struct EventRow {
         #[property(get, set)]
         pub event: RefCell<Option<Event>>,
+        /// The searched text, shown in bold in the name.
+        #[property(get, set)]
+        pub highlight: RefCell<String>,
+        /// Pango markup of the part of the description matching the search, if any.
+        #[property(get, set)]
+        pub snippet: RefCell<String>,
+        #[template_child]
+        pub show_button: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -39,7 +49,16 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for EventRow {}
+    impl ObjectImpl for EventRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            if let Some(event) = self.obj().event() {
+                self.show_button
+                    .set_action_target_value(Some(&event.uri().to_variant()));
+            }
+        }
+    }
     impl WidgetImpl for EventRow {}
     impl BoxImpl for EventRow {}
 
@@ -50,32 +69,76 @@ mod imp {
             get_horizontal_bar_paintable_from_color(&color, 6., 48.)
         }
 
+        #[template_callback(function)]
+        fn has_text(text: String) -> bool {
+            !text.is_empty()
+        }
+
+        #[template_callback]
+        fn highlight_name(&self, name: String, highlight: String) -> String {
+            let mut markup = String::new();
+            let mut end = 0;
+            for range in search::match_ranges(&name, &search::Query::parse(&highlight).terms) {
+                markup.push_str(&glib::markup_escape_text(&name[end..range.start]));
+                markup.push_str("<b>");
+                markup.push_str(&glib::markup_escape_text(&name[range.clone()]));
+                markup.push_str("</b>");
+                end = range.end;
+            }
+            markup.push_str(&glib::markup_escape_text(&name[end..]));
+            markup
+        }
+
         #[template_callback]
         fn get_start_time(&self) -> String {
-            let Some(event) = self.obj().event() else {
+            let Some((start, _)) = self.times() else {
                 return String::new();
             };
-            format!(
-                "{}",
-                &event
-                    .timeframe()
-                    .expect("Event should have a timeframe")
-                    .start()
-            )
+            if self.all_day() {
+                let today = Zoned::now().with_time_zone(start.time_zone().clone());
+                format::relative_day(start.date(), today.date())
+            } else {
+                format::relative_date_time(&start)
+            }
         }
 
         #[template_callback]
         fn get_end_time(&self) -> String {
-            let Some(event) = self.obj().event() else {
+            let Some((start, end)) = self.times() else {
                 return String::new();
             };
-            format!(
-                "{}",
-                &event
-                    .timeframe()
-                    .expect("Event should have a timeframe")
-                    .end()
-            )
+            if self.all_day() {
+                // The end of all-day events is the start of the next day
+                let last_day = end.yesterday().unwrap_or(end);
+                if last_day.date() > start.date() {
+                    format::date(last_day.date())
+                } else {
+                    String::new()
+                }
+            } else if end.date() == start.date() {
+                format::time(end.time())
+            } else {
+                format::relative_date_time(&end)
+            }
+        }
+
+        fn all_day(&self) -> bool {
+            self.obj()
+                .event()
+                .and_then(|event| event.timeframe())
+                .is_some_and(|timeframe| timeframe.all_day())
+        }
+
+        /// The start and end of the event, in the time zone it is shown in.
+        fn times(&self) -> Option<(Zoned, Zoned)> {
+            let timeframe = self.obj().event()?.timeframe()?;
+            let time_zone = CalendarManagerApplication::default()
+                .time_zones()
+                .for_timeframe(&timeframe);
+            Some((
+                timeframe.start().timestamp().to_zoned(time_zone.clone()),
+                timeframe.end().timestamp().to_zoned(time_zone),
+            ))
         }
     }
 }
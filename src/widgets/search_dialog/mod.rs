@@ -1,13 +1,35 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+};
+
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::Event;
-use gtk::glib;
+use ccm::{
+    Calendar, Event, Resource,
+    jiff::{Timestamp, Zoned, civil},
+};
+use gettextrs::gettext;
+use gtk::{
+    gdk, gio,
+    glib::{self, clone, translate::IntoGlib},
+};
+use tracing::warn;
 
 mod event_row;
 
-use crate::CalendarManagerApplication;
+use crate::{
+    CalendarManagerApplication, format,
+    search::{self, DateRange, EventData, Filters, Hit, Kind, Query, Section},
+    spawn, time_zones,
+    utils::all_calendars,
+    widgets::{CalendarManagerWindow, EventDetailsDialog},
+};
 
 use self::event_row::EventRow;
 
+/// The number of calendar names suggested at once.
+const MAX_COMPLETIONS: usize = 5;
+
 mod imp {
     use super::*;
 
@@ -17,7 +39,40 @@ mod imp {
         #[template_child]
         pub search_entry: TemplateChild<gtk::SearchEntry>,
         #[template_child]
+        pub calendar_filter: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub range_filter: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub kind_filter: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub sort_order: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub from_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
+        pub from_calendar: TemplateChild<gtk::Calendar>,
+        #[template_child]
+        pub to_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
+        pub to_calendar: TemplateChild<gtk::Calendar>,
+        #[template_child]
         pub results_view: TemplateChild<gtk::ListView>,
+        #[template_child]
+        pub completion_popover: TemplateChild<gtk::Popover>,
+        #[template_child]
+        pub completion_list: TemplateChild<gtk::ListBox>,
+        /// The calendar names suggested in the completion popover.
+        pub completions: RefCell<Vec<String>>,
+        /// When the results were sorted in sections.
+        pub search_time: RefCell<Option<Zoned>>,
+        /// The section headers, removed when the results are sorted by relevance, see
+        /// [`SearchDialog::show_results`].
+        pub header_factory: OnceCell<gtk::ListItemFactory>,
+        /// The snippets of the descriptions of the results found in the index, by URI.
+        pub snippets: RefCell<HashMap<String, String>>,
+        /// The calendars of the calendar filter, after "All Calendars".
+        pub calendars: RefCell<Vec<Calendar>>,
+        /// Cancels the running search when a new one starts.
+        pub cancellable: RefCell<Option<gio::Cancellable>>,
     }
 
     #[glib::object_subclass]
@@ -29,6 +84,16 @@ mod imp {
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
             klass.bind_template_callbacks();
+
+            klass.install_action(
+                "search.show-in-calendar",
+                Some(glib::VariantTy::STRING),
+                |obj, _, uri| {
+                    if let Some(uri) = uri.and_then(|uri| uri.str()) {
+                        obj.imp().show_in_calendar(uri);
+                    }
+                },
+            );
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -37,32 +102,295 @@ mod imp {
     }
 
     // TODO: Call adw_entry_row_grab_focus_without_selecting on the name entry row
-    impl ObjectImpl for SearchDialog {}
+    impl ObjectImpl for SearchDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let manager = CalendarManagerApplication::default().manager();
+            let calendars = all_calendars(&manager);
+            let names = gtk::StringList::new(&[&gettext("All Calendars")]);
+            for calendar in &calendars {
+                names.append(&calendar.name());
+            }
+            self.calendar_filter.set_model(Some(&names));
+            self.calendars.replace(calendars);
+
+            self.update_range_labels();
+            self.completion_popover.set_parent(&*self.search_entry);
+            self.search_entry
+                .set_key_capture_widget(Some(&*self.results_view));
+            if let Some(header_factory) = self.results_view.header_factory() {
+                let _ = self.header_factory.set(header_factory);
+            }
+        }
+
+        fn dispose(&self) {
+            self.completion_popover.unparent();
+            if let Some(cancellable) = self.cancellable.take() {
+                cancellable.cancel();
+            }
+        }
+    }
+
     impl WidgetImpl for SearchDialog {}
     impl AdwDialogImpl for SearchDialog {}
 
     #[gtk::template_callbacks]
     impl SearchDialog {
+        /// Search in the background, dropping the results of the previous search.
+        ///
+        /// The search entry delays this until the user stops typing.
         #[template_callback]
         fn search_events(&self) {
-            let manager = CalendarManagerApplication::default().manager();
+            if let Some(cancellable) = self.cancellable.take() {
+                cancellable.cancel();
+            }
             let text = self.search_entry.text();
-            let results = manager.search_events(&text);
-            self.results_view
-                .set_model(Some(&gtk::NoSelection::new(Some(results))));
+            self.results_view.set_model(None::<&gtk::SelectionModel>);
+
+            let query = Query::parse(&text);
+            let filters = self.filters();
+            if query.is_empty() && filters.is_empty() {
+                return;
+            }
+
+            let calendars: Vec<Calendar> = match &filters.calendar_uri {
+                Some(uri) => self
+                    .calendars
+                    .borrow()
+                    .iter()
+                    .filter(|calendar| &calendar.uri() == uri)
+                    .cloned()
+                    .collect(),
+                None => self.calendars.borrow().clone(),
+            };
+            let (events, data): (Vec<Event>, Vec<EventData>) = calendars
+                .iter()
+                .flat_map(|calendar| calendar.events().iter::<Event>().filter_map(Result::ok))
+                .filter_map(|event| EventData::new(&event).map(|data| (event, data)))
+                .unzip();
+
+            let cancellable = gio::Cancellable::new();
+            self.cancellable.replace(Some(cancellable.clone()));
+            spawn!(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    // Words and phrases are found in the full-text index, which ranks the events
+                    let index = CalendarManagerApplication::default().search_index();
+                    let hits = match index {
+                        Some(index) if !query.terms.is_empty() => {
+                            match index.search(&query.terms).await {
+                                Ok(hits) => Some(hits),
+                                Err(error) => {
+                                    warn!("Could not search the index: {error}");
+                                    None
+                                }
+                            }
+                        }
+                        _ => None,
+                    };
+                    if cancellable.is_cancelled() {
+                        return;
+                    }
+
+                    let (query, events, data) = match &hits {
+                        Some(hits) => {
+                            let ranks: HashMap<&str, usize> = hits
+                                .iter()
+                                .enumerate()
+                                .map(|(rank, hit)| (hit.uri.as_str(), rank))
+                                .collect();
+                            let (events, data): (Vec<Event>, Vec<EventData>) = events
+                                .into_iter()
+                                .zip(data)
+                                .filter(|(_, data)| ranks.contains_key(data.uri.as_str()))
+                                .unzip();
+                            (query.without_terms(), events, data)
+                        }
+                        None => (query, events, data),
+                    };
+
+                    let search_cancellable = cancellable.clone();
+                    let result = gio::spawn_blocking(move || {
+                        search::search(&data, &query, &filters, &search_cancellable)
+                    })
+                    .await;
+                    let Ok(Some(indices)) = result else {
+                        return;
+                    };
+                    if cancellable.is_cancelled() {
+                        return;
+                    }
+
+                    let results: Vec<Event> = indices
+                        .into_iter()
+                        .map(|index| events[index].clone())
+                        .collect();
+                    imp.show_results(&results, hits.as_deref());
+                }
+            ));
+        }
+
+        /// Suggest the names of calendars while a `calendar:` key is typed.
+        #[template_callback]
+        fn update_completion(&self) {
+            let text = self.search_entry.text();
+            let completions: Vec<String> = search::calendar_completion(&text)
+                .map(|(_, typed)| {
+                    self.calendars
+                        .borrow()
+                        .iter()
+                        .map(Calendar::name)
+                        .filter(|name| {
+                            let name = name.to_lowercase();
+                            name.starts_with(&typed) && name != typed
+                        })
+                        .take(MAX_COMPLETIONS)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            self.completion_list.remove_all();
+            for name in &completions {
+                let label = gtk::Label::builder().label(name).xalign(0.).build();
+                self.completion_list.append(&label);
+            }
+            if completions.is_empty() {
+                self.completion_popover.popdown();
+            } else {
+                self.completion_popover.popup();
+            }
+            self.completions.replace(completions);
+        }
+
+        /// Complete the first suggestion, or open the first result, when Enter is pressed.
+        #[template_callback]
+        fn entry_activated(&self) {
+            if self.completion_popover.is_visible() {
+                self.complete(0);
+            } else if self
+                .results_view
+                .model()
+                .is_some_and(|model| model.n_items() > 0)
+            {
+                self.open_event_details(0);
+            }
+        }
+
+        /// Move the focus from the search entry to the results with the Down key.
+        #[template_callback]
+        fn entry_key_pressed(&self, keyval: u32) -> bool {
+            let has_results = self
+                .results_view
+                .model()
+                .is_some_and(|model| model.n_items() > 0);
+            if keyval != gdk::Key::Down.into_glib()
+                || self.completion_popover.is_visible()
+                || !has_results
+            {
+                return false;
+            }
+
+            self.results_view.scroll_to(
+                0,
+                gtk::ListScrollFlags::FOCUS | gtk::ListScrollFlags::SELECT,
+                None,
+            );
+            true
+        }
+
+        #[template_callback]
+        fn completion_activated(&self, row: gtk::ListBoxRow) {
+            if let Ok(index) = usize::try_from(row.index()) {
+                self.complete(index);
+            }
+        }
+
+        #[template_callback(function)]
+        fn has_query(text: String) -> bool {
+            !Query::parse(&text).is_empty()
+        }
+
+        /// Save the search as a smart calendar, after asking for its name.
+        #[template_callback]
+        fn save_search(&self) {
+            let query = self.search_entry.text();
+            let entry = gtk::Entry::builder()
+                .text(query.as_str())
+                .activates_default(true)
+                .build();
+            let alert = adw::AlertDialog::builder()
+                .heading(gettext("Save Search"))
+                .body(gettext(
+                    "The results of the search are shown like a calendar and kept up to date",
+                ))
+                .extra_child(&entry)
+                .default_response("save")
+                .close_response("cancel")
+                .build();
+            alert.add_response("cancel", &gettext("_Cancel"));
+            alert.add_response("save", &gettext("_Save"));
+            alert.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+            alert.connect_response(Some("save"), move |_, _| {
+                let name = entry.text();
+                let name = if name.trim().is_empty() {
+                    query.as_str()
+                } else {
+                    name.trim()
+                };
+                CalendarManagerApplication::default()
+                    .saved_searches()
+                    .add(name, &query);
+            });
+            alert.present(Some(&*self.obj()));
+        }
+
+        #[template_callback]
+        fn custom_range_changed(&self) {
+            self.update_range_labels();
+            self.search_events();
         }
 
         #[template_callback]
-        fn event_item_bind(_factory: gtk::SignalListItemFactory, item: gtk::ListItem) {
+        fn is_custom_range(&self, selected: u32) -> bool {
+            selected == 3
+        }
+
+        #[template_callback]
+        fn event_item_bind(&self, item: gtk::ListItem) {
             let event: Event = item
                 .item()
                 .expect("item should be bound")
                 .downcast()
-                .expect("item should be a Calendar");
+                .expect("item should be an Event");
             let event_row = EventRow::new(&event);
+            event_row.set_highlight(self.search_entry.text().as_str());
+            if let Some(snippet) = self.snippets.borrow().get(&event.uri()) {
+                event_row.set_snippet(snippet.as_str());
+            }
             item.set_child(Some(&event_row));
         }
 
+        #[template_callback]
+        fn section_header_bind(&self, header: gtk::ListHeader) {
+            let title = self
+                .search_time
+                .borrow()
+                .as_ref()
+                .zip(header.item())
+                .and_then(|(now, item)| section(&item, now))
+                .map(|section| section.title())
+                .unwrap_or_default();
+            let label = gtk::Label::builder()
+                .label(title)
+                .xalign(0.)
+                .css_classes(["heading"])
+                .build();
+            header.set_child(Some(&label));
+        }
+
         #[template_callback]
         fn open_event_details(&self, item: u32) {
             let event = self
@@ -73,9 +401,172 @@ mod imp {
                 .unwrap()
                 .downcast::<Event>()
                 .unwrap();
-            dbg!("todo: show event details for {}", event.name());
+            EventDetailsDialog::new(&event).present(Some(&*self.obj()));
         }
     }
+
+    impl SearchDialog {
+        /// Show `results` in order of relevance if they were found in the index as `hits` and
+        /// sorted by relevance, or else in chronological order, grouped in sections.
+        ///
+        /// Results sorted by relevance have no date headers: sections must be contiguous, so
+        /// grouping them by date would undo the ranking, and headers for each change of date
+        /// would repeat the same sections. Each row shows its day instead.
+        fn show_results(&self, results: &[Event], hits: Option<&[Hit]>) {
+            let store = gio::ListStore::new::<Event>();
+            store.extend_from_slice(results);
+
+            let hits = hits.unwrap_or_default();
+            self.snippets.replace(
+                hits.iter()
+                    .map(|hit| (hit.uri.clone(), hit.snippet.clone()))
+                    .collect(),
+            );
+
+            let sorted = if !hits.is_empty() && self.sort_order.selected() == 0 {
+                let ranks: HashMap<String, usize> = hits
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, hit)| (hit.uri.clone(), rank))
+                    .collect();
+                let sorter = gtk::CustomSorter::new(move |a, b| {
+                    let rank = |event: &glib::Object| {
+                        event
+                            .downcast_ref::<Event>()
+                            .and_then(|event| ranks.get(&event.uri()).copied())
+                    };
+                    rank(a).cmp(&rank(b)).into()
+                });
+                self.results_view
+                    .set_header_factory(None::<&gtk::ListItemFactory>);
+                gtk::SortListModel::new(Some(store), Some(sorter))
+            } else {
+                let now = CalendarManagerApplication::default().time_zones().now();
+                let section_now = now.clone();
+                let section_sorter = gtk::CustomSorter::new(move |a, b| {
+                    section(a, &section_now)
+                        .cmp(&section(b, &section_now))
+                        .into()
+                });
+                let sorter = gtk::CustomSorter::new(|a, b| start(a).cmp(&start(b)).into());
+                let sorted = gtk::SortListModel::new(Some(store), Some(sorter));
+                sorted.set_section_sorter(Some(&section_sorter));
+
+                self.search_time.replace(Some(now));
+                self.results_view
+                    .set_header_factory(self.header_factory.get());
+                sorted
+            };
+
+            let selection = gtk::SingleSelection::builder()
+                .model(&sorted)
+                .autoselect(false)
+                .can_unselect(true)
+                .build();
+            self.results_view.set_model(Some(&selection));
+        }
+
+        /// Close the dialog and show the event at `uri` in the calendar views.
+        fn show_in_calendar(&self, uri: &str) {
+            let manager = CalendarManagerApplication::default().manager();
+            let Some(Resource::Event(event)) = manager.find_resource(uri) else {
+                return;
+            };
+            let Some(window) = self.obj().root().and_downcast::<CalendarManagerWindow>() else {
+                return;
+            };
+            self.obj().close();
+            window.show_event(&event);
+        }
+
+        /// Replace the calendar name being typed with the suggestion at `index`.
+        fn complete(&self, index: usize) {
+            let text = self.search_entry.text();
+            let Some(name) = self.completions.borrow().get(index).cloned() else {
+                return;
+            };
+            let Some((start, _)) = search::calendar_completion(&text) else {
+                return;
+            };
+
+            let text = format!("{}{} ", &text[..start], search::calendar_key(&name));
+            self.search_entry.set_text(&text);
+            self.search_entry.set_position(-1);
+            self.completion_popover.popdown();
+        }
+
+        fn filters(&self) -> Filters {
+            let calendar_uri = match self.calendar_filter.selected() {
+                0 | gtk::INVALID_LIST_POSITION => None,
+                position => self
+                    .calendars
+                    .borrow()
+                    .get(position as usize - 1)
+                    .map(Calendar::uri),
+            };
+            let range = match self.range_filter.selected() {
+                1 => DateRange::Past,
+                2 => DateRange::Upcoming,
+                3 => {
+                    // The custom range includes the whole last day, in the display time zone
+                    let time_zone = CalendarManagerApplication::default().time_zones().display();
+                    let bounds = |calendar: &gtk::Calendar| {
+                        civil_date(&calendar.date())
+                            .and_then(|date| time_zones::day_bounds(date, &time_zone))
+                    };
+                    match (bounds(&self.from_calendar), bounds(&self.to_calendar)) {
+                        (Some((from, _)), Some((_, to))) => DateRange::Between(from, to),
+                        _ => DateRange::Any,
+                    }
+                }
+                _ => DateRange::Any,
+            };
+            let kind = match self.kind_filter.selected() {
+                1 => Kind::AllDay,
+                2 => Kind::Timed,
+                _ => Kind::Any,
+            };
+
+            Filters {
+                calendar_uri,
+                range,
+                kind,
+            }
+        }
+
+        fn update_range_labels(&self) {
+            let label = |calendar: &gtk::Calendar| {
+                civil_date(&calendar.date())
+                    .map(format::date)
+                    .unwrap_or_default()
+            };
+            self.from_button.set_label(&label(&self.from_calendar));
+            self.to_button.set_label(&label(&self.to_calendar));
+        }
+    }
+
+    fn start(event: &glib::Object) -> Option<Timestamp> {
+        let timeframe = event.downcast_ref::<Event>()?.timeframe()?;
+        Some(timeframe.start().timestamp())
+    }
+
+    fn section(event: &glib::Object, now: &Zoned) -> Option<Section> {
+        let timeframe = event.downcast_ref::<Event>()?.timeframe()?;
+        Some(Section::new(
+            timeframe.start().timestamp(),
+            timeframe.end().timestamp(),
+            now,
+        ))
+    }
+
+    fn civil_date(date: &glib::DateTime) -> Option<civil::Date> {
+        civil::Date::new(
+            date.year().try_into().ok()?,
+            date.month().try_into().ok()?,
+            date.day_of_month().try_into().ok()?,
+        )
+        .ok()
+    }
 }
 
 glib::wrapper! {
@@ -87,6 +578,13 @@ impl SearchDialog {
     pub fn new() -> Self {
         glib::Object::new()
     }
+
+    /// Search for `text` right away, e.g. when the search comes from GNOME Shell.
+    pub fn with_text(text: &str) -> Self {
+        let obj = Self::new();
+        obj.imp().search_entry.set_text(text);
+        obj
+    }
 }
 
 impl Default for SearchDialog {
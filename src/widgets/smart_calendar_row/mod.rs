@@ -0,0 +1,127 @@
+use std::cell::{Cell, RefCell};
+
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::ngettext;
+use gtk::{
+    gdk::{Paintable, RGBA},
+    glib::{self, clone},
+};
+
+use crate::{
+    CalendarManagerApplication, saved_searches::SmartCalendar,
+    utils::get_circle_paintable_from_color,
+};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/smart_calendar_row.ui")]
+    #[properties(wrapper_type = super::SmartCalendarRow)]
+    pub struct SmartCalendarRow {
+        #[property(get, set, construct_only)]
+        pub smart_calendar: RefCell<Option<SmartCalendar>>,
+        /// Whether the row has a button to delete the saved search.
+        #[property(get, set, construct_only)]
+        pub deletable: Cell<bool>,
+        #[template_child]
+        pub visible_switch: TemplateChild<gtk::Switch>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SmartCalendarRow {
+        const NAME: &'static str = "SmartCalendarRow";
+        type Type = super::SmartCalendarRow;
+        type ParentType = adw::ActionRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            SmartCalendar::ensure_type();
+
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for SmartCalendarRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            if let Some(smart_calendar) = self.obj().smart_calendar() {
+                smart_calendar
+                    .bind_property("visible", &*self.visible_switch, "active")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+
+                // The subtitle follows the query and its results
+                let events = smart_calendar.events();
+                events.connect_items_changed(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, _, _, _| imp.update_subtitle()
+                ));
+                smart_calendar.connect_query_notify(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.update_subtitle()
+                ));
+                self.update_subtitle();
+            }
+        }
+    }
+    impl WidgetImpl for SmartCalendarRow {}
+    impl ListBoxRowImpl for SmartCalendarRow {}
+    impl PreferencesRowImpl for SmartCalendarRow {}
+    impl ActionRowImpl for SmartCalendarRow {}
+
+    impl SmartCalendarRow {
+        fn update_subtitle(&self) {
+            let Some(smart_calendar) = self.obj().smart_calendar() else {
+                return;
+            };
+            let n_events = smart_calendar.events().n_items();
+            let events =
+                ngettext("{} event", "{} events", n_events).replace("{}", &n_events.to_string());
+            self.obj()
+                .set_subtitle(&format!("{} · {events}", smart_calendar.query()));
+        }
+    }
+
+    #[gtk::template_callbacks]
+    impl SmartCalendarRow {
+        #[template_callback]
+        fn delete(&self) {
+            if let Some(smart_calendar) = self.obj().smart_calendar() {
+                CalendarManagerApplication::default()
+                    .saved_searches()
+                    .remove(&smart_calendar);
+            }
+        }
+
+        #[template_callback(function)]
+        fn get_color_image(color: String) -> Paintable {
+            let color = RGBA::parse(color).unwrap_or(RGBA::BLACK);
+            get_circle_paintable_from_color(&color, 16.)
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A saved search in the lists of calendars, with a switch to show its events.
+    pub struct SmartCalendarRow(ObjectSubclass<imp::SmartCalendarRow>)
+        @extends gtk::Widget, gtk::ListBoxRow, adw::PreferencesRow, adw::ActionRow;
+}
+
+impl SmartCalendarRow {
+    pub fn new(smart_calendar: &SmartCalendar, deletable: bool) -> Self {
+        glib::Object::builder()
+            .property("smart-calendar", smart_calendar)
+            .property("deletable", deletable)
+            .build()
+    }
+}
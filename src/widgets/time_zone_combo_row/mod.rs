@@ -0,0 +1,126 @@
+use std::{
+    cell::{Cell, OnceCell},
+    marker::PhantomData,
+};
+
+use adw::{prelude::*, subclass::prelude::*};
+use ccm::jiff::tz::TimeZone;
+use gettextrs::gettext;
+use gtk::glib;
+
+use crate::time_zones;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/time_zone_combo_row.ui")]
+    #[properties(wrapper_type = super::TimeZoneComboRow)]
+    pub struct TimeZoneComboRow {
+        /// The IANA name of the selected time zone, or an empty string for the system time zone.
+        #[property(get = Self::time_zone, set = Self::set_time_zone)]
+        time_zone: PhantomData<String>,
+        /// Whether the system time zone is one of the choices.
+        #[property(get, set, construct_only)]
+        system_choice: Cell<bool>,
+        names: OnceCell<gtk::StringList>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TimeZoneComboRow {
+        const NAME: &'static str = "TimeZoneComboRow";
+        type Type = super::TimeZoneComboRow;
+        type ParentType = adw::ComboRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TimeZoneComboRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            let names = gtk::StringList::new(&[]);
+            if self.system_choice.get() {
+                names.append("");
+            }
+            for name in time_zones::names() {
+                names.append(&name);
+            }
+            obj.set_model(Some(&names));
+            self.names
+                .set(names)
+                .expect("names should only be set once");
+
+            obj.connect_selected_notify(|row| row.notify_time_zone());
+        }
+    }
+
+    impl WidgetImpl for TimeZoneComboRow {}
+    impl ListBoxRowImpl for TimeZoneComboRow {}
+    impl PreferencesRowImpl for TimeZoneComboRow {}
+    impl ActionRowImpl for TimeZoneComboRow {}
+    impl ComboRowImpl for TimeZoneComboRow {}
+
+    #[gtk::template_callbacks]
+    impl TimeZoneComboRow {
+        #[template_callback(function)]
+        fn time_zone_label(name: String) -> String {
+            if name.is_empty() {
+                gettext("System ({})").replace("{}", &time_zones::name(&TimeZone::system()))
+            } else {
+                name.replace('_', " ")
+            }
+        }
+
+        fn names(&self) -> &gtk::StringList {
+            self.names.get().expect("names should be initialized")
+        }
+
+        fn time_zone(&self) -> String {
+            self.obj()
+                .selected_item()
+                .and_downcast::<gtk::StringObject>()
+                .map(|name| name.string().into())
+                .unwrap_or_default()
+        }
+
+        /// Select `name`, adding it to the choices if it is not one of them.
+        fn set_time_zone(&self, name: String) {
+            let names = self.names();
+            let position = (0..names.n_items())
+                .find(|&position| names.string(position).is_some_and(|item| item == name))
+                .unwrap_or_else(|| {
+                    names.append(&name);
+                    names.n_items() - 1
+                });
+            self.obj().set_selected(position);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A choice of IANA time zone, which can be searched.
+    pub struct TimeZoneComboRow(ObjectSubclass<imp::TimeZoneComboRow>)
+    @extends gtk::Widget, gtk::ListBoxRow, adw::PreferencesRow, adw::ActionRow, adw::ComboRow;
+}
+
+impl TimeZoneComboRow {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+}
+
+impl Default for TimeZoneComboRow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,107 @@
+use adw::{prelude::*, subclass::prelude::*};
+use ccm::jiff::ToSpan;
+use gtk::glib::{self, clone};
+
+use super::{connect_marked_days_changed, marked_days};
+use crate::{CalendarManagerApplication, format};
+
+/// How far ahead the agenda looks, in days.
+const DAYS_AHEAD: i32 = 365;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/agenda_view.ui")]
+    pub struct AgendaView {
+        #[template_child]
+        stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        days_list: TemplateChild<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AgendaView {
+        const NAME: &'static str = "AgendaView";
+        type Type = super::AgendaView;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for AgendaView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            connect_marked_days_changed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move || imp.update_days()
+            ));
+            CalendarManagerApplication::default().connect_current_day_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.update_days()
+            ));
+            self.update_days();
+        }
+    }
+
+    impl WidgetImpl for AgendaView {}
+    impl BinImpl for AgendaView {}
+
+    impl AgendaView {
+        /// List the coming holidays and birthdays, from today.
+        fn update_days(&self) {
+            self.days_list.remove_all();
+
+            let today = CalendarManagerApplication::default()
+                .time_zones()
+                .now()
+                .date();
+            let Ok(end) = today.checked_add(DAYS_AHEAD.days()) else {
+                return;
+            };
+            let marked_days = marked_days(today, end);
+
+            for marked_day in &marked_days {
+                let row = adw::ActionRow::builder()
+                    .title(&marked_day.title)
+                    .subtitle(format::relative_day(marked_day.date, today))
+                    .css_classes(["property"])
+                    .build();
+                self.days_list.append(&row);
+            }
+            self.stack
+                .set_visible_child_name(if marked_days.is_empty() {
+                    "empty"
+                } else {
+                    "days"
+                });
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The coming days and what happens on them, as a list.
+    pub struct AgendaView(ObjectSubclass<imp::AgendaView>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl AgendaView {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl Default for AgendaView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
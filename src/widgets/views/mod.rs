@@ -1,4 +1,133 @@
+mod agenda_view;
 mod month_view;
+mod time_rulers;
 mod year_view;
 
-pub use self::{month_view::*, year_view::*};
+use ccm::{
+    Event,
+    jiff::{ToSpan, civil},
+};
+use gtk::glib;
+
+pub use self::{agenda_view::AgendaView, month_view::*, time_rulers::TimeRulers, year_view::*};
+use crate::{CalendarManagerApplication, calendar_systems::OtherDate};
+
+/// A day marked in the views for what happens on it without being an event of a calendar, like a
+/// holiday or a birthday.
+#[derive(Debug, Clone)]
+struct MarkedDay {
+    date: civil::Date,
+    title: String,
+    /// The style class of its labels.
+    css_class: &'static str,
+}
+
+/// An event shown over its days in the views, e.g. once found in the search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightedEvent {
+    pub name: String,
+    /// The first day of the event.
+    pub start: civil::Date,
+    /// The day after the last day of the event.
+    pub end: civil::Date,
+}
+
+impl HighlightedEvent {
+    /// Returns `None` for events without a timeframe.
+    pub fn new(event: &Event) -> Option<Self> {
+        let timeframe = event.timeframe()?;
+        let time_zone = CalendarManagerApplication::default()
+            .time_zones()
+            .for_timeframe(&timeframe);
+        let start = timeframe.start().timestamp().to_zoned(time_zone.clone());
+        let end = timeframe.end().timestamp().to_zoned(time_zone);
+
+        let start = start.date();
+        // An event ending at midnight does not last on the next day
+        let end = if end.time() == civil::Time::midnight() {
+            end.date()
+        } else {
+            end.date().checked_add(1.day()).ok()?
+        };
+        let end = end.max(start.checked_add(1.day()).ok()?);
+        Some(Self {
+            name: event.name(),
+            start,
+            end,
+        })
+    }
+
+    pub fn contains(&self, date: civil::Date) -> bool {
+        self.start <= date && date < self.end
+    }
+}
+
+/// The holidays and birthdays shown from `start` to `end`, excluded, by date.
+fn marked_days(start: civil::Date, end: civil::Date) -> Vec<MarkedDay> {
+    let application = CalendarManagerApplication::default();
+    let holidays = application
+        .holidays()
+        .between(start, end)
+        .into_iter()
+        .map(|holiday| MarkedDay {
+            date: holiday.date,
+            title: holiday.name,
+            css_class: "holiday",
+        });
+    let birthdays = application
+        .birthdays()
+        .between(start, end)
+        .into_iter()
+        .map(|birthday| MarkedDay {
+            date: birthday.date,
+            title: birthday.title(),
+            css_class: "birthday",
+        });
+
+    let mut marked_days: Vec<MarkedDay> = holidays.chain(birthdays).collect();
+    marked_days.sort_by_key(|marked_day| marked_day.date);
+    marked_days
+}
+
+/// The date of `date` in the calendar system shown next to the Gregorian one, if any.
+fn other_date(date: civil::Date) -> Option<OtherDate> {
+    CalendarManagerApplication::default()
+        .calendar_system()
+        .date(date)
+}
+
+/// The markup of a day label with the number of `date`, and its day in the other calendar system
+/// in small text under it, bold on the first day of its months.
+fn day_markup(date: civil::Date) -> String {
+    let Some(other_date) = other_date(date) else {
+        return date.day().to_string();
+    };
+    let weight = if other_date.is_month_start() {
+        "bold"
+    } else {
+        "normal"
+    };
+    format!(
+        "{}\n<span size=\"x-small\" alpha=\"70%\" weight=\"{weight}\">{}</span>",
+        date.day(),
+        glib::markup_escape_text(&other_date.day()),
+    )
+}
+
+/// The name of the principal phase of the Moon on `date` and the name of its icon, if the phases
+/// are shown.
+fn moon_phase(date: civil::Date) -> Option<(String, &'static str)> {
+    let astronomy = CalendarManagerApplication::default().astronomy();
+    let phase = astronomy.moon_phase(date)?;
+    Some((phase.name(), astronomy.moon_icon_name(phase)))
+}
+
+/// Call `f` when the holidays or birthdays shown change.
+fn connect_marked_days_changed<F: Fn() + Clone + 'static>(f: F) {
+    let application = CalendarManagerApplication::default();
+    let on_holidays_changed = f.clone();
+    application
+        .holidays()
+        .connect_changed(move |_| on_holidays_changed());
+    application.birthdays().connect_changed(move |_| f());
+}
@@ -1,19 +1,21 @@
 use std::{
-    cell::{Cell, OnceCell},
+    cell::{Cell, OnceCell, RefCell},
     sync::{LazyLock, Mutex},
 };
 
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::jiff;
+use ccm::jiff::{self, ToSpan};
 use gtk::{
     Allocation,
-    glib::{self, subclass::Signal},
+    glib::{self, clone, subclass::Signal},
 };
 
 mod month_view_day_cell;
 mod month_view_week_row;
 
 use self::{month_view_day_cell::*, month_view_week_row::*};
+use super::HighlightedEvent;
+use crate::{CalendarManagerApplication, format};
 
 pub(crate) mod imp {
     use super::*;
@@ -31,6 +33,7 @@ pub(crate) mod imp {
         week: Cell<i8>,
         week_rows: OnceCell<Mutex<Vec<MonthViewWeekRow>>>,
         scroll_offset: Cell<f64>,
+        highlighted_event: RefCell<Option<HighlightedEvent>>,
     }
 
     #[glib::object_subclass]
@@ -54,7 +57,7 @@ pub(crate) mod imp {
         fn constructed(&self) {
             let obj = self.obj();
 
-            let now = jiff::Zoned::now();
+            let now = CalendarManagerApplication::default().time_zones().now();
             let current_year = now.year() as i32;
             let current_week = now.iso_week_date().week();
             obj.set_year(current_year);
@@ -110,6 +113,18 @@ pub(crate) mod imp {
                 week_rows.push(row);
             }
             self.week_rows.get_or_init(|| Mutex::new(week_rows));
+
+            obj.connect_year_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.update_rows()
+            ));
+            obj.connect_week_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.update_rows()
+            ));
+            self.update_rows();
         }
 
         fn signals() -> &'static [Signal] {
@@ -138,6 +153,31 @@ pub(crate) mod imp {
 
     #[gtk::template_callbacks]
     impl MonthView {
+        /// Show the weeks from the one of the view, which is the first visible row, with the
+        /// highlighted event.
+        pub(super) fn update_rows(&self) {
+            let obj = self.obj();
+            let Ok(week_date) = jiff::civil::ISOWeekDate::new(
+                obj.year() as i16,
+                obj.week(),
+                jiff::civil::Weekday::Monday,
+            ) else {
+                return;
+            };
+            // The first row is hidden above the view, for scrolling
+            let Ok(first_monday) = week_date.date().checked_sub(7.days()) else {
+                return;
+            };
+
+            let week_rows = self.week_rows.get().unwrap().lock().unwrap();
+            for (row, monday) in week_rows.iter().zip(first_monday.series(7.days())) {
+                let week_date = monday.iso_week_date();
+                row.set_year(week_date.year().into());
+                row.set_week(week_date.week());
+                row.set_highlighted_event(self.highlighted_event.borrow().clone());
+            }
+        }
+
         fn get_month(&self) -> i32 {
             let weekdate = jiff::civil::ISOWeekDate::new(
                 self.obj().year() as i16,
@@ -150,9 +190,8 @@ pub(crate) mod imp {
 
         #[template_callback]
         fn get_month_label(&self) -> String {
-            let year = self.obj().year();
-            let month = self.obj().month();
-            format!("{year} {month}")
+            let obj = self.obj();
+            format::month_year(obj.year() as i16, obj.month() as i8)
         }
 
         #[template_callback]
@@ -172,4 +211,11 @@ glib::wrapper! {
         @extends gtk::Widget;
 }
 
-impl MonthView {}
+impl MonthView {
+    /// Show `event` over its days, or stop showing the highlighted event.
+    pub fn highlight_event(&self, event: Option<HighlightedEvent>) {
+        let imp = self.imp();
+        imp.highlighted_event.replace(event);
+        imp.update_rows();
+    }
+}
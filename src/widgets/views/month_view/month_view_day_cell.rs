@@ -2,9 +2,10 @@ use std::cell::Cell;
 
 use adw::{prelude::*, subclass::prelude::*};
 use ccm::jiff;
-use gettextrs::gettext;
 use gtk::glib::{self, clone};
 
+use crate::format;
+
 pub(crate) mod imp {
     use super::*;
 
@@ -100,22 +101,7 @@ pub(crate) mod imp {
 
         #[template_callback]
         fn get_month_name(&self) -> String {
-            let month = self.obj().month();
-            match month {
-                1 => gettext("January"),
-                2 => gettext("February"),
-                3 => gettext("March"),
-                4 => gettext("April"),
-                5 => gettext("May"),
-                6 => gettext("June"),
-                7 => gettext("July"),
-                8 => gettext("August"),
-                9 => gettext("September"),
-                10 => gettext("October"),
-                11 => gettext("November"),
-                12 => gettext("December"),
-                _ => "".to_string(),
-            }
+            format::month_name(self.obj().month() as i8)
         }
     }
 }
@@ -1,7 +1,16 @@
-use std::{cell::Cell, sync::LazyLock};
+use std::{
+    cell::{Cell, RefCell},
+    sync::LazyLock,
+};
 
 use adw::{prelude::*, subclass::prelude::*};
-use gtk::glib::{self, closure_local, subclass::Signal};
+use ccm::jiff::{self, ToSpan};
+use gtk::glib::{self, clone, closure_local, subclass::Signal};
+
+use super::super::{
+    HighlightedEvent, connect_marked_days_changed, marked_days, moon_phase, other_date,
+};
+use crate::CalendarManagerApplication;
 
 // use super::MonthViewDayCell;
 
@@ -16,6 +25,7 @@ pub(crate) mod imp {
         year: Cell<i32>,
         #[property(get, set)]
         week: Cell<i8>,
+        pub highlighted_event: RefCell<Option<HighlightedEvent>>,
     }
 
     #[glib::object_subclass]
@@ -36,6 +46,31 @@ pub(crate) mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for MonthViewWeekRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.connect_year_notify(|obj| obj.imp().update_days());
+            obj.connect_week_notify(|obj| obj.imp().update_days());
+            CalendarManagerApplication::default().connect_calendar_system_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.update_days()
+            ));
+            connect_marked_days_changed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move || imp.update_days()
+            ));
+            CalendarManagerApplication::default()
+                .astronomy()
+                .connect_changed(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.update_days()
+                ));
+        }
+
         fn signals() -> &'static [Signal] {
             static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
                 vec![
@@ -54,7 +89,98 @@ pub(crate) mod imp {
     impl BoxImpl for MonthViewWeekRow {}
 
     #[gtk::template_callbacks]
-    impl MonthViewWeekRow {}
+    impl MonthViewWeekRow {
+        /// Show the days of the week, each with its phase of the Moon, holidays and birthdays, and
+        /// the highlighted event.
+        pub(super) fn update_days(&self) {
+            let obj = self.obj();
+            while let Some(child) = obj.first_child() {
+                obj.remove(&child);
+            }
+
+            let Ok(week_date) = jiff::civil::ISOWeekDate::new(
+                self.year.get() as i16,
+                self.week.get(),
+                jiff::civil::Weekday::Monday,
+            ) else {
+                return;
+            };
+            let monday = week_date.date();
+            let Ok(next_monday) = monday.checked_add(7.days()) else {
+                return;
+            };
+            let marked_days = marked_days(monday, next_monday);
+
+            for day in monday.series(1.day()).take(7) {
+                let day_box = gtk::Box::new(gtk::Orientation::Vertical, 3);
+                let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+                header.append(
+                    &gtk::Label::builder()
+                        .label(day.day().to_string())
+                        .xalign(0.)
+                        .hexpand(true)
+                        .css_classes(["numeric"])
+                        .build(),
+                );
+                if let Some((name, icon_name)) = moon_phase(day) {
+                    header.append(
+                        &gtk::Image::builder()
+                            .icon_name(icon_name)
+                            .tooltip_text(&name)
+                            .css_classes(["dim-label"])
+                            .build(),
+                    );
+                }
+                day_box.append(&header);
+                if let Some(other_date) = other_date(day) {
+                    let style = if other_date.is_month_start() {
+                        "caption-heading"
+                    } else {
+                        "caption"
+                    };
+                    day_box.append(
+                        &gtk::Label::builder()
+                            .label(other_date.day_label())
+                            .xalign(0.)
+                            .css_classes([style, "dim-label"])
+                            .build(),
+                    );
+                }
+                for marked_day in marked_days
+                    .iter()
+                    .filter(|marked_day| marked_day.date == day)
+                {
+                    day_box.append(
+                        &gtk::Label::builder()
+                            .label(&marked_day.title)
+                            .tooltip_text(&marked_day.title)
+                            .xalign(0.)
+                            .ellipsize(gtk::pango::EllipsizeMode::End)
+                            .css_classes(["caption", marked_day.css_class])
+                            .build(),
+                    );
+                }
+                if let Some(event) = self
+                    .highlighted_event
+                    .borrow()
+                    .as_ref()
+                    .filter(|event| event.contains(day))
+                {
+                    day_box.add_css_class("highlighted-day");
+                    day_box.append(
+                        &gtk::Label::builder()
+                            .label(&event.name)
+                            .tooltip_text(&event.name)
+                            .xalign(0.)
+                            .ellipsize(gtk::pango::EllipsizeMode::End)
+                            .css_classes(["caption", "highlighted-event"])
+                            .build(),
+                    );
+                }
+                obj.append(&day_box);
+            }
+        }
+    }
 }
 
 glib::wrapper! {
@@ -71,6 +197,16 @@ impl MonthViewWeekRow {
             .build()
     }
 
+    /// Show `event` over its days in the week, or stop showing the highlighted event.
+    pub fn set_highlighted_event(&self, event: Option<HighlightedEvent>) {
+        let imp = self.imp();
+        if *imp.highlighted_event.borrow() == event {
+            return;
+        }
+        imp.highlighted_event.replace(event);
+        imp.update_days();
+    }
+
     pub fn connect_month_clicked<F: Fn(&Self, i32, i32) + 'static>(
         &self,
         f: F,
@@ -0,0 +1,141 @@
+use adw::subclass::prelude::*;
+use ccm::jiff::{SignedDuration, Timestamp, civil, tz::TimeZone};
+use gtk::{
+    glib::{self, clone},
+    prelude::*,
+};
+
+mod time_ruler;
+
+use self::time_ruler::TimeRuler;
+use crate::{CalendarManagerApplication, astronomy::Daylight, time_zones};
+
+/// The height of an hour in the time-grid views.
+const HOUR_HEIGHT: i32 = 48;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/time_rulers.ui")]
+    pub struct TimeRulers {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TimeRulers {
+        const NAME: &'static str = "TimeRulers";
+        type Type = super::TimeRulers;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TimeRulers {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let time_zones = CalendarManagerApplication::default().time_zones();
+            time_zones.connect_display_name_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.update_rulers()
+            ));
+            time_zones.connect_secondary_names_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.update_rulers()
+            ));
+            CalendarManagerApplication::default()
+                .astronomy()
+                .connect_changed(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.update_rulers()
+                ));
+            self.update_rulers();
+        }
+    }
+
+    impl WidgetImpl for TimeRulers {}
+    impl BoxImpl for TimeRulers {}
+
+    impl TimeRulers {
+        /// Show the hours of today in the display time zone, then in the secondary time zones,
+        /// shaded by their daylight if it is shown.
+        fn update_rulers(&self) {
+            let obj = self.obj();
+            while let Some(child) = obj.first_child() {
+                obj.remove(&child);
+            }
+
+            let time_zones = CalendarManagerApplication::default().time_zones();
+            let display = time_zones.display();
+            let today = time_zones.now().date();
+            let hours = hours(today, &display);
+            let daylight = daylight(today, &hours);
+
+            // The secondary time zones come first, so the display one is next to the events
+            for (name, time_zone) in time_zones.secondary() {
+                let ruler = TimeRuler::new(&name, &time_zone, &hours);
+                ruler.shade(&daylight);
+                ruler.add_css_class("dim-label");
+                obj.append(&ruler);
+            }
+            let ruler = TimeRuler::new(&time_zones::name(&display), &display, &hours);
+            ruler.shade(&daylight);
+            obj.append(&ruler);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The hour rulers of the time-grid views, one for the display time zone and one for each
+    /// secondary time zone.
+    pub struct TimeRulers(ObjectSubclass<imp::TimeRulers>)
+    @extends gtk::Widget, gtk::Box;
+}
+
+impl TimeRulers {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl Default for TimeRulers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The start of each hour of `date` in `time_zone`, which are 23 or 25 on the days of daylight
+/// saving time transitions.
+fn hours(date: civil::Date, time_zone: &TimeZone) -> Vec<Timestamp> {
+    let Some((start, end)) = time_zones::day_bounds(date, time_zone) else {
+        return Vec::new();
+    };
+    std::iter::successors(Some(start), |hour| {
+        hour.checked_add(SignedDuration::from_hours(1)).ok()
+    })
+    .take_while(|hour| *hour < end)
+    .collect()
+}
+
+/// The daylight in the middle of each of `hours` of `date` at the location, if it is shown.
+fn daylight(date: civil::Date, hours: &[Timestamp]) -> Vec<Daylight> {
+    let Some(sun_times) = CalendarManagerApplication::default()
+        .astronomy()
+        .sun_times(date)
+    else {
+        return Vec::new();
+    };
+    hours
+        .iter()
+        .filter_map(|hour| hour.checked_add(SignedDuration::from_mins(30)).ok())
+        .map(|middle| sun_times.daylight(middle))
+        .collect()
+}
@@ -0,0 +1,84 @@
+use adw::subclass::prelude::*;
+use ccm::jiff::{Timestamp, tz::TimeZone};
+use gtk::{glib, prelude::*};
+
+use super::HOUR_HEIGHT;
+use crate::{astronomy::Daylight, format};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/gitlab/TitouanReal/CalendarManager/time_ruler.ui")]
+    pub struct TimeRuler {
+        #[template_child]
+        pub header: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub hours: TemplateChild<gtk::Box>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TimeRuler {
+        const NAME: &'static str = "TimeRuler";
+        type Type = super::TimeRuler;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TimeRuler {}
+    impl WidgetImpl for TimeRuler {}
+    impl BoxImpl for TimeRuler {}
+}
+
+glib::wrapper! {
+    /// The hours of a day in one time zone, under the abbreviation of the time zone.
+    pub struct TimeRuler(ObjectSubclass<imp::TimeRuler>)
+    @extends gtk::Widget, gtk::Box;
+}
+
+impl TimeRuler {
+    /// Label each of `hours` with its time in `time_zone`, named `name`.
+    pub fn new(name: &str, time_zone: &TimeZone, hours: &[Timestamp]) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+
+        if let Some(first_hour) = hours.first() {
+            let abbreviation = first_hour.to_zoned(time_zone.clone()).strftime("%Z");
+            imp.header.set_label(&abbreviation.to_string());
+        }
+        imp.header.set_tooltip_text(Some(&name.replace('_', " ")));
+
+        for hour in hours {
+            let label = gtk::Label::builder()
+                .label(format::time(hour.to_zoned(time_zone.clone()).time()))
+                .height_request(HOUR_HEIGHT)
+                .valign(gtk::Align::Start)
+                .xalign(1.)
+                .css_classes(["numeric"])
+                .build();
+            imp.hours.append(&label);
+        }
+
+        obj
+    }
+
+    /// Shade each hour by its `daylight`, in the order of the hours.
+    pub fn shade(&self, daylight: &[Daylight]) {
+        let hours =
+            std::iter::successors(self.imp().hours.first_child(), |hour| hour.next_sibling());
+        for (hour, daylight) in hours.zip(daylight) {
+            match daylight {
+                Daylight::Night => hour.add_css_class("night"),
+                Daylight::Twilight => hour.add_css_class("twilight"),
+                Daylight::Day => {}
+            }
+        }
+    }
+}
@@ -2,12 +2,18 @@ use std::{cell::Cell, cmp};
 
 use adw::{prelude::*, subclass::prelude::*};
 use ccm::jiff;
-use gettextrs::gettext;
 use gtk::glib::{self, clone};
 
-use crate::CalendarManagerApplication;
+use crate::{CalendarManagerApplication, format};
 
-use super::YearViewStyling;
+use super::{
+    super::{connect_marked_days_changed, day_markup, marked_days, moon_phase},
+    YearViewStyling,
+};
+
+/// The style classes showing the icons of the phases of the Moon on the day labels.
+const MOON_PHASE_CLASSES: [&str; 4] =
+    ["moon-new", "moon-right-half", "moon-full", "moon-left-half"];
 
 pub(crate) mod imp {
     use super::*;
@@ -71,6 +77,24 @@ pub(crate) mod imp {
                 obj.imp().update_styling();
             });
 
+            CalendarManagerApplication::default().connect_calendar_system_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.set_days_grid()
+            ));
+            connect_marked_days_changed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move || imp.update_marked_days()
+            ));
+            CalendarManagerApplication::default()
+                .astronomy()
+                .connect_changed(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.update_marked_days()
+                ));
+
             for cell in 0..42 {
                 let label = gtk::Label::new(None);
                 label.add_css_class("numeric");
@@ -247,14 +271,17 @@ pub(crate) mod imp {
                 label.set_label("");
             }
 
-            for (day_number, cell) in cells.by_ref().take(days_in_month).enumerate() {
+            for (date, cell) in first_day
+                .series(jiff::Span::new().days(1))
+                .zip(cells.by_ref().take(days_in_month))
+            {
                 let label = self
                     .days_grid
                     .child_at(cell % 7, cell / 7)
                     .expect("Grid should be initialized")
                     .downcast::<gtk::Label>()
                     .expect("Widget should be a label");
-                label.set_label(&format!("{}", day_number + 1));
+                label.set_markup(&day_markup(date));
             }
 
             for cell in cells {
@@ -266,6 +293,69 @@ pub(crate) mod imp {
                     .expect("Widget should be a label");
                 label.set_label("");
             }
+
+            self.update_marked_days();
+        }
+
+        /// Mark the holidays, birthdays and phases of the Moon of the month, with what happens on
+        /// them as tooltip.
+        fn update_marked_days(&self) {
+            for cell in 0..42 {
+                if let Some(label) = self.days_grid.child_at(cell % 7, cell / 7) {
+                    label.remove_css_class("holiday");
+                    label.remove_css_class("birthday");
+                    for class in MOON_PHASE_CLASSES {
+                        label.remove_css_class(class);
+                    }
+                    label.set_tooltip_text(None);
+                }
+            }
+
+            let Ok(first_day) =
+                jiff::civil::Date::new(self.year.get() as i16, self.month.get() as i8, 1)
+            else {
+                return;
+            };
+            let Ok(next_month) = first_day.checked_add(jiff::Span::new().months(1)) else {
+                return;
+            };
+            let weekday_of_first_day = first_day.weekday() as i32 - 1;
+
+            for marked_day in marked_days(first_day, next_month) {
+                let cell = weekday_of_first_day + marked_day.date.day() as i32 - 1;
+                let Some(label) = self.days_grid.child_at(cell % 7, cell / 7) else {
+                    continue;
+                };
+                let titles = match label.tooltip_text() {
+                    Some(titles) => format!("{titles}\n{}", marked_day.title),
+                    None => marked_day.title,
+                };
+                label.set_tooltip_text(Some(&titles));
+                // Holidays take precedence, as they are days off
+                if !label.has_css_class("holiday") {
+                    label.add_css_class(marked_day.css_class);
+                }
+            }
+
+            for (date, cell) in first_day
+                .series(jiff::Span::new().days(1))
+                .take_while(|date| *date < next_month)
+                .zip(weekday_of_first_day..)
+            {
+                let Some((name, icon_name)) = moon_phase(date) else {
+                    continue;
+                };
+                let Some(label) = self.days_grid.child_at(cell % 7, cell / 7) else {
+                    continue;
+                };
+                let titles = match label.tooltip_text() {
+                    Some(titles) => format!("{titles}\n{name}"),
+                    None => name,
+                };
+                label.set_tooltip_text(Some(&titles));
+                // The style classes show the icons, which labels cannot have
+                label.add_css_class(icon_name.trim_end_matches("-symbolic"));
+            }
         }
 
         fn update_styling(&self) {
@@ -350,22 +440,7 @@ pub(crate) mod imp {
 
         #[template_callback]
         fn get_month_name(&self) -> String {
-            let month = self.obj().month();
-            match month {
-                1 => gettext("January"),
-                2 => gettext("February"),
-                3 => gettext("March"),
-                4 => gettext("April"),
-                5 => gettext("May"),
-                6 => gettext("June"),
-                7 => gettext("July"),
-                8 => gettext("August"),
-                9 => gettext("September"),
-                10 => gettext("October"),
-                11 => gettext("November"),
-                12 => gettext("December"),
-                _ => "".to_string(),
-            }
+            format::month_name(self.obj().month() as i8)
         }
     }
 }
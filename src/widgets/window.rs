@@ -1,11 +1,19 @@
 use adw::{prelude::*, subclass::prelude::*};
-use ccm::jiff;
+use ccm::{Event, jiff};
 use gettextrs::gettext;
-use gtk::{gdk, gio, glib};
+use gtk::{
+    gdk, gio,
+    glib::{self, clone},
+};
+use tracing::debug;
 
-use crate::widgets::{
-    CalendarManagerDialog, CreateEventDialog, SearchDialog,
-    views::{MonthView, YearView},
+use crate::{
+    CalendarManagerApplication, format, spawn,
+    widgets::{
+        CalendarManagerDialog, CreateEventDialog, EventDetailsDialog, ImportDialog, SearchDialog,
+        SmartCalendarRow,
+        views::{AgendaView, HighlightedEvent, MonthView, TimeRulers, YearView},
+    },
 };
 
 pub(crate) mod imp {
@@ -22,6 +30,8 @@ pub(crate) mod imp {
         narrow_stack: TemplateChild<gtk::Stack>,
         #[template_child]
         month_view: TemplateChild<MonthView>,
+        #[template_child]
+        saved_searches_list: TemplateChild<gtk::ListBox>,
     }
 
     #[glib::object_subclass]
@@ -32,6 +42,8 @@ pub(crate) mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             YearView::ensure_type();
+            TimeRulers::ensure_type();
+            AgendaView::ensure_type();
 
             klass.bind_template();
             klass.bind_template_callbacks();
@@ -65,6 +77,16 @@ pub(crate) mod imp {
                 gdk::ModifierType::CONTROL_MASK,
                 "win.create-event",
             );
+
+            klass.install_action("win.import-events", None, |obj, _, _| {
+                obj.imp().import_events();
+            });
+
+            klass.add_binding_action(
+                gdk::Key::I,
+                gdk::ModifierType::CONTROL_MASK,
+                "win.import-events",
+            );
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -72,7 +94,32 @@ pub(crate) mod imp {
         }
     }
 
-    impl ObjectImpl for CalendarManagerWindow {}
+    impl ObjectImpl for CalendarManagerWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            ImportDialog::install_drop_target(&*self.obj(), None);
+
+            let saved_searches = CalendarManagerApplication::default()
+                .saved_searches()
+                .model();
+            self.saved_searches_list
+                .bind_model(Some(&saved_searches), |smart_calendar| {
+                    SmartCalendarRow::new(
+                        smart_calendar
+                            .downcast_ref()
+                            .expect("Model should contain only SmartCalendar objects"),
+                        false,
+                    )
+                    .upcast()
+                });
+            saved_searches
+                .bind_property("n-items", &*self.saved_searches_list, "visible")
+                .transform_to(|_, n_items: u32| Some(n_items > 0))
+                .sync_create()
+                .build();
+        }
+    }
     impl WidgetImpl for CalendarManagerWindow {}
     impl WindowImpl for CalendarManagerWindow {}
     impl ApplicationWindowImpl for CalendarManagerWindow {}
@@ -98,6 +145,33 @@ pub(crate) mod imp {
             dialog.present(Some(&*self.obj()));
         }
 
+        fn import_events(&self) {
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some(&gettext("iCalendar Files")));
+            filter.add_mime_type("text/calendar");
+            filter.add_suffix("ics");
+            let filters = gio::ListStore::new::<gtk::FileFilter>();
+            filters.append(&filter);
+
+            let file_dialog = gtk::FileDialog::builder()
+                .title(gettext("Import Events"))
+                .filters(&filters)
+                .default_filter(&filter)
+                .build();
+
+            spawn!(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    let window = imp.obj();
+                    match file_dialog.open_future(Some(&*window)).await {
+                        Ok(file) => ImportDialog::present_for_file(&file, None, &*window).await,
+                        Err(error) => debug!("No file to import: {error}"),
+                    }
+                }
+            ));
+        }
+
         #[template_callback(function)]
         fn get_year_label(year: i32) -> String {
             year.to_string()
@@ -107,31 +181,27 @@ pub(crate) mod imp {
         fn open_month_view(&self, year: i32, month: i32) {
             let date =
                 jiff::civil::Date::new(year as i16, month as i8, 1).expect("Date should be valid");
-            let week = date.iso_week_date().week();
-            self.month_view.set_year(year);
-            self.month_view.set_week(week);
+            self.open_week(date);
+        }
+
+        /// Open the month view on the week of `date`.
+        fn open_week(&self, date: jiff::civil::Date) {
+            let week_date = date.iso_week_date();
+            self.month_view.set_year(week_date.year().into());
+            self.month_view.set_week(week_date.week());
             self.wide_view_stack.set_visible_child_name("month");
             self.narrow_stack.set_visible_child_name("month");
         }
 
+        /// Open the month view on the first week of `event`, with the event shown over its days.
+        pub(super) fn highlight_event(&self, event: HighlightedEvent) {
+            self.open_week(event.start);
+            self.month_view.highlight_event(Some(event));
+        }
+
         #[template_callback(function)]
         fn get_year_month_label(year: i32, month: i32) -> String {
-            let month_name = match month {
-                1 => gettext("January"),
-                2 => gettext("February"),
-                3 => gettext("March"),
-                4 => gettext("April"),
-                5 => gettext("May"),
-                6 => gettext("June"),
-                7 => gettext("July"),
-                8 => gettext("August"),
-                9 => gettext("September"),
-                10 => gettext("October"),
-                11 => gettext("November"),
-                12 => gettext("December"),
-                _ => "".to_string(),
-            };
-            format!("{month_name} {year} ")
+            format::month_year(year as i16, month as i8)
         }
 
         #[template_callback]
@@ -182,4 +252,17 @@ impl CalendarManagerWindow {
             .property("application", application)
             .build()
     }
+
+    /// Show the week of `event` with the event highlighted, and its details on top.
+    pub fn show_event(&self, event: &Event) {
+        if let Some(highlighted_event) = HighlightedEvent::new(event) {
+            self.imp().highlight_event(highlighted_event);
+        }
+        EventDetailsDialog::new(event).present(Some(self));
+    }
+
+    /// Open the search with `text`.
+    pub fn search(&self, text: &str) {
+        SearchDialog::with_text(text).present(Some(self));
+    }
 }